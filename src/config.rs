@@ -2,7 +2,13 @@ use crate::Error;
 use once_cell::sync::Lazy;
 use poise::serenity_prelude::{ChannelId, GuildId, RoleId, UserId};
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, fs::File, io::Write};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::Write,
+    net::IpAddr,
+    sync::{Arc, RwLock},
+};
 
 pub static CURRENT_ENV: Lazy<&str> = Lazy::new(|| {
     let current_env = include_bytes!("../current-env");
@@ -13,6 +19,69 @@ pub static CURRENT_ENV: Lazy<&str> = Lazy::new(|| {
 /// Global config object
 pub static CONFIG: Lazy<Config> = Lazy::new(|| Config::load().expect("Failed to load config"));
 
+/// CDN scopes of the current environment
+///
+/// Unlike the rest of the config these can be reloaded at runtime using `reload_cdn_scopes`
+pub static CDN_SCOPES: Lazy<Arc<RwLock<HashMap<String, CdnScopeData>>>> =
+    Lazy::new(|| Arc::new(RwLock::new(CONFIG.panel.cdn_scopes.get())));
+
+/// Returns the current data of a CDN scope
+pub fn cdn_scope(scope: &str) -> Option<CdnScopeData> {
+    CDN_SCOPES
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(scope)
+        .cloned()
+}
+
+/// Rereads the CDN scopes of the current environment from config.yaml
+///
+/// The scopes are only returned if every scope path is an existing directory and the main scope is still present,
+/// swapping them into `CDN_SCOPES` is left to the caller
+pub fn read_cdn_scopes() -> Result<HashMap<String, CdnScopeData>, Error> {
+    let cfg: Config = serde_yaml::from_reader(File::open("config.yaml")?)?;
+
+    let cdn_scopes = cfg.panel.cdn_scopes.get();
+
+    if !cdn_scopes.contains_key(&CONFIG.panel.main_scope) {
+        return Err(format!(
+            "config.yaml: panel.main_scope '{}' is not in panel.cdn_scopes.{}",
+            CONFIG.panel.main_scope, *CURRENT_ENV
+        )
+        .into());
+    }
+
+    for (name, scope) in &cdn_scopes {
+        match std::fs::metadata(&scope.path) {
+            Ok(metadata) if metadata.is_dir() => {}
+            Ok(_) => {
+                return Err(format!(
+                    "config.yaml: panel.cdn_scopes.{}.{} path '{}' is not a directory",
+                    *CURRENT_ENV, name, scope.path
+                )
+                .into())
+            }
+            Err(e) => {
+                return Err(format!(
+                    "config.yaml: panel.cdn_scopes.{}.{} path '{}' could not be read: {}",
+                    *CURRENT_ENV, name, scope.path, e
+                )
+                .into())
+            }
+        }
+
+        if scope.exposed_url.is_empty() {
+            return Err(format!(
+                "config.yaml: panel.cdn_scopes.{}.{} must have an exposed_url",
+                *CURRENT_ENV, name
+            )
+            .into());
+        }
+    }
+
+    Ok(cdn_scopes)
+}
+
 #[derive(Serialize, Deserialize, Default)]
 pub struct Differs<T: Default + Clone> {
     staging: T,
@@ -111,6 +180,8 @@ pub struct PanelConfig {
     ///
     /// Currently the panel uses the following scopes:
     /// - ibl@main
+    ///
+    /// Use `crate::config::cdn_scope` to read these, they can be reloaded at runtime using `ReloadCdnScopes`
     pub cdn_scopes: Differs<HashMap<String, CdnScopeData>>,
 
     /// Main scope
@@ -120,6 +191,50 @@ pub struct PanelConfig {
     pub panel_scope: String,
     /// Panel response scope, used by frontend for validation. Should be static
     pub panel_response_scope: String,
+
+    /// Bearer token required to scrape `/metrics`, metrics are disabled if empty
+    #[serde(default)]
+    pub metrics_token: String,
+
+    /// Origins allowed to make cross-origin requests to the panel API
+    ///
+    /// Defaults to the frontend URL of the current environment if empty
+    #[serde(default)]
+    pub cors_origins: Vec<String>,
+
+    /// Discord webhook URL new panel sessions are announced to, disabled if empty
+    #[serde(default)]
+    pub session_audit_webhook: String,
+
+    /// Permissions that need a recent MFA check even within an active session
+    ///
+    /// Maps the permission (e.g. `partners.delete`) to the maximum age of the last MFA check in seconds
+    #[serde(default)]
+    pub step_up_mfa: HashMap<String, u64>,
+
+    /// Number of 30 second time steps a TOTP code may be off by and still be accepted
+    ///
+    /// Defaults to 0 (only the current code is accepted)
+    #[serde(default)]
+    pub totp_window: u64,
+
+    /// How long (in hours) a deleted partner can still be restored before it is permanently deleted
+    #[serde(default = "default_partner_restore_window")]
+    pub partner_restore_window: u64,
+
+    /// Header the reverse proxy puts the client IP in (e.g. `X-Forwarded-For`)
+    ///
+    /// Only read on requests coming from `trusted_proxies`, the peer address is used otherwise
+    #[serde(default)]
+    pub client_ip_header: String,
+
+    /// Addresses of the reverse proxies trusted to set `client_ip_header`
+    #[serde(default)]
+    pub trusted_proxies: Vec<IpAddr>,
+}
+
+fn default_partner_restore_window() -> u64 {
+    24 * 7
 }
 
 #[derive(Serialize, Deserialize, Clone, Default)]
@@ -130,11 +245,62 @@ pub struct CdnScopeData {
     pub exposed_url: String,
 }
 
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RpcRatelimit {
+    /// Maximum number of requests allowed within the window
+    pub max_requests: i64,
+    /// Length of the ratelimit window in minutes
+    pub window_minutes: i32,
+}
+
+impl Default for RpcRatelimit {
+    fn default() -> Self {
+        Self {
+            max_requests: 5,
+            window_minutes: 7,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DatabasePoolConfig {
+    /// Maximum number of connections to the database
+    pub max_connections: u32,
+    /// Minimum number of idle connections kept open
+    pub min_connections: u32,
+}
+
+impl Default for DatabasePoolConfig {
+    fn default() -> Self {
+        Self {
+            max_connections: 6,
+            min_connections: 0,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct RpcConfig {
+    /// Default ratelimit for RPC methods, applied per user and method
+    pub ratelimit: RpcRatelimit,
+    /// Per-method ratelimit overrides keyed by method name (e.g. `Claim`)
+    pub method_ratelimits: HashMap<String, RpcRatelimit>,
+}
+
+fn default_server_host() -> String {
+    String::from("127.0.0.1")
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct Config {
+    /// Host the panel API binds to, use `0.0.0.0` to listen on all interfaces (e.g. in containers)
+    #[serde(default = "default_server_host")]
+    pub server_host: String,
     pub server_port: Differs<u16>,
     pub prefix: Differs<String>,
     pub database_url: String,
+    #[serde(default)]
+    pub database_pool: DatabasePoolConfig,
     pub token: Differs<String>,
     pub servers: Servers,
     pub roles: Roles,
@@ -150,11 +316,14 @@ pub struct Config {
     pub protected_bots: Vec<UserId>,
     pub panel: PanelConfig,
     pub japi_key: String,
+    #[serde(default)]
+    pub rpc: RpcConfig,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
+            server_host: default_server_host(),
             server_port: Differs {
                 staging: 3011,
                 prod: 3010,
@@ -164,6 +333,7 @@ impl Default for Config {
                 prod: String::from("ibs!"),
             },
             database_url: String::from(""),
+            database_pool: DatabasePoolConfig::default(),
             token: Differs {
                 staging: String::from(""),
                 prod: String::from(""),
@@ -187,6 +357,7 @@ impl Default for Config {
             ],
             panel: PanelConfig::default(),
             japi_key: String::from(""),
+            rpc: RpcConfig::default(),
         }
     }
 }
@@ -212,6 +383,9 @@ impl Config {
                 // Parse config.yaml
                 let cfg: Config = serde_yaml::from_reader(file)?;
 
+                // Catch missing/invalid values now instead of deep inside a handler
+                cfg.validate()?;
+
                 // Return config
                 Ok(cfg)
             }
@@ -224,4 +398,69 @@ impl Config {
             }
         }
     }
+
+    /// Validates the config, returning an error describing the first invalid value found
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.database_url.is_empty() {
+            return Err("config.yaml: database_url must be set".into());
+        }
+
+        if self.database_pool.max_connections == 0
+            || self.database_pool.min_connections > self.database_pool.max_connections
+        {
+            return Err("config.yaml: database_pool.max_connections must be at least 1 and not less than database_pool.min_connections".into());
+        }
+
+        if self.token.get().is_empty() {
+            return Err(format!("config.yaml: token.{} must be set", *CURRENT_ENV).into());
+        }
+
+        if self.panel.client_id.is_empty() || self.panel.client_secret.is_empty() {
+            return Err("config.yaml: panel.client_id and panel.client_secret must be set".into());
+        }
+
+        if self.panel.redirect_url.is_empty() {
+            return Err("config.yaml: panel.redirect_url must have at least one URL".into());
+        }
+
+        for url in &self.panel.redirect_url {
+            if let Err(e) = reqwest::Url::parse(url) {
+                return Err(format!(
+                    "config.yaml: panel.redirect_url '{}' is invalid: {}",
+                    url, e
+                )
+                .into());
+            }
+        }
+
+        for origin in &self.panel.cors_origins {
+            if let Err(e) = reqwest::Url::parse(origin) {
+                return Err(format!(
+                    "config.yaml: panel.cors_origins '{}' is invalid: {}",
+                    origin, e
+                )
+                .into());
+            }
+        }
+
+        let cdn_scopes = self.panel.cdn_scopes.get();
+
+        let Some(main_scope) = cdn_scopes.get(&self.panel.main_scope) else {
+            return Err(format!(
+                "config.yaml: panel.main_scope '{}' is not in panel.cdn_scopes.{}",
+                self.panel.main_scope, *CURRENT_ENV
+            )
+            .into());
+        };
+
+        if main_scope.path.is_empty() || main_scope.exposed_url.is_empty() {
+            return Err(format!(
+                "config.yaml: panel.cdn_scopes.{}.{} must have a path and exposed_url",
+                *CURRENT_ENV, self.panel.main_scope
+            )
+            .into());
+        }
+
+        Ok(())
+    }
 }
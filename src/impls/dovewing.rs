@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 use serenity::all::UserId;
 use sqlx::PgPool;
+use std::collections::HashMap;
 use ts_rs::TS;
 use utoipa::ToSchema;
 
@@ -210,3 +211,96 @@ pub async fn get_platform_user(
         Ok(user)
     }
 }
+
+/// Returns many platform users at once, looking up all users not in the cache with a single query
+///
+/// Users that could not be found (or have invalid IDs) are left out of the returned map
+pub async fn get_platform_users(
+    pool: &PgPool,
+    src: DovewingSource,
+    user_ids: &[String],
+) -> Result<HashMap<String, PlatformUser>, crate::Error> {
+    let mut users = HashMap::new();
+    let mut uncached = Vec::new();
+
+    for user_id in user_ids {
+        if users.contains_key(user_id) {
+            continue;
+        }
+
+        match src.cached_user(user_id) {
+            Ok(Some(user)) => {
+                users.insert(user_id.clone(), user);
+            }
+            Ok(None) => uncached.push(user_id.clone()),
+            Err(_) => continue,
+        }
+    }
+
+    if uncached.is_empty() {
+        return Ok(users);
+    }
+
+    let recs = sqlx::query!(
+        "SELECT id, username, display_name, avatar, bot, last_updated FROM internal_user_cache__discord WHERE id = ANY($1)",
+        &uncached
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut stale = Vec::new();
+
+    for rec in recs {
+        if rec.last_updated.timestamp() + src.user_expiry_time() < chrono::Utc::now().timestamp() {
+            stale.push(rec.id.clone());
+        }
+
+        users.insert(
+            rec.id.clone(),
+            PlatformUser {
+                id: rec.id,
+                username: rec.username,
+                display_name: rec.display_name,
+                bot: rec.bot,
+                avatar: rec.avatar,
+                status: "offline".to_string(),
+            },
+        );
+    }
+
+    // Stale users are refreshed in the background, same as get_platform_user
+    for user_id in stale {
+        let pool = pool.clone();
+        let src = src.clone();
+
+        tokio::spawn(async move {
+            let user = src.http_user(&user_id).await?;
+
+            sqlx::query!(
+                "UPDATE internal_user_cache__discord SET username = $1, display_name = $2, avatar = $3, bot = $4, last_updated = NOW() WHERE id = $5",
+                user.username,
+                user.display_name,
+                user.avatar,
+                user.bot,
+                user_id,
+            )
+            .execute(&pool)
+            .await?;
+
+            Ok::<(), crate::Error>(())
+        });
+    }
+
+    // Anything left has never been seen before and must be fetched over HTTP
+    for user_id in uncached {
+        if users.contains_key(&user_id) {
+            continue;
+        }
+
+        if let Ok(user) = get_platform_user(pool, src.clone(), &user_id).await {
+            users.insert(user_id, user);
+        }
+    }
+
+    Ok(users)
+}
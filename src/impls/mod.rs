@@ -1,4 +1,5 @@
 pub mod dovewing;
 pub mod link;
+pub mod queue_events;
 pub mod target_types;
 pub mod utils;
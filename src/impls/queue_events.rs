@@ -0,0 +1,40 @@
+use log::error;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use ts_rs::TS;
+use utoipa::ToSchema;
+
+/// Postgres channel that bot queue events are sent on, also used by the `arcadia_bot_submitted` trigger
+pub const QUEUE_EVENTS_CHANNEL: &str = "arcadia_bot_queue";
+
+/// A change to a bot in the queue
+#[derive(Serialize, Deserialize, ToSchema, TS, Clone)]
+#[ts(export, export_to = ".generated/QueueEvent.ts")]
+pub struct QueueEvent {
+    /// The bot that changed
+    pub bot_id: String,
+    /// The new type of the bot (pending, approved, denied etc.)
+    pub r#type: String,
+    /// Who the bot is now claimed by, if anyone
+    pub claimed_by: Option<String>,
+}
+
+/// Notifies listeners (such as the panel queue websocket) of a change to the bot queue
+///
+/// Queue events are best-effort, so errors are only logged
+pub async fn notify(pool: &PgPool, event: &QueueEvent) {
+    let payload = match serde_json::to_string(event) {
+        Ok(payload) => payload,
+        Err(e) => {
+            error!("Failed to serialize queue event: {}", e);
+            return;
+        }
+    };
+
+    if let Err(e) = sqlx::query!("SELECT pg_notify($1, $2)", QUEUE_EVENTS_CHANNEL, payload)
+        .execute(pool)
+        .await
+    {
+        error!("Failed to send queue event: {}", e);
+    }
+}
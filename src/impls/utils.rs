@@ -277,3 +277,18 @@ pub async fn get_user_perms(
             .collect::<Vec<Permission>>(),
     })
 }
+
+/// Returns the path of a partners image on the CDN, or in the CDN trash if `trash` is set
+///
+/// Images of deleted partners are kept in the trash until the partner is permanently deleted
+pub fn partner_image_path(id: &str, trash: bool) -> Result<String, crate::Error> {
+    let Some(cdn_path) = crate::config::cdn_scope(&crate::config::CONFIG.panel.main_scope) else {
+        return Err("Main scope not found".into());
+    };
+
+    if trash {
+        Ok(format!("{}/trash/partners/{}.webp", cdn_path.path, id))
+    } else {
+        Ok(format!("{}/avatars/partners/{}.webp", cdn_path.path, id))
+    }
+}
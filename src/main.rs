@@ -3,7 +3,9 @@ use poise::serenity_prelude::{self as serenity, CreateEmbed, CreateMessage, Full
 use sqlx::postgres::PgPoolOptions;
 
 use botox::cache::CacheHttpImpl;
+use metrics_exporter_prometheus::PrometheusBuilder;
 use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
 
 mod botowners;
 mod checks;
@@ -115,17 +117,6 @@ async fn event_listener(
             .execute(&user_data.pool)
             .await?;
 
-            // Start RPC
-            let cache_http_papi = CacheHttpImpl {
-                http: ctx.serenity_context.http.clone(),
-                cache: ctx.serenity_context.cache.clone(),
-            };
-
-            tokio::task::spawn(panelapi::server::init_panelapi(
-                user_data.pool.clone(),
-                cache_http_papi,
-            ));
-
             if *crate::config::CURRENT_ENV != "staging" {
                 tokio::task::spawn(botox::taskman::start_all_tasks(
                     crate::tasks::tasks(),
@@ -205,8 +196,6 @@ async fn event_listener(
 
 #[tokio::main]
 async fn main() {
-    const MAX_CONNECTIONS: u32 = 6; // max connections to the database, we don't need too many here
-
     std::env::set_var("RUST_LOG", "bot=info, moka=error");
 
     env_logger::init();
@@ -223,13 +212,26 @@ async fn main() {
     let client_builder =
         serenity::ClientBuilder::new_with_http(http, serenity::GatewayIntents::all());
 
-    let data = Data {
-        pool: PgPoolOptions::new()
-            .max_connections(MAX_CONNECTIONS)
-            .connect(&config::CONFIG.database_url)
-            .await
-            .expect("Could not initialize connection"),
-    };
+    let pool_config = &config::CONFIG.database_pool;
+
+    info!(
+        "Database pool: max_connections={}, min_connections={}",
+        pool_config.max_connections, pool_config.min_connections
+    );
+
+    let pool = PgPoolOptions::new()
+        .max_connections(pool_config.max_connections)
+        .min_connections(pool_config.min_connections)
+        .connect(&config::CONFIG.database_url)
+        .await
+        .expect("Could not initialize connection");
+
+    // Installed here as the global recorder can only be set once per process
+    let metrics = PrometheusBuilder::new()
+        .install_recorder()
+        .expect("Failed to install metrics recorder");
+
+    let data = Data { pool: pool.clone() };
 
     let prefix = crate::config::CONFIG.prefix.get();
 
@@ -245,6 +247,9 @@ async fn main() {
             help::help(),
             explain::explainme(),
             staff::staff(),
+            staff::onboardstatus(),
+            staff::actionlogs(),
+            staff::resetapitoken(),
             testing::invite_db(),
             testing::invite(),
             testing::claim(),
@@ -293,7 +298,61 @@ async fn main() {
         .await
         .expect("Error creating client");
 
-    if let Err(why) = client.start().await {
-        error!("Client error: {:?}", why);
+    // Start the panel API once here rather than on Ready, which fires again on every reconnect
+    let shutdown = CancellationToken::new();
+
+    let panelapi = tokio::task::spawn(panelapi::server::init_panelapi(
+        pool,
+        CacheHttpImpl {
+            http: client.http.clone(),
+            cache: client.cache.clone(),
+        },
+        metrics,
+        shutdown.clone(),
+    ));
+
+    tokio::select! {
+        res = client.start() => {
+            if let Err(why) = res {
+                error!("Client error: {:?}", why);
+            }
+        }
+        _ = shutdown_signal() => {
+            client.shard_manager.shutdown_all().await;
+        }
+    }
+
+    // Let the panel API finish in-flight requests before exiting
+    shutdown.cancel();
+
+    if let Err(e) = panelapi.await {
+        error!("Panel API task failed: {}", e);
+    }
+
+    info!("Shutting down");
+}
+
+/// Resolves once SIGINT or SIGTERM is received
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Failed to install SIGINT handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => info!("Received SIGINT, shutting down"),
+        _ = terminate => info!("Received SIGTERM, shutting down"),
     }
 }
@@ -1,25 +1,96 @@
 use crate::panelapi::auth::{check_auth, check_auth_insecure};
-use crate::panelapi::core::{AppState, Error};
+use crate::panelapi::core::{AppState, ClientInfo, Error, PanelError};
 use crate::panelapi::types::auth::{AuthorizeAction, MfaLogin, MfaLoginSecret};
 use crate::panelapi::types::webcore::StartAuth;
 use axum::response::Response;
 use axum::{http::StatusCode, response::IntoResponse, Json};
+use log::{debug, error, warn};
 use rand::Rng;
 use serde::Deserialize;
-use serenity::all::User;
+use serenity::all::{
+    CreateEmbed, CreateEmbedFooter, CreateMessage, ExecuteWebhook, User, UserId, Webhook,
+};
+use sha2::{Digest, Sha256};
 use std::time::Duration;
 
 const AUTH_VERSION: u16 = 5;
 
+/// Length of a TOTP time step in seconds
+const TOTP_STEP: u64 = 30;
+
+/// Verifies a TOTP code of a staff member
+///
+/// Codes outside of the configured drift window are rejected, as are codes whose time step is not newer
+/// than the last accepted one so a code cannot be replayed while it is still valid
+async fn verify_otp(
+    conn: &mut sqlx::PgConnection,
+    user_id: &str,
+    otp: &str,
+    secret: &[u8],
+) -> Result<bool, Error> {
+    let window = crate::config::CONFIG.panel.totp_window;
+
+    let (result, discrepancy) = thotp::verify_totp(otp, secret, window).map_err(Error::new)?;
+
+    if !result {
+        return Ok(false);
+    }
+
+    let discrepancy = discrepancy as i64;
+
+    debug!(
+        "Accepted TOTP code of {} that was {} time step(s) off",
+        user_id, discrepancy
+    );
+
+    if discrepancy.unsigned_abs() > window {
+        return Ok(false);
+    }
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_err(Error::new)?
+        .as_secs();
+
+    let counter = (now / TOTP_STEP) as i64 + discrepancy;
+
+    let last_counter = sqlx::query!(
+        "SELECT mfa_last_counter FROM staff_members WHERE user_id = $1 FOR UPDATE",
+        user_id
+    )
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(Error::new)?
+    .mfa_last_counter;
+
+    if last_counter.is_some_and(|c| counter <= c) {
+        warn!("Rejected reused TOTP code of {}", user_id);
+        return Ok(false);
+    }
+
+    sqlx::query!(
+        "UPDATE staff_members SET mfa_last_counter = $1 WHERE user_id = $2",
+        counter,
+        user_id
+    )
+    .execute(&mut *conn)
+    .await
+    .map_err(Error::new)?;
+
+    Ok(true)
+}
+
 pub async fn authorize(
     state: &AppState,
     // Authorize protocol version, should be `AUTH_VERSION`
     version: u16,
     // Action to take
     action: AuthorizeAction,
+    // Client making the request, used for rate limiting logins and session info
+    client: &ClientInfo,
 ) -> Result<Response, Error> {
     if version != AUTH_VERSION {
-        return Ok((StatusCode::BAD_REQUEST, "Invalid version".to_string()).into_response());
+        return Err(PanelError::BadRequest("Invalid version".to_string()).into());
     }
 
     match action {
@@ -27,8 +98,10 @@ pub async fn authorize(
             scope,
             redirect_url,
         } => {
+            state.check_login_ratelimit(client.ip).await?;
+
             if scope != crate::config::CONFIG.panel.panel_scope {
-                return Ok((StatusCode::BAD_REQUEST, "Invalid scope".to_string()).into_response());
+                return Err(PanelError::BadRequest("Invalid scope".to_string()).into());
             }
 
             Ok(
@@ -49,130 +122,26 @@ pub async fn authorize(
             )
         }
         AuthorizeAction::CreateSession { code, redirect_url } => {
-            if !crate::config::CONFIG
-                .panel
-                .redirect_url
-                .contains(&redirect_url)
-            {
-                return Ok(
-                    (StatusCode::BAD_REQUEST, "Invalid redirect url".to_string()).into_response(),
-                );
-            }
-
-            let client = reqwest::Client::builder()
-                .timeout(Duration::from_secs(10))
-                .build()
-                .map_err(Error::new)?;
-
-            let resp = client
-                .post("https://discord.com/api/oauth2/token")
-                .header("Content-Type", "application/x-www-form-urlencoded")
-                .header("User-Agent", "DiscordBot (arcadia v1.0)")
-                .form(&[
-                    ("client_id", crate::config::CONFIG.panel.client_id.as_str()),
-                    (
-                        "client_secret",
-                        crate::config::CONFIG.panel.client_secret.as_str(),
-                    ),
-                    ("grant_type", "authorization_code"),
-                    ("code", code.as_str()),
-                    ("redirect_uri", redirect_url.as_str()),
-                    ("scope", "identify"),
-                ])
-                .send()
-                .await
-                .map_err(Error::new)?
-                .error_for_status()
-                .map_err(Error::new)?;
-
-            #[derive(Deserialize)]
-            struct Oauth2 {
-                access_token: String,
-            }
-
-            let oauth2 = resp.json::<Oauth2>().await.map_err(Error::new)?;
-
-            let user_resp = client
-                .get("https://discord.com/api/users/@me")
-                .header(
-                    "Authorization",
-                    "Bearer ".to_string() + oauth2.access_token.as_str(),
-                )
-                .header("Content-Type", "application/x-www-form-urlencoded")
-                .header("User-Agent", "DiscordBot (arcadia v1.0)")
-                .send()
-                .await
-                .map_err(Error::new)?
-                .error_for_status()
-                .map_err(Error::new)?;
+            state.check_login_ratelimit(client.ip).await?;
 
-            let user = user_resp.json::<User>().await.map_err(Error::new)?;
+            let resp = create_session(state, code, redirect_url, client.user_agent.clone()).await;
 
-            let rec = sqlx::query!(
-                "SELECT positions FROM staff_members WHERE user_id = $1",
-                user.id.to_string()
-            )
-            .fetch_optional(&state.pool)
-            .await
-            .map_err(Error::new)?;
-
-            let Some(positions) = rec else {
-                return Ok((
-                    StatusCode::FORBIDDEN,
-                    "You are not a staff member [not in db]".to_string(),
-                )
-                    .into_response());
-            };
+            state
+                .record_login_attempt(client.ip, matches!(&resp, Ok(r) if r.status().is_success()))
+                .await;
 
-            if positions.positions.is_empty() {
-                return Ok((
-                    StatusCode::FORBIDDEN,
-                    "You are not a staff member [no positions]".to_string(),
-                )
-                    .into_response());
-            }
-
-            let mut tx = state.pool.begin().await.map_err(Error::new)?;
-
-            sqlx::query!(
-                "DELETE FROM staffpanel__authchain WHERE user_id = $1",
-                user.id.to_string()
-            )
-            .execute(&mut *tx)
-            .await
-            .map_err(Error::new)?;
-
-            // Create a random number between 4196 and 6000 for the token
-            let tlength = rand::thread_rng().gen_range(4196..6000);
-
-            let token = botox::crypto::gen_random(tlength as usize);
-
-            sqlx::query!(
-                "INSERT INTO staffpanel__authchain (user_id, token, popplio_token, state) VALUES ($1, $2, $3, $4)",
-                user.id.to_string(),
-                token,
-                botox::crypto::gen_random(2048),
-                "pending"
-            )
-            .execute(&mut *tx)
-            .await
-            .map_err(Error::new)?;
-
-            tx.commit().await.map_err(Error::new)?;
-
-            Ok((StatusCode::OK, token).into_response())
+            resp
         }
         AuthorizeAction::CheckMfaState { login_token } => {
             let auth_data = check_auth_insecure(&state.pool, &login_token)
                 .await
-                .map_err(Error::new)?;
+                .map_err(|e| PanelError::NotAuthorized(e.to_string()))?;
 
             if auth_data.state != "pending" && auth_data.state != "active" {
-                return Err(Error {
-                    status: StatusCode::BAD_REQUEST,
-                    message: "This endpoint can only be used by pending and active sessions"
-                        .to_string(),
-                });
+                return Err(PanelError::BadRequest(
+                    "This endpoint can only be used by pending and active sessions".to_string(),
+                )
+                .into());
             }
 
             let mut tx = state.pool.begin().await.map_err(Error::new)?;
@@ -191,10 +160,9 @@ pub async fn authorize(
             })?;
 
             if mfa.is_none() {
-                return Err(Error {
-                    status: StatusCode::BAD_REQUEST,
-                    message: "You are not a staff member".to_string(),
-                });
+                return Err(
+                    PanelError::BadRequest("You are not a staff member".to_string()).into(),
+                );
             }
 
             let mfa = mfa.unwrap();
@@ -260,7 +228,7 @@ pub async fn authorize(
         AuthorizeAction::ResetMfaTotp { login_token, otp } => {
             let auth_data = check_auth(&state.pool, &login_token)
                 .await
-                .map_err(Error::new)?;
+                .map_err(|e| PanelError::NotAuthorized(e.to_string()))?;
 
             let mut tx = state.pool.begin().await.map_err(Error::new)?;
 
@@ -274,26 +242,18 @@ pub async fn authorize(
             .mfa_secret;
 
             if secret.is_none() {
-                return Err(Error {
-                    status: StatusCode::BAD_REQUEST,
-                    message: "mfaNotSetup".to_string(),
-                });
+                return Err(PanelError::BadRequest("mfaNotSetup".to_string()).into());
             }
 
             let secret = thotp::encoding::decode(&secret.unwrap(), data_encoding::BASE32)
                 .map_err(Error::new)?;
 
-            let (result, _discrepancy) = thotp::verify_totp(&otp, &secret, 0).unwrap();
-
-            if !result {
-                return Err(Error {
-                    status: StatusCode::BAD_REQUEST,
-                    message: "Invalid OTP Entered".to_string(),
-                });
+            if !verify_otp(&mut tx, &auth_data.user_id, &otp, &secret).await? {
+                return Err(PanelError::BadRequest("Invalid OTP Entered".to_string()).into());
             }
 
             sqlx::query!(
-                "UPDATE staff_members SET mfa_secret = NULL, mfa_verified = FALSE WHERE user_id = $1",
+                "UPDATE staff_members SET mfa_secret = NULL, mfa_verified = FALSE, mfa_last_counter = NULL WHERE user_id = $1",
                 auth_data.user_id
             )
             .execute(&mut *tx)
@@ -311,19 +271,17 @@ pub async fn authorize(
 
             tx.commit().await.map_err(Error::new)?;
 
+            state.perms_cache.invalidate(&login_token).await;
+
             Ok((StatusCode::NO_CONTENT, "").into_response())
         }
         AuthorizeAction::ActivateSession { login_token, otp } => {
             let auth_data = check_auth_insecure(&state.pool, &login_token)
                 .await
-                .map_err(Error::new)?;
+                .map_err(|e| PanelError::NotAuthorized(e.to_string()))?;
 
-            if auth_data.state != "pending" {
-                return Err(Error {
-                    status: StatusCode::BAD_REQUEST,
-                    message: "sessionAlreadyActive".to_string(),
-                });
-            }
+            // Active sessions can re-run this to refresh their last MFA check (step-up re-authentication)
+            let step_up = auth_data.state == "active";
 
             let mut tx = state.pool.begin().await.map_err(Error::new)?;
 
@@ -336,32 +294,29 @@ pub async fn authorize(
             .map_err(Error::new)?;
 
             if mfa.mfa_secret.is_none() {
-                return Err(Error {
-                    status: StatusCode::BAD_REQUEST,
-                    message: "mfaNotSetup".to_string(),
-                });
+                return Err(PanelError::BadRequest("mfaNotSetup".to_string()).into());
             }
 
             let secret = thotp::encoding::decode(&mfa.mfa_secret.unwrap(), data_encoding::BASE32)
                 .map_err(Error::new)?;
 
-            let (result, _discrepancy) = thotp::verify_totp(&otp, &secret, 0).unwrap();
-
-            if !result {
-                return Err(Error {
-                    status: StatusCode::BAD_REQUEST,
-                    message: "Invalid OTP entered".to_string(),
-                });
+            if !verify_otp(&mut tx, &auth_data.user_id, &otp, &secret).await? {
+                return Err(PanelError::BadRequest("Invalid OTP entered".to_string()).into());
             }
 
             sqlx::query!(
-                "UPDATE staffpanel__authchain SET state = 'active' WHERE token = $1",
+                "UPDATE staffpanel__authchain SET state = 'active', last_mfa_at = NOW() WHERE token = $1",
                 login_token
             )
             .execute(&mut *tx)
             .await
             .map_err(Error::new)?;
 
+            if step_up {
+                tx.commit().await.map_err(Error::new)?;
+                return Ok((StatusCode::NO_CONTENT, "").into_response());
+            }
+
             sqlx::query!(
                 "UPDATE staff_members SET mfa_verified = TRUE WHERE user_id = $1",
                 auth_data.user_id
@@ -372,9 +327,18 @@ pub async fn authorize(
 
             tx.commit().await.map_err(Error::new)?;
 
+            // Best effort, a failed notification should never fail the login
+            tokio::spawn(notify_session_activated(
+                state.cache_http.clone(),
+                auth_data.user_id,
+                session_fingerprint(&login_token),
+            ));
+
             Ok((StatusCode::NO_CONTENT, "").into_response())
         }
         AuthorizeAction::Logout { login_token } => {
+            state.perms_cache.invalidate(&login_token).await;
+
             // Just delete the auth, no point in even erroring if it doesn't exist
             let row = sqlx::query!(
                 "DELETE FROM staffpanel__authchain WHERE token = $1",
@@ -388,3 +352,187 @@ pub async fn authorize(
         }
     }
 }
+
+/// Exchanges a Discord OAuth2 code for a (pending) panel session
+async fn create_session(
+    state: &AppState,
+    code: String,
+    redirect_url: String,
+    user_agent: Option<String>,
+) -> Result<Response, Error> {
+    if !crate::config::CONFIG
+        .panel
+        .redirect_url
+        .contains(&redirect_url)
+    {
+        return Err(PanelError::BadRequest("Invalid redirect url".to_string()).into());
+    }
+
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .map_err(Error::new)?;
+
+    let resp = client
+        .post("https://discord.com/api/oauth2/token")
+        .header("Content-Type", "application/x-www-form-urlencoded")
+        .header("User-Agent", "DiscordBot (arcadia v1.0)")
+        .form(&[
+            ("client_id", crate::config::CONFIG.panel.client_id.as_str()),
+            (
+                "client_secret",
+                crate::config::CONFIG.panel.client_secret.as_str(),
+            ),
+            ("grant_type", "authorization_code"),
+            ("code", code.as_str()),
+            ("redirect_uri", redirect_url.as_str()),
+            ("scope", "identify"),
+        ])
+        .send()
+        .await
+        .map_err(Error::new)?
+        .error_for_status()
+        .map_err(Error::new)?;
+
+    #[derive(Deserialize)]
+    struct Oauth2 {
+        access_token: String,
+    }
+
+    let oauth2 = resp.json::<Oauth2>().await.map_err(Error::new)?;
+
+    let user_resp = client
+        .get("https://discord.com/api/users/@me")
+        .header(
+            "Authorization",
+            "Bearer ".to_string() + oauth2.access_token.as_str(),
+        )
+        .header("Content-Type", "application/x-www-form-urlencoded")
+        .header("User-Agent", "DiscordBot (arcadia v1.0)")
+        .send()
+        .await
+        .map_err(Error::new)?
+        .error_for_status()
+        .map_err(Error::new)?;
+
+    let user = user_resp.json::<User>().await.map_err(Error::new)?;
+
+    let rec = sqlx::query!(
+        "SELECT positions FROM staff_members WHERE user_id = $1",
+        user.id.to_string()
+    )
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(Error::new)?;
+
+    let Some(positions) = rec else {
+        return Err(PanelError::PermissionMissing(
+            "You are not a staff member [not in db]".to_string(),
+        )
+        .into());
+    };
+
+    if positions.positions.is_empty() {
+        return Err(PanelError::PermissionMissing(
+            "You are not a staff member [no positions]".to_string(),
+        )
+        .into());
+    }
+
+    let mut tx = state.pool.begin().await.map_err(Error::new)?;
+
+    sqlx::query!(
+        "DELETE FROM staffpanel__authchain WHERE user_id = $1",
+        user.id.to_string()
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(Error::new)?;
+
+    // Create a random number between 4196 and 6000 for the token
+    let tlength = rand::thread_rng().gen_range(4196..6000);
+
+    let token = botox::crypto::gen_random(tlength as usize);
+
+    sqlx::query!(
+        "INSERT INTO staffpanel__authchain (user_id, token, popplio_token, state, user_agent) VALUES ($1, $2, $3, $4, $5)",
+        user.id.to_string(),
+        token,
+        botox::crypto::gen_random(2048),
+        "pending",
+        user_agent
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(Error::new)?;
+
+    tx.commit().await.map_err(Error::new)?;
+
+    Ok((StatusCode::OK, token).into_response())
+}
+
+/// Returns a short, non-reversible fingerprint of a login token that is safe to show to users
+fn session_fingerprint(login_token: &str) -> String {
+    let hash = Sha256::digest(login_token.as_bytes());
+    data_encoding::HEXLOWER.encode(&hash[..6])
+}
+
+/// DMs the user and posts to the session audit webhook (if set) that a new session was activated
+async fn notify_session_activated(
+    cache_http: botox::cache::CacheHttpImpl,
+    user_id: String,
+    fingerprint: String,
+) {
+    let embed = CreateEmbed::new()
+        .title("New Panel Session")
+        .description(format!(
+            "A new staff panel session was activated for <@{}>",
+            user_id
+        ))
+        .field("Session", format!("`{}`", fingerprint), true)
+        .field(
+            "Activated At",
+            format!("<t:{}:F>", chrono::Utc::now().timestamp()),
+            true,
+        )
+        .footer(CreateEmbedFooter::new(
+            "If this wasn't you, revoke your sessions and contact an admin right now!",
+        ))
+        .color(0xff0000);
+
+    match user_id.parse::<UserId>() {
+        Ok(uid) => {
+            if let Err(e) = uid
+                .direct_message(&cache_http.http, CreateMessage::new().embed(embed.clone()))
+                .await
+            {
+                warn!("Failed to DM {} about new panel session: {}", user_id, e);
+            }
+        }
+        Err(e) => warn!("Invalid user id {}: {}", user_id, e),
+    }
+
+    if crate::config::CONFIG.panel.session_audit_webhook.is_empty() {
+        return;
+    }
+
+    let webhook = match Webhook::from_url(
+        &cache_http.http,
+        &crate::config::CONFIG.panel.session_audit_webhook,
+    )
+    .await
+    {
+        Ok(webhook) => webhook,
+        Err(e) => {
+            error!("Failed to get session audit webhook: {}", e);
+            return;
+        }
+    };
+
+    if let Err(e) = webhook
+        .execute(&cache_http.http, false, ExecuteWebhook::new().embed(embed))
+        .await
+    {
+        error!("Failed to post to session audit webhook: {}", e);
+    }
+}
@@ -1,5 +1,5 @@
 use crate::panelapi::auth::check_auth;
-use crate::panelapi::core::{AppState, Error};
+use crate::panelapi::core::{AppState, Error, PanelError};
 use crate::panelapi::types::analytics::BaseAnalytics;
 use axum::{
     http::StatusCode,
@@ -10,7 +10,11 @@ use axum::{
 pub async fn base_analytics(state: &AppState, login_token: String) -> Result<Response, Error> {
     check_auth(&state.pool, &login_token)
         .await
-        .map_err(Error::new)?;
+        .map_err(|e| PanelError::NotAuthorized(e.to_string()))?;
+
+    if let Some(analytics) = state.base_analytics.get(&()) {
+        return Ok((StatusCode::OK, Json(analytics)).into_response());
+    }
 
     let bot_counts = sqlx::query!("SELECT type, COUNT(*) FROM bots GROUP BY type")
         .fetch_all(&state.pool)
@@ -37,33 +41,67 @@ pub async fn base_analytics(state: &AppState, login_token: String) -> Result<Res
         .await
         .map_err(Error::new)?;
 
-    Ok((
-        StatusCode::OK,
-        Json(BaseAnalytics {
-            bot_counts: bot_counts
-                .iter()
-                .map(|b| (b.r#type.clone(), b.count.unwrap_or_default()))
-                .collect(),
-            server_counts: server_counts
-                .iter()
-                .map(|s| (s.r#type.clone(), s.count.unwrap_or_default()))
-                .collect(),
-            ticket_counts: ticket_counts
-                .iter()
-                .map(|t| {
-                    (
-                        if t.open {
-                            "open".to_string()
-                        } else {
-                            "closed".to_string()
-                        },
-                        t.count.unwrap_or_default(),
-                    )
-                })
-                .collect(),
-            total_users: total_users.count.unwrap_or_default(),
-            changelogs_count: total_changelogs.count.unwrap_or_default(),
-        }),
+    let reviews_last_day = sqlx::query!(
+        "SELECT method, COUNT(*) FROM rpc_logs WHERE (method = 'Approve' OR method = 'Deny') AND state = 'success' AND created_at > NOW() - INTERVAL '1 day' GROUP BY method"
     )
-        .into_response())
+    .fetch_all(&state.pool)
+    .await
+    .map_err(Error::new)?;
+
+    let total_staff = sqlx::query!("SELECT COUNT(*) FROM staff_members")
+        .fetch_one(&state.pool)
+        .await
+        .map_err(Error::new)?;
+
+    let total_partners = sqlx::query!("SELECT COUNT(*) FROM partners WHERE deleted_at IS NULL")
+        .fetch_one(&state.pool)
+        .await
+        .map_err(Error::new)?;
+
+    let reviews_of = |method: &str| {
+        reviews_last_day
+            .iter()
+            .find(|r| r.method == method)
+            .and_then(|r| r.count)
+            .unwrap_or_default()
+    };
+
+    let analytics = BaseAnalytics {
+        bot_counts: bot_counts
+            .iter()
+            .map(|b| (b.r#type.clone(), b.count.unwrap_or_default()))
+            .collect(),
+        server_counts: server_counts
+            .iter()
+            .map(|s| (s.r#type.clone(), s.count.unwrap_or_default()))
+            .collect(),
+        ticket_counts: ticket_counts
+            .iter()
+            .map(|t| {
+                (
+                    if t.open {
+                        "open".to_string()
+                    } else {
+                        "closed".to_string()
+                    },
+                    t.count.unwrap_or_default(),
+                )
+            })
+            .collect(),
+        total_users: total_users.count.unwrap_or_default(),
+        changelogs_count: total_changelogs.count.unwrap_or_default(),
+        queue_size: bot_counts
+            .iter()
+            .filter(|b| b.r#type == "pending" || b.r#type == "claimed")
+            .map(|b| b.count.unwrap_or_default())
+            .sum(),
+        approved_last_day: reviews_of("Approve"),
+        denied_last_day: reviews_of("Deny"),
+        total_staff: total_staff.count.unwrap_or_default(),
+        total_partners: total_partners.count.unwrap_or_default(),
+    };
+
+    state.base_analytics.insert((), analytics.clone()).await;
+
+    Ok((StatusCode::OK, Json(analytics)).into_response())
 }
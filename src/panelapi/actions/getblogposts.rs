@@ -0,0 +1,96 @@
+use crate::panelapi::auth::check_auth;
+use crate::panelapi::core::{AppState, Error, PanelError};
+use crate::panelapi::types::blog::BlogPost;
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use kittycat::perms;
+
+/// Permission needed to see draft blog posts
+const VIEW_DRAFTS_PERM: &str = "blog.update_entry";
+
+pub async fn get_blog_posts(state: &AppState, login_token: String) -> Result<Response, Error> {
+    let auth_data = check_auth(&state.pool, &login_token)
+        .await
+        .map_err(|e| PanelError::NotAuthorized(e.to_string()))?;
+
+    let user_perms = state
+        .get_user_perms(&login_token, &auth_data.user_id)
+        .await?;
+
+    let show_drafts = perms::has_perm(&user_perms, &VIEW_DRAFTS_PERM.into());
+
+    let rows = sqlx::query!(
+        "SELECT itag, slug, title, description, user_id, content, created_at, draft, published_at, tags FROM blogs WHERE $1 OR NOT draft ORDER BY created_at DESC",
+        show_drafts
+    )
+    .fetch_all(&state.pool)
+    .await
+    .map_err(Error::new)?;
+
+    let posts = rows
+        .into_iter()
+        .map(|row| BlogPost {
+            itag: row.itag.hyphenated().to_string(),
+            slug: row.slug,
+            title: row.title,
+            description: row.description,
+            user_id: row.user_id,
+            tags: row.tags,
+            content: row.content,
+            created_at: row.created_at,
+            draft: row.draft,
+            published_at: row.published_at,
+        })
+        .collect::<Vec<_>>();
+
+    Ok((StatusCode::OK, Json(posts)).into_response())
+}
+
+pub async fn get_blog_post(
+    state: &AppState,
+    login_token: String,
+    slug: String,
+) -> Result<Response, Error> {
+    let auth_data = check_auth(&state.pool, &login_token)
+        .await
+        .map_err(|e| PanelError::NotAuthorized(e.to_string()))?;
+
+    let user_perms = state
+        .get_user_perms(&login_token, &auth_data.user_id)
+        .await?;
+
+    let row = sqlx::query!(
+        "SELECT itag, slug, title, description, user_id, content, created_at, draft, published_at, tags FROM blogs WHERE slug = $1",
+        slug
+    )
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(Error::new)?;
+
+    // Drafts are reported as not found so their existence isn't leaked
+    let Some(row) =
+        row.filter(|r| !r.draft || perms::has_perm(&user_perms, &VIEW_DRAFTS_PERM.into()))
+    else {
+        return Err(PanelError::NotFound("Blog post not found".to_string()).into());
+    };
+
+    Ok((
+        StatusCode::OK,
+        Json(BlogPost {
+            itag: row.itag.hyphenated().to_string(),
+            slug: row.slug,
+            title: row.title,
+            description: row.description,
+            user_id: row.user_id,
+            tags: row.tags,
+            content: row.content,
+            created_at: row.created_at,
+            draft: row.draft,
+            published_at: row.published_at,
+        }),
+    )
+        .into_response())
+}
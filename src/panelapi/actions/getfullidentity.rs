@@ -0,0 +1,52 @@
+use crate::impls::dovewing::{get_platform_user, DovewingSource};
+use crate::panelapi::auth::check_auth;
+use crate::panelapi::core::{AppState, Error, PanelError};
+use crate::panelapi::types::auth::FullIdentity;
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+
+pub async fn get_full_identity(state: &AppState, login_token: String) -> Result<Response, Error> {
+    let auth_data = check_auth(&state.pool, &login_token)
+        .await
+        .map_err(|e| PanelError::NotAuthorized(e.to_string()))?;
+
+    let user = get_platform_user(
+        &state.pool,
+        DovewingSource::Discord(state.cache_http.clone()),
+        &auth_data.user_id,
+    )
+    .await
+    .map_err(Error::new)?;
+
+    let perms = state
+        .get_user_perms(&login_token, &auth_data.user_id)
+        .await?
+        .iter()
+        .map(|p| p.to_string())
+        .collect();
+
+    let positions = sqlx::query!(
+        "SELECT name FROM staff_positions WHERE id = ANY((SELECT positions FROM staff_members WHERE user_id = $1)) ORDER BY index ASC",
+        auth_data.user_id
+    )
+    .fetch_all(&state.pool)
+    .await
+    .map_err(Error::new)?
+    .into_iter()
+    .map(|p| p.name)
+    .collect();
+
+    Ok((
+        StatusCode::OK,
+        Json(FullIdentity {
+            auth_data,
+            user,
+            perms,
+            positions,
+        }),
+    )
+        .into_response())
+}
@@ -0,0 +1,76 @@
+use crate::panelapi::core::{AppState, Error, PanelError};
+use crate::panelapi::types::partners::{PartnerAnalytics, PartnerAnalyticsDay};
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+
+/// Default number of days of analytics returned
+const DEFAULT_DAYS: i32 = 30;
+
+/// Maximum number of days of analytics that can be returned
+const MAX_DAYS: i32 = 365;
+
+pub async fn get_partner_analytics(
+    state: &AppState,
+    login_token: String,
+    partner_id: String,
+    days: Option<i32>,
+) -> Result<Response, Error> {
+    state
+        .require_perm(&login_token, "partner_analytics.view")
+        .await?;
+
+    let days = days.unwrap_or(DEFAULT_DAYS).clamp(1, MAX_DAYS);
+
+    if sqlx::query!("SELECT id FROM partners WHERE id = $1", partner_id)
+        .fetch_optional(&state.pool)
+        .await
+        .map_err(Error::new)?
+        .is_none()
+    {
+        return Err(PanelError::NotFound("Partner not found".to_string()).into());
+    }
+
+    let totals = sqlx::query!(
+        "SELECT COALESCE(SUM(clicks), 0)::bigint AS \"clicks!\", COALESCE(SUM(impressions), 0)::bigint AS \"impressions!\"
+        FROM partner_analytics WHERE partner_id = $1 AND day > CURRENT_DATE - $2::integer",
+        partner_id,
+        days
+    )
+    .fetch_one(&state.pool)
+    .await
+    .map_err(Error::new)?;
+
+    let rows = sqlx::query!(
+        "SELECT day, clicks, impressions FROM partner_analytics
+        WHERE partner_id = $1 AND day > CURRENT_DATE - $2::integer
+        ORDER BY day ASC",
+        partner_id,
+        days
+    )
+    .fetch_all(&state.pool)
+    .await
+    .map_err(Error::new)?;
+
+    let daily = rows
+        .into_iter()
+        .map(|row| PartnerAnalyticsDay {
+            day: row.day,
+            clicks: row.clicks,
+            impressions: row.impressions,
+        })
+        .collect::<Vec<_>>();
+
+    Ok((
+        StatusCode::OK,
+        Json(PartnerAnalytics {
+            partner_id,
+            total_clicks: totals.clicks,
+            total_impressions: totals.impressions,
+            daily,
+        }),
+    )
+        .into_response())
+}
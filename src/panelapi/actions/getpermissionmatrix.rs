@@ -0,0 +1,108 @@
+use std::str::FromStr;
+
+use crate::panelapi::auth::check_auth;
+use crate::panelapi::core::{AppState, Error, PanelError};
+use crate::panelapi::types::permissions::{PermissionMatrix, PermissionMatrixEntry};
+use crate::rpc::core::RPCMethod;
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use kittycat::perms::{self, Permission};
+use strum::VariantNames;
+
+/// Permissions checked by panel queries, must be kept in sync with the handlers
+const PANEL_PERMISSIONS: &[&str] = &[
+    "blog.create_entry",
+    "blog.update_entry",
+    "blog.delete_entry",
+    "bot_whitelist.create",
+    "bot_whitelist.update",
+    "bot_whitelist.delete",
+    "core_constants.infernoplex_url",
+    "core_constants.popplio_url",
+    "core_constants.servers",
+    "partner_analytics.view",
+    "partners.create",
+    "partners.update",
+    "partners.delete",
+    "partner_types.create",
+    "partner_types.update",
+    "partner_types.delete",
+    "rpc_logs.retry_any",
+    "rpc_logs.view",
+    "shop_items.create",
+    "shop_items.update",
+    "shop_items.delete",
+    "shop_item_benefits.create",
+    "shop_item_benefits.update",
+    "shop_item_benefits.delete",
+    "shop_coupons.list",
+    "shop_coupons.create",
+    "shop_coupons.update",
+    "shop_coupons.delete",
+    "shop_holds.create",
+    "shop_holds.update",
+    "shop_holds.delete",
+    "staff_disciplinaries.view",
+    "staff_disciplinary_types.create",
+    "staff_disciplinary_types.update",
+    "staff_disciplinary_types.delete",
+    "staff_members.edit",
+    "staff_positions.create",
+    "staff_positions.edit",
+    "staff_positions.delete",
+    "staff_positions.set_index",
+    "staff_positions.swap_index",
+    "teams.list",
+    "vote_credit_tiers.create",
+    "vote_credit_tiers.update",
+    "vote_credit_tiers.delete",
+];
+
+fn check_perm(user_perms: &[Permission], perm: &str) -> PermissionMatrixEntry {
+    let required = perm.into();
+    let allowed = perms::has_perm(user_perms, &required);
+
+    PermissionMatrixEntry {
+        perm: perm.to_string(),
+        allowed,
+        granted_by: if allowed {
+            user_perms
+                .iter()
+                .find(|p| perms::has_perm(&[(*p).clone()], &required))
+                .map(|p| p.to_string())
+        } else {
+            None
+        },
+    }
+}
+
+pub async fn get_permission_matrix(
+    state: &AppState,
+    login_token: String,
+) -> Result<Response, Error> {
+    let auth_data = check_auth(&state.pool, &login_token)
+        .await
+        .map_err(|e| PanelError::NotAuthorized(e.to_string()))?;
+
+    let user_perms = state
+        .get_user_perms(&login_token, &auth_data.user_id)
+        .await?;
+
+    let panel = PANEL_PERMISSIONS
+        .iter()
+        .map(|perm| check_perm(&user_perms, perm))
+        .collect();
+
+    let mut rpc = Vec::new();
+
+    for method in RPCMethod::VARIANTS {
+        // Same check as RPCMethod::handle
+        let variant = RPCMethod::from_str(method).map_err(Error::new)?;
+        rpc.push(check_perm(&user_perms, &format!("rpc.{}", variant)));
+    }
+
+    Ok((StatusCode::OK, Json(PermissionMatrix { panel, rpc })).into_response())
+}
@@ -0,0 +1,90 @@
+use crate::panelapi::core::{AppState, Error};
+use crate::panelapi::types::rpclogs::{RPCLogEntries, RPCLogEntry, RPCLogFilters};
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+
+/// Default number of entries returned per page
+const DEFAULT_LIMIT: i64 = 50;
+
+/// Maximum number of entries that can be returned per page
+const MAX_LIMIT: i64 = 500;
+
+pub async fn get_rpc_log_entries(
+    state: &AppState,
+    login_token: String,
+    filters: RPCLogFilters,
+    limit: Option<i64>,
+    offset: Option<i64>,
+) -> Result<Response, Error> {
+    state.require_perm(&login_token, "rpc_logs.view").await?;
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let offset = offset.unwrap_or_default().max(0);
+    let target_type = filters.target_type.map(|t| t.to_string());
+
+    let total = sqlx::query!(
+        "SELECT COUNT(*) FROM rpc_logs
+        WHERE ($1::text IS NULL OR user_id = $1)
+        AND ($2::text IS NULL OR method = $2)
+        AND ($3::text IS NULL OR target_type = $3)
+        AND ($4::timestamptz IS NULL OR created_at > $4)
+        AND ($5::timestamptz IS NULL OR created_at < $5)",
+        filters.user_id,
+        filters.method,
+        target_type,
+        filters.after,
+        filters.before
+    )
+    .fetch_one(&state.pool)
+    .await
+    .map_err(Error::new)?
+    .count
+    .unwrap_or_default();
+
+    let entries = sqlx::query!(
+        "SELECT id, user_id, method, target_type, data, state, error, created_at FROM rpc_logs
+        WHERE ($1::text IS NULL OR user_id = $1)
+        AND ($2::text IS NULL OR method = $2)
+        AND ($3::text IS NULL OR target_type = $3)
+        AND ($4::timestamptz IS NULL OR created_at > $4)
+        AND ($5::timestamptz IS NULL OR created_at < $5)
+        ORDER BY created_at DESC LIMIT $6 OFFSET $7",
+        filters.user_id,
+        filters.method,
+        target_type,
+        filters.after,
+        filters.before,
+        limit,
+        offset
+    )
+    .fetch_all(&state.pool)
+    .await
+    .map_err(Error::new)?;
+
+    let mut rpc_log = vec![];
+
+    for entry in entries {
+        rpc_log.push(RPCLogEntry {
+            id: entry.id.to_string(),
+            user_id: entry.user_id,
+            method: entry.method,
+            target_type: entry.target_type,
+            data: entry.data,
+            state: entry.state,
+            error: entry.error,
+            created_at: entry.created_at,
+        });
+    }
+
+    Ok((
+        StatusCode::OK,
+        Json(RPCLogEntries {
+            entries: rpc_log,
+            total,
+        }),
+    )
+        .into_response())
+}
@@ -0,0 +1,34 @@
+use crate::panelapi::auth::get_staff_disciplinaries;
+use crate::panelapi::core::{AppState, Error};
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+
+pub async fn get_staff_disciplinaries_of(
+    state: &AppState,
+    login_token: String,
+    user_id: String,
+    active_only: bool,
+    after: Option<chrono::DateTime<chrono::Utc>>,
+    before: Option<chrono::DateTime<chrono::Utc>>,
+) -> Result<Response, Error> {
+    state
+        .require_perm(&login_token, "staff_disciplinaries.view")
+        .await?;
+
+    let mut disciplinaries = get_staff_disciplinaries(&state.pool, &user_id, active_only)
+        .await
+        .map_err(Error::new)?;
+
+    disciplinaries.retain(|d| {
+        after.map_or(true, |after| d.created_at > after)
+            && before.map_or(true, |before| d.created_at < before)
+    });
+
+    // Newest first
+    disciplinaries.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+
+    Ok((StatusCode::OK, Json(disciplinaries)).into_response())
+}
@@ -1,11 +1,12 @@
-use crate::impls::dovewing::{get_platform_user, DovewingSource};
+use crate::impls::dovewing::{get_platform_user, get_platform_users, DovewingSource};
 use crate::panelapi::auth::check_auth;
-use crate::panelapi::core::{AppState, Error};
+use crate::panelapi::core::{AppState, Error, PanelError};
 use axum::{
     http::StatusCode,
     response::{IntoResponse, Response},
     Json,
 };
+use serenity::all::UserId;
 
 pub async fn get_user(
     state: &AppState,
@@ -14,7 +15,11 @@ pub async fn get_user(
 ) -> Result<Response, Error> {
     check_auth(&state.pool, &login_token)
         .await
-        .map_err(Error::new)?;
+        .map_err(|e| PanelError::NotAuthorized(e.to_string()))?;
+
+    if user_id.parse::<UserId>().is_err() {
+        return Err(PanelError::BadRequest("Invalid user id".to_string()).into());
+    }
 
     let user = get_platform_user(
         &state.pool,
@@ -26,3 +31,34 @@ pub async fn get_user(
 
     Ok((StatusCode::OK, Json(user)).into_response())
 }
+
+/// Maximum number of users that can be fetched in a single bulk request
+const MAX_BULK_USERS: usize = 100;
+
+pub async fn get_user_bulk(
+    state: &AppState,
+    login_token: String,
+    user_ids: Vec<String>,
+) -> Result<Response, Error> {
+    check_auth(&state.pool, &login_token)
+        .await
+        .map_err(|e| PanelError::NotAuthorized(e.to_string()))?;
+
+    if user_ids.len() > MAX_BULK_USERS {
+        return Err(PanelError::BadRequest(format!(
+            "At most {} users can be fetched at once",
+            MAX_BULK_USERS
+        ))
+        .into());
+    }
+
+    let users = get_platform_users(
+        &state.pool,
+        DovewingSource::Discord(state.cache_http.clone()),
+        &user_ids,
+    )
+    .await
+    .map_err(Error::new)?;
+
+    Ok((StatusCode::OK, Json(users)).into_response())
+}
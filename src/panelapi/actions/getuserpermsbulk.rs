@@ -0,0 +1,85 @@
+use crate::panelapi::auth::check_auth;
+use crate::panelapi::core::{AppState, Error, PanelError};
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use kittycat::perms::{PartialStaffPosition, Permission, StaffPermissions};
+use std::collections::HashMap;
+
+/// The maximum number of users whose permissions can be fetched at once
+const MAX_BULK_USERS: usize = 500;
+
+pub async fn get_user_perms_bulk(
+    state: &AppState,
+    login_token: String,
+    user_ids: Vec<String>,
+) -> Result<Response, Error> {
+    check_auth(&state.pool, &login_token)
+        .await
+        .map_err(|e| PanelError::NotAuthorized(e.to_string()))?;
+
+    if user_ids.len() > MAX_BULK_USERS {
+        return Err(PanelError::BadRequest(format!(
+            "At most {} users can be fetched at once",
+            MAX_BULK_USERS
+        ))
+        .into());
+    }
+
+    let members = sqlx::query!(
+        "SELECT user_id, positions, perm_overrides FROM staff_members WHERE user_id = ANY($1)",
+        &user_ids
+    )
+    .fetch_all(&state.pool)
+    .await
+    .map_err(Error::new)?;
+
+    let position_ids = members
+        .iter()
+        .flat_map(|m| m.positions.iter().copied())
+        .collect::<Vec<_>>();
+
+    let positions = sqlx::query!(
+        "SELECT id, index, perms FROM staff_positions WHERE id = ANY($1)",
+        &position_ids
+    )
+    .fetch_all(&state.pool)
+    .await
+    .map_err(Error::new)?;
+
+    // Users who are not staff members are returned as null
+    let mut perms: HashMap<String, Option<Vec<String>>> =
+        user_ids.into_iter().map(|id| (id, None)).collect();
+
+    for member in members {
+        let sp = StaffPermissions {
+            user_positions: positions
+                .iter()
+                .filter(|p| member.positions.contains(&p.id))
+                .map(|p| PartialStaffPosition {
+                    id: p.id.hyphenated().to_string(),
+                    index: p.index,
+                    perms: p
+                        .perms
+                        .iter()
+                        .map(|x| Permission::from_string(x))
+                        .collect::<Vec<Permission>>(),
+                })
+                .collect(),
+            perm_overrides: member
+                .perm_overrides
+                .iter()
+                .map(|x| Permission::from_string(x))
+                .collect::<Vec<Permission>>(),
+        };
+
+        perms.insert(
+            member.user_id,
+            Some(sp.resolve().iter().map(|p| p.to_string()).collect()),
+        );
+    }
+
+    Ok((StatusCode::OK, Json(perms)).into_response())
+}
@@ -2,16 +2,20 @@ use std::str::FromStr;
 
 use crate::impls::target_types::TargetType;
 use crate::panelapi::auth::{check_auth, get_staff_member};
-use crate::panelapi::core::{AppState, Error};
-use crate::panelapi::types::webcore::{CoreConstants, Hello, InstanceConfig, PanelServers};
+use crate::panelapi::core::{AppState, Error, PanelError};
+use crate::panelapi::types::webcore::{CoreConstants, Hello, InstanceDiagnostics, PanelServers};
 use axum::{
     http::StatusCode,
     response::{IntoResponse, Response},
     Json,
 };
+use kittycat::perms;
 use strum::VariantNames;
 
-const HELLO_VERSION: u16 = 5;
+const HELLO_VERSION: u16 = 7;
+
+/// Database latency (in milliseconds) above which a warning is shown on the panel
+const DB_LATENCY_WARN_MS: u64 = 500;
 
 pub async fn hello(
     state: &AppState,
@@ -22,10 +26,10 @@ pub async fn hello(
 ) -> Result<Response, Error> {
     let auth_data = check_auth(&state.pool, &login_token)
         .await
-        .map_err(Error::new)?;
+        .map_err(|e| PanelError::NotAuthorized(e.to_string()))?;
 
     if version != HELLO_VERSION {
-        return Ok((StatusCode::BAD_REQUEST, "Invalid version".to_string()).into_response());
+        return Err(PanelError::BadRequest("Invalid version".to_string()).into());
     }
 
     // Get permissions
@@ -33,6 +37,11 @@ pub async fn hello(
         .await
         .map_err(Error::new)?;
 
+    // Internal URLs are only returned to staff with the relevant permissions
+    let user_perms = state
+        .get_user_perms(&login_token, &auth_data.user_id)
+        .await?;
+
     let mut target_types: Vec<TargetType> = Vec::new();
 
     for target_type in TargetType::VARIANTS {
@@ -40,40 +49,64 @@ pub async fn hello(
         target_types.push(variant);
     }
 
+    let start = std::time::Instant::now();
+
+    sqlx::query!("SELECT 1 AS one")
+        .fetch_one(&state.pool)
+        .await
+        .map_err(Error::new)?;
+
+    let diagnostics = InstanceDiagnostics {
+        db_latency_ms: start.elapsed().as_millis() as u64,
+        db_pool_size: state.pool.size(),
+        db_pool_idle: state.pool.num_idle() as u32,
+    };
+
+    let mut instance_config = state.get_instance_config().await?;
+
+    if diagnostics.db_latency_ms > DB_LATENCY_WARN_MS {
+        instance_config.warnings.push(format!(
+            "[Warning]: The database is responding slowly ({}ms), some actions may take longer than usual.",
+            diagnostics.db_latency_ms
+        ));
+    }
+
     Ok((
-    StatusCode::OK,
-    Json(
-        Hello {
-            instance_config: InstanceConfig {
-                description: {
-                    if *crate::config::CURRENT_ENV == "staging" {
-                        "Arcadia Staging Panel Instance".to_string()
-                    } else {
-                        "Arcadia Production Panel Instance".to_string()
-                    }
-                },
-                warnings: vec![
-                    "Oh, hello there. This panel is currently being rewritten, and may have some issues. If you find any issues, please contact a Lead Developer in the `Staff Center` Discord Server!".to_string(),
-                    "[Warning]: `panel.infinitybots.gg` will soon be unaccessible as we move our panel into the main site.".to_string()
-                ],
-            },
+        StatusCode::OK,
+        Json(Hello {
+            instance_config,
             auth_data,
             staff_member,
             core_constants: CoreConstants {
                 frontend_url: crate::config::CONFIG.frontend_url.get().clone(),
-                infernoplex_url: crate::config::CONFIG.infernoplex_url.clone(),
-                popplio_url: crate::config::CONFIG.popplio_url.clone(),
+                infernoplex_url: if perms::has_perm(
+                    &user_perms,
+                    &"core_constants.infernoplex_url".into(),
+                ) {
+                    Some(crate::config::CONFIG.infernoplex_url.clone())
+                } else {
+                    None
+                },
+                popplio_url: if perms::has_perm(&user_perms, &"core_constants.popplio_url".into()) {
+                    Some(crate::config::CONFIG.popplio_url.clone())
+                } else {
+                    None
+                },
                 htmlsanitize_url: crate::config::CONFIG.htmlsanitize_url.clone(),
                 cdn_url: crate::config::CONFIG.cdn_url.clone(),
-                servers: PanelServers {
-                    main: crate::config::CONFIG.servers.main.to_string(),
-                    staff: crate::config::CONFIG.servers.staff.to_string(),
-                    testing: crate::config::CONFIG.servers.testing.to_string(),
+                servers: if perms::has_perm(&user_perms, &"core_constants.servers".into()) {
+                    Some(PanelServers {
+                        main: crate::config::CONFIG.servers.main.to_string(),
+                        staff: crate::config::CONFIG.servers.staff.to_string(),
+                        testing: crate::config::CONFIG.servers.testing.to_string(),
+                    })
+                } else {
+                    None
                 },
             },
             target_types,
-        }
+            diagnostics,
+        }),
     )
-)
-    .into_response())
+        .into_response())
 }
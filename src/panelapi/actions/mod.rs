@@ -1,9 +1,23 @@
 pub mod authorize;
 pub mod baseanalytics;
+pub mod getblogposts;
+pub mod getfullidentity;
+pub mod getpartneranalytics;
+pub mod getpermissionmatrix;
+pub mod getrpclogentries;
+pub mod getstaffdisciplinaries;
 pub mod getuser;
+pub mod getuserpermsbulk;
 pub mod hello;
+pub mod reloadcdnscopes;
+pub mod retryrpclogentry;
+pub mod reviewbot;
 pub mod searchentitys;
+pub mod sessions;
+pub mod setinstanceconfig;
+pub mod teamlist;
 pub mod updatepartners;
+pub mod updatepartnertypes;
 pub mod updateshopholds;
 pub mod updatestaffmembers;
 pub mod updatestaffposition;
@@ -0,0 +1,34 @@
+use crate::panelapi::auth::check_auth;
+use crate::panelapi::core::{require_owner, AppState, Error, PanelError};
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use log::info;
+
+pub async fn reload_cdn_scopes(state: &AppState, login_token: String) -> Result<Response, Error> {
+    let auth_data = check_auth(&state.pool, &login_token)
+        .await
+        .map_err(|e| PanelError::NotAuthorized(e.to_string()))?;
+
+    require_owner(&auth_data)?;
+
+    let cdn_scopes = tokio::task::spawn_blocking(crate::config::read_cdn_scopes)
+        .await
+        .map_err(Error::new)?
+        .map_err(|e| PanelError::BadRequest(e.to_string()))?;
+
+    let mut scope_names = cdn_scopes.keys().cloned().collect::<Vec<String>>();
+    scope_names.sort();
+
+    *state.cdn_scopes.write().unwrap_or_else(|e| e.into_inner()) = cdn_scopes;
+
+    info!(
+        "CDN scopes reloaded by {}: {}",
+        auth_data.user_id,
+        scope_names.join(", ")
+    );
+
+    Ok((StatusCode::OK, Json(scope_names)).into_response())
+}
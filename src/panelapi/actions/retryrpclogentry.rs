@@ -0,0 +1,117 @@
+use std::str::FromStr;
+
+use crate::impls::target_types::TargetType;
+use crate::panelapi::auth::check_auth;
+use crate::panelapi::core::{require_perm, rpc_response, AppState, Error, PanelError};
+use crate::rpc::core::{RPCHandle, RPCMethod, RPCSuccess};
+use axum::response::Response;
+
+/// How long (in seconds) an entry can stay retrying before it is assumed the retry died and it can be retried again
+const RETRY_TIMEOUT: f64 = 10.0 * 60.0;
+
+pub async fn retry_rpc_log_entry(
+    state: &AppState,
+    login_token: String,
+    log_id: String,
+    confirmation_token: Option<String>,
+) -> Result<Response, Error> {
+    let auth_data = check_auth(&state.pool, &login_token)
+        .await
+        .map_err(|e| PanelError::NotAuthorized(e.to_string()))?;
+
+    let Ok(log_id) = log_id.parse::<uuid::Uuid>() else {
+        return Err(PanelError::BadRequest("Invalid log ID".to_string()).into());
+    };
+
+    let mut tx = state.pool.begin().await.map_err(Error::new)?;
+
+    // Lock the entry so that concurrent retries of it wait here and then see it as retrying
+    let Some(entry) = sqlx::query!(
+        "SELECT user_id, data, state, target_type, COALESCE(retrying_at < NOW() - make_interval(secs => $2), true) AS \"retry_stale!\" FROM rpc_logs WHERE id = $1 FOR UPDATE",
+        log_id,
+        RETRY_TIMEOUT
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(Error::new)?
+    else {
+        return Err(PanelError::NotFound("Log entry not found".to_string()).into());
+    };
+
+    // Pending entries may still be running (or may have succeeded without being marked), so only failed ones are retried
+    //
+    // Retries that have been running for longer than RETRY_TIMEOUT most likely died (e.g. on a restart) and would otherwise block the entry forever
+    if entry.state != "error" && !(entry.state == "retrying" && entry.retry_stale) {
+        return Err(PanelError::BadRequest(format!(
+            "Only failed actions can be retried, this action is {}",
+            entry.state
+        ))
+        .into());
+    }
+
+    if entry.user_id != auth_data.user_id {
+        let user_perms = state
+            .get_user_perms(&login_token, &auth_data.user_id)
+            .await?;
+
+        require_perm(&user_perms, "rpc_logs.retry_any")?;
+    }
+
+    // Older entries did not store the target type and so cannot be replayed
+    let Some(target_type) = entry.target_type else {
+        return Err(
+            PanelError::BadRequest("This log entry is too old to be retried".to_string()).into(),
+        );
+    };
+
+    let target_type = TargetType::from_str(&target_type).map_err(Error::new)?;
+
+    let method = match serde_json::from_value::<RPCMethod>(entry.data) {
+        Ok(method) => method,
+        Err(e) => {
+            return Err(PanelError::BadRequest(format!(
+                "This log entry can no longer be retried: {}",
+                e
+            ))
+            .into())
+        }
+    };
+
+    sqlx::query!(
+        "UPDATE rpc_logs SET state = 'retrying', retrying_at = NOW() WHERE id = $1",
+        log_id
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(Error::new)?;
+
+    tx.commit().await.map_err(Error::new)?;
+
+    // The retry goes through the normal RPC path, so permissions and confirmations are checked for the retrying user
+    let handle = RPCHandle {
+        pool: state.pool.clone(),
+        cache_http: state.cache_http.clone(),
+        user_id: auth_data.user_id,
+        target_type,
+        confirmation_token,
+    };
+
+    let resp = method.handle(handle).await;
+
+    // The retry itself is logged as a new entry, the old one can be retried again unless it went through
+    let new_state = match resp {
+        Ok(RPCSuccess::ConfirmationRequired { .. }) | Err(_) => "error",
+        Ok(_) => "retried",
+    };
+
+    sqlx::query!(
+        "UPDATE rpc_logs SET state = $1, retrying_at = NULL WHERE id = $2",
+        new_state,
+        log_id
+    )
+    .execute(&state.pool)
+    .await
+    .map_err(Error::new)?;
+
+    Ok(rpc_response(resp))
+}
@@ -0,0 +1,62 @@
+use crate::impls::target_types::TargetType;
+use crate::panelapi::auth::check_auth;
+use crate::panelapi::core::{rpc_response, AppState, Error, PanelError};
+use crate::rpc::core::{RPCHandle, RPCMethod};
+use axum::response::Response;
+
+/// Approves a bot, this is a shortcut for the `Approve` RPC method
+pub async fn approve_bot(
+    state: &AppState,
+    login_token: String,
+    bot_id: String,
+    reason: String,
+) -> Result<Response, Error> {
+    review_bot(
+        state,
+        login_token,
+        RPCMethod::Approve {
+            target_id: bot_id,
+            reason,
+        },
+    )
+    .await
+}
+
+/// Denies a bot, this is a shortcut for the `Deny` RPC method
+pub async fn deny_bot(
+    state: &AppState,
+    login_token: String,
+    bot_id: String,
+    reason: String,
+) -> Result<Response, Error> {
+    review_bot(
+        state,
+        login_token,
+        RPCMethod::Deny {
+            target_id: bot_id,
+            reason,
+        },
+    )
+    .await
+}
+
+/// Runs a review through the normal RPC path so permissions, onboarding and logging all still apply
+async fn review_bot(
+    state: &AppState,
+    login_token: String,
+    method: RPCMethod,
+) -> Result<Response, Error> {
+    let auth_data = check_auth(&state.pool, &login_token)
+        .await
+        .map_err(|e| PanelError::NotAuthorized(e.to_string()))?;
+
+    let handle = RPCHandle {
+        pool: state.pool.clone(),
+        cache_http: state.cache_http.clone(),
+        user_id: auth_data.user_id,
+        target_type: TargetType::Bot,
+        confirmation_token: None,
+    };
+
+    Ok(rpc_response(method.handle(handle).await))
+}
@@ -1,41 +1,117 @@
 use crate::impls::dovewing::{get_platform_user, DovewingSource};
 use crate::impls::target_types::TargetType;
 use crate::impls::utils::get_entity_managers;
+use crate::panelapi::actions::teamlist::get_partial_teams;
 use crate::panelapi::auth::check_auth;
-use crate::panelapi::core::{AppState, Error};
-use crate::panelapi::types::entity::{PartialBot, PartialEntity, PartialServer};
+use crate::panelapi::core::{AppState, Error, PageCursor, PanelError};
+use crate::panelapi::types::entity::{
+    EntitySearchResults, PartialBot, PartialEntity, PartialServer,
+};
 use axum::{
     http::StatusCode,
     response::{IntoResponse, Response},
     Json,
 };
 
+/// Default number of results returned per page
+const DEFAULT_LIMIT: i64 = 25;
+
+/// Maximum number of results that can be returned per page
+const MAX_LIMIT: i64 = 100;
+
 pub async fn search_entitys(
     state: &AppState,
     login_token: String,
     target_type: TargetType,
     query: String,
+    limit: Option<i64>,
+    offset: Option<i64>,
+    cursor: Option<String>,
 ) -> Result<Response, Error> {
     check_auth(&state.pool, &login_token)
         .await
-        .map_err(Error::new)?;
+        .map_err(|e| PanelError::NotAuthorized(e.to_string()))?;
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let cursor = cursor.as_deref().map(PageCursor::decode).transpose()?;
+
+    // Cursors already point past the previous page
+    let offset = if cursor.is_some() {
+        0
+    } else {
+        offset.unwrap_or_default().max(0)
+    };
+
+    // Keyset over the same order the results are ranked in, one more result than requested is fetched to know if there is a next page
+    let (after_exact, after_similarity, after_created_at, after_id) = match cursor {
+        Some(c) => (
+            Some(c.exact),
+            Some(c.similarity),
+            Some(c.created_at),
+            Some(c.id),
+        ),
+        None => (None, None, None, None),
+    };
 
     match target_type {
         TargetType::Bot => {
-            let queue = sqlx::query!(
+            // Name matches use the trigram index (if pg_trgm is installed) and are ranked by similarity, exact ID matches always come first
+            let total = sqlx::query!(
+                "SELECT COUNT(*) FROM bots
+                INNER JOIN internal_user_cache__discord discord_users ON bots.bot_id = discord_users.id
+                WHERE bot_id = $1 OR client_id = $1 OR discord_users.username ILIKE $2",
+                query,
+                format!("%{}%", query)
+            )
+            .fetch_one(&state.pool)
+            .await
+            .map_err(Error::new)?
+            .count
+            .unwrap_or_default();
+
+            let mut queue = sqlx::query!(
             "
             SELECT bot_id, client_id, type, approximate_votes, shards, library, invite_clicks, clicks,
-            servers, last_claimed, claimed_by, approval_note, short, invite FROM bots
+            servers, last_claimed, claimed_by, approval_note, short, invite, bots.created_at,
+            (bot_id = $1 OR client_id = $1) AS \"exact!\", arcadia_search_similarity(discord_users.username, $1) AS \"similarity!\" FROM bots
             INNER JOIN internal_user_cache__discord discord_users ON bots.bot_id = discord_users.id
-            WHERE bot_id = $1 OR client_id = $1 OR discord_users.username ILIKE $2 ORDER BY bots.created_at
+            WHERE (bot_id = $1 OR client_id = $1 OR discord_users.username ILIKE $2)
+            AND ($5::bool IS NULL OR (NOT (bot_id = $1 OR client_id = $1), -arcadia_search_similarity(discord_users.username, $1), bots.created_at, bot_id) > (NOT $5, -$6::real, $7, $8))
+            ORDER BY (bot_id = $1 OR client_id = $1) DESC, arcadia_search_similarity(discord_users.username, $1) DESC, bots.created_at, bot_id
+            LIMIT $3 OFFSET $4
             ",
             query,
-            format!("%{}%", query)
+            format!("%{}%", query),
+            limit + 1,
+            offset,
+            after_exact,
+            after_similarity,
+            after_created_at,
+            after_id
         )
         .fetch_all(&state.pool)
         .await
         .map_err(Error::new)?;
 
+            let next_cursor = if queue.len() as i64 > limit {
+                queue.truncate(limit as usize);
+
+                queue
+                    .last()
+                    .map(|bot| {
+                        PageCursor {
+                            exact: bot.exact,
+                            similarity: bot.similarity,
+                            created_at: bot.created_at,
+                            id: bot.bot_id.clone(),
+                        }
+                        .encode()
+                    })
+                    .transpose()?
+            } else {
+                None
+            };
+
             let mut bots = Vec::new();
 
             for bot in queue {
@@ -68,25 +144,75 @@ pub async fn search_entitys(
                     short: bot.short,
                     mentionable: owners.mentionables(),
                     invite: bot.invite,
+                    queue_position: None,
+                    estimated_wait: None,
                 }));
             }
 
-            Ok((StatusCode::OK, Json(bots)).into_response())
+            Ok((
+                StatusCode::OK,
+                Json(EntitySearchResults {
+                    entities: bots,
+                    total,
+                    next_cursor,
+                }),
+            )
+                .into_response())
         }
         TargetType::Server => {
-            let queue = sqlx::query!(
+            let total = sqlx::query!(
+                "SELECT COUNT(*) FROM servers WHERE server_id = $1 OR name ILIKE $2",
+                query,
+                format!("%{}%", query)
+            )
+            .fetch_one(&state.pool)
+            .await
+            .map_err(Error::new)?
+            .count
+            .unwrap_or_default();
+
+            let mut queue = sqlx::query!(
             "
             SELECT server_id, name, total_members, online_members, short, type, approximate_votes, invite_clicks,
-            clicks, nsfw, tags, premium, claimed_by, last_claimed FROM servers
-            WHERE server_id = $1 OR name ILIKE $2 ORDER BY created_at
+            clicks, nsfw, tags, premium, claimed_by, last_claimed, created_at,
+            (server_id = $1) AS \"exact!\", arcadia_search_similarity(name, $1) AS \"similarity!\" FROM servers
+            WHERE (server_id = $1 OR name ILIKE $2)
+            AND ($5::bool IS NULL OR (NOT (server_id = $1), -arcadia_search_similarity(name, $1), created_at, server_id) > (NOT $5, -$6::real, $7, $8))
+            ORDER BY (server_id = $1) DESC, arcadia_search_similarity(name, $1) DESC, created_at, server_id
+            LIMIT $3 OFFSET $4
             ",
             query,
-            format!("%{}%", query)
+            format!("%{}%", query),
+            limit + 1,
+            offset,
+            after_exact,
+            after_similarity,
+            after_created_at,
+            after_id
         )
         .fetch_all(&state.pool)
         .await
         .map_err(Error::new)?;
 
+            let next_cursor = if queue.len() as i64 > limit {
+                queue.truncate(limit as usize);
+
+                queue
+                    .last()
+                    .map(|server| {
+                        PageCursor {
+                            exact: server.exact,
+                            similarity: server.similarity,
+                            created_at: server.created_at,
+                            id: server.server_id.clone(),
+                        }
+                        .encode()
+                    })
+                    .transpose()?
+            } else {
+                None
+            };
+
             let mut servers = Vec::new();
 
             for server in queue {
@@ -119,7 +245,84 @@ pub async fn search_entitys(
                 }));
             }
 
-            Ok((StatusCode::OK, Json(servers)).into_response())
+            Ok((
+                StatusCode::OK,
+                Json(EntitySearchResults {
+                    entities: servers,
+                    total,
+                    next_cursor,
+                }),
+            )
+                .into_response())
+        }
+        TargetType::Team => {
+            let total = sqlx::query!(
+                "SELECT COUNT(*) FROM teams WHERE id::text = $1 OR name ILIKE $2",
+                query,
+                format!("%{}%", query)
+            )
+            .fetch_one(&state.pool)
+            .await
+            .map_err(Error::new)?
+            .count
+            .unwrap_or_default();
+
+            let mut rows = sqlx::query!(
+                "
+                SELECT id, created_at, (id::text = $1) AS \"exact!\", arcadia_search_similarity(name, $1) AS \"similarity!\" FROM teams
+                WHERE (id::text = $1 OR name ILIKE $2)
+                AND ($5::bool IS NULL OR (NOT (id::text = $1), -arcadia_search_similarity(name, $1), created_at, id::text) > (NOT $5, -$6::real, $7, $8))
+                ORDER BY (id::text = $1) DESC, arcadia_search_similarity(name, $1) DESC, created_at, id::text
+                LIMIT $3 OFFSET $4
+                ",
+                query,
+                format!("%{}%", query),
+                limit + 1,
+                offset,
+                after_exact,
+                after_similarity,
+                after_created_at,
+                after_id
+            )
+            .fetch_all(&state.pool)
+            .await
+            .map_err(Error::new)?;
+
+            let next_cursor = if rows.len() as i64 > limit {
+                rows.truncate(limit as usize);
+
+                rows.last()
+                    .map(|team| {
+                        PageCursor {
+                            exact: team.exact,
+                            similarity: team.similarity,
+                            created_at: team.created_at,
+                            id: team.id.to_string(),
+                        }
+                        .encode()
+                    })
+                    .transpose()?
+            } else {
+                None
+            };
+
+            let team_ids = rows.into_iter().map(|t| t.id).collect::<Vec<_>>();
+
+            let teams = get_partial_teams(state, &team_ids)
+                .await?
+                .into_iter()
+                .map(PartialEntity::Team)
+                .collect();
+
+            Ok((
+                StatusCode::OK,
+                Json(EntitySearchResults {
+                    entities: teams,
+                    total,
+                    next_cursor,
+                }),
+            )
+                .into_response())
         }
         _ => Ok((
             StatusCode::NOT_IMPLEMENTED,
@@ -0,0 +1,190 @@
+use crate::panelapi::auth::check_auth;
+use crate::panelapi::core::{require_owner, AppState, Error, PanelError};
+use crate::panelapi::types::auth::{PanelSession, PurgedSessions};
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+
+/// Maximum length of a session name
+const MAX_SESSION_NAME_LENGTH: usize = 64;
+
+/// Returns a coarse, human readable description of a user agent
+fn describe_user_agent(user_agent: &str) -> String {
+    // Order matters here as most browsers also claim to be Chrome/Safari
+    let browser = [
+        ("Edg/", "Edge"),
+        ("OPR/", "Opera"),
+        ("Firefox/", "Firefox"),
+        ("Chrome/", "Chrome"),
+        ("Safari/", "Safari"),
+    ]
+    .iter()
+    .find(|(needle, _)| user_agent.contains(needle))
+    .map(|(_, name)| *name);
+
+    let os = [
+        ("Android", "Android"),
+        ("iPhone", "iOS"),
+        ("iPad", "iPadOS"),
+        ("Windows", "Windows"),
+        ("Mac OS X", "macOS"),
+        ("CrOS", "ChromeOS"),
+        ("Linux", "Linux"),
+    ]
+    .iter()
+    .find(|(needle, _)| user_agent.contains(needle))
+    .map(|(_, name)| *name);
+
+    match (browser, os) {
+        (Some(browser), Some(os)) => format!("{} on {}", browser, os),
+        (Some(browser), None) => browser.to_string(),
+        (None, Some(os)) => format!("Unknown browser on {}", os),
+        (None, None) => "Unknown client".to_string(),
+    }
+}
+
+pub async fn list_sessions(state: &AppState, login_token: String) -> Result<Response, Error> {
+    let auth_data = check_auth(&state.pool, &login_token)
+        .await
+        .map_err(|e| PanelError::NotAuthorized(e.to_string()))?;
+
+    let recs = sqlx::query!(
+        "SELECT itag, token, name, user_agent, state, created_at FROM staffpanel__authchain WHERE user_id = $1 ORDER BY created_at DESC",
+        auth_data.user_id
+    )
+    .fetch_all(&state.pool)
+    .await
+    .map_err(Error::new)?;
+
+    let sessions = recs
+        .into_iter()
+        .map(|rec| PanelSession {
+            id: rec.itag.hyphenated().to_string(),
+            client: rec
+                .user_agent
+                .as_deref()
+                .map(describe_user_agent)
+                .unwrap_or_else(|| "Unknown client".to_string()),
+            name: rec.name,
+            user_agent: rec.user_agent,
+            state: rec.state,
+            created_at: rec.created_at,
+            current: rec.token == login_token,
+        })
+        .collect::<Vec<_>>();
+
+    Ok((StatusCode::OK, Json(sessions)).into_response())
+}
+
+pub async fn rename_session(
+    state: &AppState,
+    login_token: String,
+    session_id: String,
+    name: Option<String>,
+) -> Result<Response, Error> {
+    let auth_data = check_auth(&state.pool, &login_token)
+        .await
+        .map_err(|e| PanelError::NotAuthorized(e.to_string()))?;
+
+    let session_id = sqlx::types::Uuid::parse_str(&session_id)
+        .map_err(|_| PanelError::BadRequest("Invalid session ID".to_string()))?;
+
+    let name = name.map(|n| n.trim().to_string()).filter(|n| !n.is_empty());
+
+    if let Some(ref name) = name {
+        if name.chars().count() > MAX_SESSION_NAME_LENGTH {
+            return Err(PanelError::BadRequest(format!(
+                "Session name must be at most {} characters",
+                MAX_SESSION_NAME_LENGTH
+            ))
+            .into());
+        }
+    }
+
+    let res = sqlx::query!(
+        "UPDATE staffpanel__authchain SET name = $1 WHERE itag = $2 AND user_id = $3",
+        name,
+        session_id,
+        auth_data.user_id
+    )
+    .execute(&state.pool)
+    .await
+    .map_err(Error::new)?;
+
+    if res.rows_affected() == 0 {
+        return Err(PanelError::NotFound("Session not found".to_string()).into());
+    }
+
+    Ok((StatusCode::NO_CONTENT, "").into_response())
+}
+
+pub async fn purge_expired_sessions(
+    state: &AppState,
+    login_token: String,
+) -> Result<Response, Error> {
+    let auth_data = check_auth(&state.pool, &login_token)
+        .await
+        .map_err(|e| PanelError::NotAuthorized(e.to_string()))?;
+
+    require_owner(&auth_data)?;
+
+    let (expired, former_staff) = crate::tasks::sessionpurge::purge_expired_sessions(&state.pool)
+        .await
+        .map_err(Error::new)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(PurgedSessions {
+            expired,
+            former_staff,
+        }),
+    )
+        .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::describe_user_agent;
+
+    #[test]
+    fn describes_common_browsers() {
+        assert_eq!(
+            describe_user_agent(
+                "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:128.0) Gecko/20100101 Firefox/128.0"
+            ),
+            "Firefox on Windows"
+        );
+        assert_eq!(
+            describe_user_agent("Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.5 Safari/605.1.15"),
+            "Safari on macOS"
+        );
+        assert_eq!(
+            describe_user_agent("Mozilla/5.0 (Linux; Android 14) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/126.0.0.0 Mobile Safari/537.36"),
+            "Chrome on Android"
+        );
+    }
+
+    #[test]
+    fn prefers_specific_browsers_over_chrome() {
+        assert_eq!(
+            describe_user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/126.0.0.0 Safari/537.36 Edg/126.0.0.0"),
+            "Edge on Windows"
+        );
+        assert_eq!(
+            describe_user_agent("Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/126.0.0.0 Safari/537.36 OPR/111.0.0.0"),
+            "Opera on Linux"
+        );
+    }
+
+    #[test]
+    fn describes_unknown_clients() {
+        assert_eq!(describe_user_agent("curl/8.8.0"), "Unknown client");
+        assert_eq!(
+            describe_user_agent("SomeBot/1.0 (Windows)"),
+            "Unknown browser on Windows"
+        );
+        assert_eq!(describe_user_agent("Firefox/128.0"), "Firefox");
+    }
+}
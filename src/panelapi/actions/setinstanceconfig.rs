@@ -0,0 +1,41 @@
+use crate::panelapi::auth::check_auth;
+use crate::panelapi::core::{require_owner, AppState, Error, PanelError};
+use crate::panelapi::types::webcore::InstanceConfig;
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+
+pub async fn set_instance_config(
+    state: &AppState,
+    login_token: String,
+    instance_config: InstanceConfig,
+) -> Result<Response, Error> {
+    let auth_data = check_auth(&state.pool, &login_token)
+        .await
+        .map_err(|e| PanelError::NotAuthorized(e.to_string()))?;
+
+    // Only owners may change what every staff member sees on the panel
+    require_owner(&auth_data)?;
+
+    if instance_config.description.is_empty() {
+        return Err(PanelError::BadRequest("Description cannot be empty".to_string()).into());
+    }
+
+    let env = crate::config::CURRENT_ENV.to_string();
+
+    sqlx::query!(
+        "INSERT INTO instance_config (env, description, warnings, updated_at) VALUES ($1, $2, $3, NOW())
+        ON CONFLICT (env) DO UPDATE SET description = EXCLUDED.description, warnings = EXCLUDED.warnings, updated_at = NOW()",
+        env,
+        instance_config.description,
+        &instance_config.warnings
+    )
+    .execute(&state.pool)
+    .await
+    .map_err(Error::new)?;
+
+    state.instance_config.invalidate(&env).await;
+
+    Ok((StatusCode::NO_CONTENT, "").into_response())
+}
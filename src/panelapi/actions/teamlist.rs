@@ -0,0 +1,86 @@
+use crate::impls::dovewing::{get_platform_users, DovewingSource};
+use crate::panelapi::core::{AppState, Error};
+use crate::panelapi::types::entity::{PartialEntity, PartialTeam};
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+
+/// Returns the partial teams for the given team IDs, in the same order as the IDs
+pub async fn get_partial_teams(
+    state: &AppState,
+    team_ids: &[sqlx::types::Uuid],
+) -> Result<Vec<PartialTeam>, Error> {
+    let teams = sqlx::query!(
+        "SELECT id, name, created_at, (SELECT COUNT(*) FROM team_members WHERE team_id = teams.id) AS member_count
+        FROM teams WHERE id = ANY($1)",
+        team_ids
+    )
+    .fetch_all(&state.pool)
+    .await
+    .map_err(Error::new)?;
+
+    let owners = sqlx::query!(
+        "SELECT team_id, user_id FROM team_members WHERE team_id = ANY($1) AND flags @> ARRAY['global.*']",
+        team_ids
+    )
+    .fetch_all(&state.pool)
+    .await
+    .map_err(Error::new)?;
+
+    let users = get_platform_users(
+        &state.pool,
+        DovewingSource::Discord(state.cache_http.clone()),
+        &owners.iter().map(|o| o.user_id.clone()).collect::<Vec<_>>(),
+    )
+    .await
+    .map_err(Error::new)?;
+
+    let mut partial_teams = Vec::new();
+
+    for team_id in team_ids {
+        let Some(team) = teams.iter().find(|t| t.id == *team_id) else {
+            continue;
+        };
+
+        partial_teams.push(PartialTeam {
+            id: team.id.to_string(),
+            name: team.name.clone(),
+            avatar: format!(
+                "{}/teams/avatars/{}.webp",
+                crate::config::CONFIG.cdn_url,
+                team.id
+            ),
+            member_count: team.member_count.unwrap_or_default(),
+            owners: owners
+                .iter()
+                .filter(|o| o.team_id == team.id)
+                .filter_map(|o| users.get(&o.user_id).cloned())
+                .collect(),
+            created_at: team.created_at,
+        });
+    }
+
+    Ok(partial_teams)
+}
+
+pub async fn team_list(state: &AppState, login_token: String) -> Result<Response, Error> {
+    state.require_perm(&login_token, "teams.list").await?;
+
+    let team_ids = sqlx::query!("SELECT id FROM teams ORDER BY created_at DESC")
+        .fetch_all(&state.pool)
+        .await
+        .map_err(Error::new)?
+        .into_iter()
+        .map(|t| t.id)
+        .collect::<Vec<_>>();
+
+    let teams = get_partial_teams(state, &team_ids)
+        .await?
+        .into_iter()
+        .map(PartialEntity::Team)
+        .collect::<Vec<_>>();
+
+    Ok((StatusCode::OK, Json(teams)).into_response())
+}
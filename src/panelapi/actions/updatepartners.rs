@@ -1,6 +1,8 @@
-use crate::impls::utils::get_user_perms;
+use crate::impls::utils::partner_image_path;
 use crate::panelapi::auth::check_auth;
-use crate::panelapi::core::{AppState, Error};
+use crate::panelapi::core::{
+    require_perm, AppState, Error, IdempotentRequest, PanelError, IDEMPOTENCY_RESERVATION_TIMEOUT,
+};
 use crate::panelapi::types::partners::{
     CreatePartner, Partner, PartnerAction, PartnerType, Partners,
 };
@@ -9,8 +11,48 @@ use axum::{
     response::{IntoResponse, Response},
     Json,
 };
-use kittycat::perms;
+use log::warn;
 use sqlx::PgPool;
+use std::collections::HashSet;
+use std::time::Instant;
+
+/// The maximum number of links a partner can have
+const MAX_PARTNER_LINKS: usize = 10;
+
+/// Locks partners for the rest of the transaction so concurrent position changes cannot interleave and collide
+async fn lock_positions(tx: &mut sqlx::PgConnection) -> Result<(), Error> {
+    sqlx::query!("LOCK TABLE partners IN SHARE ROW EXCLUSIVE MODE")
+        .execute(tx)
+        .await
+        .map_err(Error::new)?;
+
+    Ok(())
+}
+
+/// Moves a partners image between the CDN and the CDN trash, doing nothing if there is no image
+async fn move_partner_image(id: &str, to_trash: bool) -> Result<(), crate::Error> {
+    let from = partner_image_path(id, !to_trash)?;
+    let to = partner_image_path(id, to_trash)?;
+
+    match tokio::fs::metadata(&from).await {
+        Ok(_) => {
+            if let Some(parent) = std::path::Path::new(&to).parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+
+            tokio::fs::rename(&from, &to).await?;
+        }
+        Err(e) => {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                return Err(
+                    format!("Fetching asset metadata failed due to unknown error: {}", e).into(),
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
 
 pub async fn update_partners(
     state: &AppState,
@@ -19,12 +61,11 @@ pub async fn update_partners(
 ) -> Result<Response, Error> {
     let auth_data = check_auth(&state.pool, &login_token)
         .await
-        .map_err(Error::new)?;
+        .map_err(|e| PanelError::NotAuthorized(e.to_string()))?;
 
-    let user_perms = get_user_perms(&state.pool, &auth_data.user_id)
-        .await
-        .map_err(Error::new)?
-        .resolve();
+    let user_perms = state
+        .get_user_perms(&login_token, &auth_data.user_id)
+        .await?;
 
     async fn parse_partner(pool: &PgPool, partner: &CreatePartner) -> Result<(), crate::Error> {
         // Check if partner type exists
@@ -38,46 +79,25 @@ pub async fn update_partners(
             return Err("Partner type does not exist".into());
         }
 
-        // Ensure that image has been uploaded to CDN
-        // Get cdn path from cdn_scope hashmap
-        let cdn_scopes = crate::config::CONFIG.panel.cdn_scopes.get();
-
-        let Some(cdn_path) = cdn_scopes.get(&crate::config::CONFIG.panel.main_scope) else {
-            return Err("Main scope not found".into());
-        };
-
-        let path = format!("{}/avatars/partners/{}.webp", cdn_path.path, partner.id);
-
-        match std::fs::metadata(&path) {
-            Ok(m) => {
-                if !m.is_file() {
-                    return Err("Image does not exist".into());
-                }
-
-                if m.len() > 100_000_000 {
-                    return Err("Image is too large".into());
-                }
-
-                if m.len() == 0 {
-                    return Err("Image is empty".into());
-                }
-            }
-            Err(e) => {
-                return Err(
-                    ("Fetching image metadata failed: ".to_string() + &e.to_string()).into(),
-                );
-            }
-        };
-
         if partner.links.is_empty() {
             return Err("Links cannot be empty".into());
         }
 
+        if partner.links.len() > MAX_PARTNER_LINKS {
+            return Err(format!("A partner can have at most {} links", MAX_PARTNER_LINKS).into());
+        }
+
+        let mut link_names = HashSet::new();
+
         for link in &partner.links {
             if link.name.is_empty() {
                 return Err("Link name cannot be empty".into());
             }
 
+            if !link_names.insert(link.name.to_lowercase()) {
+                return Err(format!("Duplicate link name: {}", link.name).into());
+            }
+
             if link.value.is_empty() {
                 return Err("Link URL cannot be empty".into());
             }
@@ -85,6 +105,13 @@ pub async fn update_partners(
             if !link.value.starts_with("https://") {
                 return Err("Link URL must start with https://".into());
             }
+
+            let url = reqwest::Url::parse(&link.value)
+                .map_err(|e| format!("Link URL {} is invalid: {}", link.value, e))?;
+
+            if url.host_str().unwrap_or_default().is_empty() {
+                return Err(format!("Link URL {} must have a host", link.value).into());
+            }
         }
 
         // Check user id
@@ -103,10 +130,42 @@ pub async fn update_partners(
         Ok(())
     }
 
+    /// Ensures that the partners image has been uploaded to the CDN
+    ///
+    /// As the image is keyed by partner id, this only needs to be checked on create
+    async fn check_partner_image(id: &str) -> Result<(), crate::Error> {
+        // Ensure that image has been uploaded to CDN
+        // Get cdn path from cdn_scope hashmap
+        let path = partner_image_path(id, false)?;
+
+        match tokio::fs::metadata(&path).await {
+            Ok(m) => {
+                if !m.is_file() {
+                    return Err("Image does not exist".into());
+                }
+
+                if m.len() > 100_000_000 {
+                    return Err("Image is too large".into());
+                }
+
+                if m.len() == 0 {
+                    return Err("Image is empty".into());
+                }
+            }
+            Err(e) => {
+                return Err(
+                    ("Fetching image metadata failed: ".to_string() + &e.to_string()).into(),
+                );
+            }
+        };
+
+        Ok(())
+    }
+
     match action {
         PartnerAction::List => {
             let prec = sqlx::query!(
-                "SELECT id, name, short, links, type, created_at, user_id, bot_id FROM partners"
+                "SELECT id, name, short, links, type, created_at, user_id, bot_id, position, version FROM partners WHERE deleted_at IS NULL ORDER BY position ASC, created_at ASC"
             )
             .fetch_all(&state.pool)
             .await
@@ -124,6 +183,8 @@ pub async fn update_partners(
                     created_at: partner.created_at,
                     user_id: partner.user_id,
                     bot_id: partner.bot_id,
+                    position: partner.position,
+                    version: partner.version,
                 })
             }
 
@@ -153,160 +214,347 @@ pub async fn update_partners(
             )
                 .into_response())
         }
-        PartnerAction::Create { partner } => {
-            if !perms::has_perm(&user_perms, &"partners.create".into()) {
-                return Ok((
-                    StatusCode::FORBIDDEN,
-                    "You do not have permission to create partners [partners.create]".to_string(),
-                )
-                    .into_response());
+        PartnerAction::Create {
+            partner,
+            idempotency_key,
+        } => {
+            require_perm(&user_perms, "partners.create")?;
+            state
+                .require_step_up(&login_token, "partners.create")
+                .await?;
+
+            // Keys are scoped per user so users cannot replay each others results
+            let idempotency_key = idempotency_key.map(|k| (auth_data.user_id.clone(), k));
+
+            // Reserve the key first so concurrent requests with it cannot both create the partner
+            if let Some(ref key) = idempotency_key {
+                let entry = state
+                    .idempotency_keys
+                    .entry_by_ref(key)
+                    .or_insert_with(async { IdempotentRequest::Processing(Instant::now()) })
+                    .await;
+
+                if !entry.is_fresh() {
+                    match entry.into_value() {
+                        IdempotentRequest::Done(status, body) => {
+                            return Ok((status, body).into_response());
+                        }
+                        IdempotentRequest::Processing(since)
+                            if since.elapsed() < IDEMPOTENCY_RESERVATION_TIMEOUT =>
+                        {
+                            return Ok((
+                                StatusCode::CONFLICT,
+                                "A request with this idempotency key is still being processed"
+                                    .to_string(),
+                            )
+                                .into_response());
+                        }
+                        // The request holding the key never finished, take it over
+                        IdempotentRequest::Processing(_) => {
+                            state
+                                .idempotency_keys
+                                .insert(key.clone(), IdempotentRequest::Processing(Instant::now()))
+                                .await;
+                        }
+                    }
+                }
             }
 
-            // Check if partner already exists
-            let partner_exists = sqlx::query!("SELECT id FROM partners WHERE id = $1", partner.id)
-                .fetch_optional(&state.pool)
-                .await
-                .map_err(Error::new)?
-                .is_some();
+            let resp = async {
+                // Check if partner already exists
+                let existing =
+                    sqlx::query!("SELECT deleted_at FROM partners WHERE id = $1", partner.id)
+                        .fetch_optional(&state.pool)
+                        .await
+                        .map_err(Error::new)?;
+
+                if let Some(existing) = existing {
+                    let msg = if existing.deleted_at.is_some() {
+                        "A deleted partner with this ID exists, restore it instead"
+                    } else {
+                        "Partner already exists"
+                    };
+
+                    return Err(PanelError::BadRequest(msg.to_string()).into());
+                }
 
-            if partner_exists {
-                return Ok((
-                    StatusCode::BAD_REQUEST,
-                    "Partner already exists".to_string(),
-                )
-                    .into_response());
-            }
+                if let Err(e) = parse_partner(&state.pool, &partner).await {
+                    return Err(PanelError::BadRequest(e.to_string()).into());
+                }
 
-            if let Err(e) = parse_partner(&state.pool, &partner).await {
-                return Ok((StatusCode::BAD_REQUEST, e.to_string()).into_response());
-            }
+                if let Err(e) = check_partner_image(&partner.id).await {
+                    return Err(PanelError::BadRequest(e.to_string()).into());
+                }
 
-            // Insert partner
-            sqlx::query!(
-            "INSERT INTO partners (id, name, short, links, type, user_id, bot_id) VALUES ($1, $2, $3, $4, $5, $6, $7)",
-            partner.id,
-            partner.name,
-            partner.short,
-            serde_json::to_value(partner.links).map_err(Error::new)?,
-            partner.r#type,
-            partner.user_id,
-            partner.bot_id
-        )
-        .execute(&state.pool)
-        .await
-        .map_err(Error::new)?;
+                let mut tx = state.pool.begin().await.map_err(Error::new)?;
 
-            Ok((StatusCode::NO_CONTENT, "").into_response())
-        }
-        PartnerAction::Update { partner } => {
-            if !perms::has_perm(&user_perms, &"partners.update".into()) {
-                return Ok((
-                    StatusCode::FORBIDDEN,
-                    "You do not have permission to update partners [partners.update]".to_string(),
-                )
-                    .into_response());
+                lock_positions(&mut *tx).await?;
+
+                // Insert partner
+                sqlx::query!(
+                "INSERT INTO partners (id, name, short, links, type, user_id, bot_id, position) VALUES ($1, $2, $3, $4, $5, $6, $7, (SELECT COALESCE(MAX(position) + 1, 0) FROM partners WHERE deleted_at IS NULL))",
+                partner.id,
+                partner.name,
+                partner.short,
+                serde_json::to_value(partner.links).map_err(Error::new)?,
+                partner.r#type,
+                partner.user_id,
+                partner.bot_id
+            )
+            .execute(&mut *tx)
+            .await
+            .map_err(Error::new)?;
+
+                tx.commit().await.map_err(Error::new)?;
+
+                Ok::<_, Error>((StatusCode::NO_CONTENT, "").into_response())
+            }
+            .await;
+
+            // Only successful results are kept, failed requests can be retried with the same key
+            if let Some(key) = idempotency_key {
+                match resp {
+                    Ok(ref r) if r.status().is_success() => {
+                        state
+                            .idempotency_keys
+                            .insert(key, IdempotentRequest::Done(r.status(), String::new()))
+                            .await;
+                    }
+                    _ => state.idempotency_keys.invalidate(&key).await,
+                }
             }
 
+            resp
+        }
+        PartnerAction::Update { partner, version } => {
+            require_perm(&user_perms, "partners.update")?;
+            state
+                .require_step_up(&login_token, "partners.update")
+                .await?;
+
             // Check if partner already exists
-            let partner_exists = sqlx::query!("SELECT id FROM partners WHERE id = $1", partner.id)
-                .fetch_optional(&state.pool)
-                .await
-                .map_err(Error::new)?
-                .is_some();
+            let partner_exists = sqlx::query!(
+                "SELECT id FROM partners WHERE id = $1 AND deleted_at IS NULL",
+                partner.id
+            )
+            .fetch_optional(&state.pool)
+            .await
+            .map_err(Error::new)?
+            .is_some();
 
             if !partner_exists {
-                return Ok((
-                    StatusCode::BAD_REQUEST,
-                    "Partner does not already exist".to_string(),
-                )
-                    .into_response());
+                return Err(
+                    PanelError::NotFound("Partner does not already exist".to_string()).into(),
+                );
             }
 
             if let Err(e) = parse_partner(&state.pool, &partner).await {
-                return Ok((StatusCode::BAD_REQUEST, e.to_string()).into_response());
+                return Err(PanelError::BadRequest(e.to_string()).into());
             }
 
-            // Update partner
-            sqlx::query!(
-            "UPDATE partners SET name = $2, short = $3, links = $4, type = $5, user_id = $6, bot_id = $7 WHERE id = $1",
+            // Update partner, only if nobody else has updated it since the client read it
+            let res = sqlx::query!(
+            "UPDATE partners SET name = $2, short = $3, links = $4, type = $5, user_id = $6, bot_id = $7, version = version + 1 WHERE id = $1 AND version = $8",
             partner.id,
             partner.name,
             partner.short,
             serde_json::to_value(partner.links).map_err(Error::new)?,
             partner.r#type,
             partner.user_id,
-            partner.bot_id
+            partner.bot_id,
+            version
         )
         .execute(&state.pool)
         .await
         .map_err(Error::new)?;
 
+            if res.rows_affected() == 0 {
+                return Err(PanelError::StaleWrite.into());
+            }
+
             Ok((StatusCode::NO_CONTENT, "").into_response())
         }
         PartnerAction::Delete { id } => {
-            if !perms::has_perm(&user_perms, &"partners.delete".into()) {
-                return Ok((
-                    StatusCode::FORBIDDEN,
-                    "You do not have permission to delete partners [partners.delete]".to_string(),
-                )
-                    .into_response());
-            }
+            require_perm(&user_perms, "partners.delete")?;
+            state
+                .require_step_up(&login_token, "partners.delete")
+                .await?;
+
+            let mut tx = state.pool.begin().await.map_err(Error::new)?;
+
+            lock_positions(&mut *tx).await?;
 
-            // Check if partner exists
-            let partner_exists = sqlx::query!("SELECT id FROM partners WHERE id = $1", id)
-                .fetch_optional(&state.pool)
+            let Some(rec) = sqlx::query!(
+                "SELECT position FROM partners WHERE id = $1 AND deleted_at IS NULL FOR UPDATE",
+                id
+            )
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(Error::new)?
+            else {
+                return Err(PanelError::NotFound("Partner does not exist".to_string()).into());
+            };
+
+            sqlx::query!("UPDATE partners SET deleted_at = NOW() WHERE id = $1", id)
+                .execute(&mut *tx)
                 .await
-                .map_err(Error::new)?
-                .is_some();
+                .map_err(Error::new)?;
 
-            if !partner_exists {
-                return Ok((
-                    StatusCode::BAD_REQUEST,
-                    "Partner does not exist".to_string(),
-                )
-                    .into_response());
+            // Close the gap left by the deleted partner
+            sqlx::query!(
+                "UPDATE partners SET position = position - 1 WHERE position > $1 AND deleted_at IS NULL",
+                rec.position
+            )
+            .execute(&mut *tx)
+            .await
+            .map_err(Error::new)?;
+
+            tx.commit().await.map_err(Error::new)?;
+
+            // Move the image to the trash so it can be brought back on restore, only once the delete has gone through
+            if let Err(e) = move_partner_image(&id, true).await {
+                warn!(
+                    "Failed to move image of deleted partner {} to the trash: {}",
+                    id, e
+                );
             }
 
-            // Ensure that image has been uploaded to CDN
-            // Get cdn path from cdn_scope hashmap
-            let cdn_scopes = crate::config::CONFIG.panel.cdn_scopes.get();
+            Ok((StatusCode::NO_CONTENT, "").into_response())
+        }
+        PartnerAction::Reposition { id, new_position } => {
+            require_perm(&user_perms, "partners.update")?;
+            state
+                .require_step_up(&login_token, "partners.update")
+                .await?;
 
-            let Some(cdn_path) = cdn_scopes.get(&crate::config::CONFIG.panel.main_scope) else {
-                return Ok(
-                    (StatusCode::BAD_REQUEST, "Main scope not found".to_string()).into_response(),
+            if new_position < 0 {
+                return Err(
+                    PanelError::BadRequest("Position cannot be lower than 0".to_string()).into(),
                 );
-            };
+            }
 
-            let path = format!("{}/partners/{}.webp", cdn_path.path, id);
+            let mut tx = state.pool.begin().await.map_err(Error::new)?;
 
-            match std::fs::metadata(&path) {
-                Ok(m) => {
-                    if m.is_symlink() || m.is_file() {
-                        // Delete the symlink
-                        std::fs::remove_file(path).map_err(Error::new)?;
-                    } else if m.is_dir() {
-                        // Delete the directory
-                        std::fs::remove_dir_all(path).map_err(Error::new)?;
-                    }
-                }
-                Err(e) => {
-                    if e.kind() != std::io::ErrorKind::NotFound {
-                        return Ok((
-                            StatusCode::BAD_REQUEST,
-                            "Fetching asset metadata failed due to unknown error: ".to_string()
-                                + &e.to_string(),
-                        )
-                            .into_response());
-                    }
-                }
+            lock_positions(&mut *tx).await?;
+
+            let Some(rec) = sqlx::query!(
+                "SELECT position FROM partners WHERE id = $1 AND deleted_at IS NULL FOR UPDATE",
+                id
+            )
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(Error::new)?
+            else {
+                return Err(PanelError::NotFound("Partner does not exist".to_string()).into());
             };
 
-            sqlx::query!("DELETE FROM partners WHERE id = $1", id)
-                .execute(&state.pool)
+            let old_position = rec.position;
+
+            // Positions past the end would leave a gap, so they move the partner to the end instead
+            let max_position = sqlx::query!(
+                "SELECT COALESCE(MAX(position), 0) AS \"max!\" FROM partners WHERE deleted_at IS NULL"
+            )
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(Error::new)?
+            .max;
+
+            let new_position = new_position.min(max_position);
+
+            if new_position < old_position {
+                // Moving up, shift everything between the new and old position down by one
+                sqlx::query!(
+                    "UPDATE partners SET position = position + 1 WHERE position >= $1 AND position < $2 AND deleted_at IS NULL",
+                    new_position,
+                    old_position
+                )
+                .execute(&mut *tx)
+                .await
+                .map_err(Error::new)?;
+            } else if new_position > old_position {
+                // Moving down, shift everything between the old and new position up by one
+                sqlx::query!(
+                    "UPDATE partners SET position = position - 1 WHERE position > $1 AND position <= $2 AND deleted_at IS NULL",
+                    old_position,
+                    new_position
+                )
+                .execute(&mut *tx)
                 .await
                 .map_err(Error::new)?;
+            }
+
+            sqlx::query!(
+                "UPDATE partners SET position = $1 WHERE id = $2",
+                new_position,
+                id
+            )
+            .execute(&mut *tx)
+            .await
+            .map_err(Error::new)?;
+
+            tx.commit().await.map_err(Error::new)?;
 
             Ok((StatusCode::NO_CONTENT, "").into_response())
         }
     }
 }
+
+pub async fn restore_partner(
+    state: &AppState,
+    login_token: String,
+    partner_id: String,
+) -> Result<Response, Error> {
+    state.require_perm(&login_token, "partners.delete").await?;
+    state
+        .require_step_up(&login_token, "partners.delete")
+        .await?;
+
+    let window = crate::config::CONFIG.panel.partner_restore_window as i32;
+
+    let mut tx = state.pool.begin().await.map_err(Error::new)?;
+
+    lock_positions(&mut *tx).await?;
+
+    let Some(rec) = sqlx::query!(
+        "SELECT deleted_at IS NOT NULL AS \"deleted!\", deleted_at + make_interval(hours => $2) >= NOW() AS \"restorable!\" FROM partners WHERE id = $1 FOR UPDATE",
+        partner_id,
+        window
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(Error::new)?
+    else {
+        return Err(PanelError::NotFound("Partner does not exist".to_string()).into());
+    };
+
+    if !rec.deleted {
+        return Err(PanelError::BadRequest("Partner has not been deleted".to_string()).into());
+    }
+
+    if !rec.restorable {
+        return Err(PanelError::BadRequest(
+            "Partner can no longer be restored as the restore window has passed".to_string(),
+        )
+        .into());
+    }
+
+    // Restored partners go to the end as their old position has since been reused
+    sqlx::query!(
+        "UPDATE partners SET deleted_at = NULL, position = (SELECT COALESCE(MAX(position) + 1, 0) FROM partners WHERE deleted_at IS NULL) WHERE id = $1",
+        partner_id
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(Error::new)?;
+
+    tx.commit().await.map_err(Error::new)?;
+
+    if let Err(e) = move_partner_image(&partner_id, false).await {
+        warn!(
+            "Failed to move image of restored partner {} out of the trash: {}",
+            partner_id, e
+        );
+    }
+
+    Ok((StatusCode::NO_CONTENT, "").into_response())
+}
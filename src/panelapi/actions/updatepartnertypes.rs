@@ -0,0 +1,176 @@
+use crate::panelapi::auth::check_auth;
+use crate::panelapi::core::{require_perm, AppState, Error, PanelError};
+use crate::panelapi::types::partners::{CreatePartnerType, PartnerType, PartnerTypeAction};
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+
+pub async fn update_partner_types(
+    state: &AppState,
+    login_token: String,
+    action: PartnerTypeAction,
+) -> Result<Response, Error> {
+    let auth_data = check_auth(&state.pool, &login_token)
+        .await
+        .map_err(|e| PanelError::NotAuthorized(e.to_string()))?;
+
+    let user_perms = state
+        .get_user_perms(&login_token, &auth_data.user_id)
+        .await?;
+
+    fn parse_partner_type(partner_type: &CreatePartnerType) -> Result<(), crate::Error> {
+        if partner_type.id.is_empty() {
+            return Err("ID cannot be empty".into());
+        }
+
+        if partner_type.name.is_empty() {
+            return Err("Name cannot be empty".into());
+        }
+
+        if partner_type.short.is_empty() {
+            return Err("Short description cannot be empty".into());
+        }
+
+        if partner_type.icon.is_empty() {
+            return Err("Icon cannot be empty".into());
+        }
+
+        Ok(())
+    }
+
+    match action {
+        PartnerTypeAction::List => {
+            let rows = sqlx::query!("SELECT id, name, short, icon, created_at FROM partner_types")
+                .fetch_all(&state.pool)
+                .await
+                .map_err(Error::new)?;
+
+            let mut partner_types = Vec::new();
+
+            for row in rows {
+                partner_types.push(PartnerType {
+                    id: row.id,
+                    name: row.name,
+                    short: row.short,
+                    icon: row.icon,
+                    created_at: row.created_at,
+                })
+            }
+
+            Ok((StatusCode::OK, Json(partner_types)).into_response())
+        }
+        PartnerTypeAction::Create { partner_type } => {
+            require_perm(&user_perms, "partner_types.create")?;
+
+            if let Err(e) = parse_partner_type(&partner_type) {
+                return Err(PanelError::BadRequest(e.to_string()).into());
+            }
+
+            // Check if partner type already exists
+            let partner_type_exists = sqlx::query!(
+                "SELECT id FROM partner_types WHERE id = $1",
+                partner_type.id
+            )
+            .fetch_optional(&state.pool)
+            .await
+            .map_err(Error::new)?
+            .is_some();
+
+            if partner_type_exists {
+                return Err(
+                    PanelError::BadRequest("Partner type already exists".to_string()).into(),
+                );
+            }
+
+            sqlx::query!(
+                "INSERT INTO partner_types (id, name, short, icon) VALUES ($1, $2, $3, $4)",
+                partner_type.id,
+                partner_type.name,
+                partner_type.short,
+                partner_type.icon
+            )
+            .execute(&state.pool)
+            .await
+            .map_err(Error::new)?;
+
+            Ok((StatusCode::NO_CONTENT, "").into_response())
+        }
+        PartnerTypeAction::Update { partner_type } => {
+            require_perm(&user_perms, "partner_types.update")?;
+
+            if let Err(e) = parse_partner_type(&partner_type) {
+                return Err(PanelError::BadRequest(e.to_string()).into());
+            }
+
+            // Check if partner type exists
+            let partner_type_exists = sqlx::query!(
+                "SELECT id FROM partner_types WHERE id = $1",
+                partner_type.id
+            )
+            .fetch_optional(&state.pool)
+            .await
+            .map_err(Error::new)?
+            .is_some();
+
+            if !partner_type_exists {
+                return Err(PanelError::NotFound(
+                    "Partner type does not already exist".to_string(),
+                )
+                .into());
+            }
+
+            sqlx::query!(
+                "UPDATE partner_types SET name = $2, short = $3, icon = $4 WHERE id = $1",
+                partner_type.id,
+                partner_type.name,
+                partner_type.short,
+                partner_type.icon
+            )
+            .execute(&state.pool)
+            .await
+            .map_err(Error::new)?;
+
+            Ok((StatusCode::NO_CONTENT, "").into_response())
+        }
+        PartnerTypeAction::Delete { id } => {
+            require_perm(&user_perms, "partner_types.delete")?;
+
+            // Check if partner type exists
+            let partner_type_exists =
+                sqlx::query!("SELECT id FROM partner_types WHERE id = $1", id)
+                    .fetch_optional(&state.pool)
+                    .await
+                    .map_err(Error::new)?
+                    .is_some();
+
+            if !partner_type_exists {
+                return Err(PanelError::NotFound("Partner type does not exist".to_string()).into());
+            }
+
+            // Refuse to delete partner types that are still in use
+            let count = sqlx::query!("SELECT COUNT(*) FROM partners WHERE type = $1", id)
+                .fetch_one(&state.pool)
+                .await
+                .map_err(Error::new)?
+                .count
+                .unwrap_or_default();
+
+            if count > 0 {
+                return Err(PanelError::BadRequest(format!(
+                    "Partner type is still in use by {} partner(s), reassign or delete them first",
+                    count
+                ))
+                .into());
+            }
+
+            sqlx::query!("DELETE FROM partner_types WHERE id = $1", id)
+                .execute(&state.pool)
+                .await
+                .map_err(Error::new)?;
+
+            Ok((StatusCode::NO_CONTENT, "").into_response())
+        }
+    }
+}
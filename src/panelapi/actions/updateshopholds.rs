@@ -1,13 +1,11 @@
-use crate::impls::utils::get_user_perms;
 use crate::panelapi::auth::check_auth;
-use crate::panelapi::core::{AppState, Error};
+use crate::panelapi::core::{require_perm, AppState, Error, PanelError};
 use crate::panelapi::types::shop_items::{ShopHold, ShopHoldAction};
 use axum::{
     http::StatusCode,
     response::{IntoResponse, Response},
     Json,
 };
-use kittycat::perms;
 
 pub async fn update_shop_holds(
     state: &AppState,
@@ -16,17 +14,16 @@ pub async fn update_shop_holds(
 ) -> Result<Response, Error> {
     let auth_data = check_auth(&state.pool, &login_token)
         .await
-        .map_err(Error::new)?;
+        .map_err(|e| PanelError::NotAuthorized(e.to_string()))?;
 
-    let user_perms = get_user_perms(&state.pool, &auth_data.user_id)
-        .await
-        .map_err(Error::new)?
-        .resolve();
+    let user_perms = state
+        .get_user_perms(&login_token, &auth_data.user_id)
+        .await?;
 
     match action {
         ShopHoldAction::List => {
             let rows = sqlx::query!(
-            "SELECT id, target_id, target_type, item, created_at, duration FROM shop_holds ORDER BY created_at ASC"
+            "SELECT id, target_id, target_type, item, created_at, duration, created_at + duration AS expires_at FROM shop_holds ORDER BY created_at ASC"
         )
         .fetch_all(&state.pool)
         .await
@@ -51,6 +48,7 @@ pub async fn update_shop_holds(
 
                         micros / 1_000_000
                     }),
+                    expires_at: row.expires_at,
                 });
             }
 
@@ -62,14 +60,7 @@ pub async fn update_shop_holds(
             item,
             duration,
         } => {
-            if !perms::has_perm(&user_perms, &"shop_holds.create".into()) {
-                return Ok((
-                    StatusCode::FORBIDDEN,
-                    "You do not have permission to create shop holds [shop_holds.create]"
-                        .to_string(),
-                )
-                    .into_response());
-            }
+            require_perm(&user_perms, "shop_holds.create")?;
 
             let item_exists = sqlx::query!("SELECT COUNT(*) FROM shop_items WHERE id = $1", item,)
                 .fetch_one(&state.pool)
@@ -80,17 +71,14 @@ pub async fn update_shop_holds(
                 > 0;
 
             if !item_exists {
-                return Ok(
-                    (StatusCode::BAD_REQUEST, "Item does not exist".to_string()).into_response()
-                );
+                return Err(PanelError::BadRequest("Item does not exist".to_string()).into());
             }
 
             if target_type != "bot" && target_type != "server" {
-                return Ok((
-                    StatusCode::BAD_REQUEST,
+                return Err(PanelError::BadRequest(
                     "Target type must be either 'bot' or 'server'".to_string(),
                 )
-                    .into_response());
+                .into());
             }
 
             // Insert entry
@@ -126,14 +114,7 @@ pub async fn update_shop_holds(
             item,
             duration,
         } => {
-            if !perms::has_perm(&user_perms, &"shop_holds.update".into()) {
-                return Ok((
-                    StatusCode::FORBIDDEN,
-                    "You do not have permission to update shop holds [shop_holds.update]"
-                        .to_string(),
-                )
-                    .into_response());
-            }
+            require_perm(&user_perms, "shop_holds.update")?;
 
             // Check if entry already exists with same id
             if sqlx::query!("SELECT COUNT(*) FROM shop_holds WHERE id = $1", id)
@@ -144,11 +125,10 @@ pub async fn update_shop_holds(
                 .unwrap_or(0)
                 == 0
             {
-                return Ok((
-                    StatusCode::BAD_REQUEST,
+                return Err(PanelError::NotFound(
                     "Entry with same id does not already exist".to_string(),
                 )
-                    .into_response());
+                .into());
             }
 
             let item_exists = sqlx::query!("SELECT COUNT(*) FROM shop_items WHERE id = $1", item,)
@@ -160,17 +140,14 @@ pub async fn update_shop_holds(
                 > 0;
 
             if !item_exists {
-                return Ok(
-                    (StatusCode::BAD_REQUEST, "Item does not exist".to_string()).into_response()
-                );
+                return Err(PanelError::BadRequest("Item does not exist".to_string()).into());
             }
 
             if target_type != "bot" && target_type != "server" {
-                return Ok((
-                    StatusCode::BAD_REQUEST,
+                return Err(PanelError::BadRequest(
                     "Target type must be either 'bot' or 'server'".to_string(),
                 )
-                    .into_response());
+                .into());
             }
 
             // Update entry
@@ -197,15 +174,17 @@ pub async fn update_shop_holds(
 
             Ok((StatusCode::NO_CONTENT, "").into_response())
         }
+        ShopHoldAction::ReleaseExpired => {
+            require_perm(&user_perms, "shop_holds.delete")?;
+
+            let released = crate::tasks::shopholds::release_expired_shop_holds(&state.pool)
+                .await
+                .map_err(Error::new)?;
+
+            Ok((StatusCode::OK, released.to_string()).into_response())
+        }
         ShopHoldAction::Delete { id } => {
-            if !perms::has_perm(&user_perms, &"shop_holds.delete".into()) {
-                return Ok((
-                    StatusCode::FORBIDDEN,
-                    "You do not have permission to delete shop holds [shop_holds.delete]"
-                        .to_string(),
-                )
-                    .into_response());
-            }
+            require_perm(&user_perms, "shop_holds.delete")?;
 
             // Check if entry already exists
             if sqlx::query!("SELECT COUNT(*) FROM shop_holds WHERE id = $1", id)
@@ -216,11 +195,10 @@ pub async fn update_shop_holds(
                 .unwrap_or(0)
                 == 0
             {
-                return Ok((
-                    StatusCode::BAD_REQUEST,
+                return Err(PanelError::NotFound(
                     "Entry with same id does not already exist".to_string(),
                 )
-                    .into_response());
+                .into());
             }
 
             // Delete entry
@@ -1,5 +1,5 @@
 use crate::panelapi::auth::{check_auth, get_staff_member};
-use crate::panelapi::core::{AppState, Error};
+use crate::panelapi::core::{require_perm, AppState, Error, PanelError};
 use crate::panelapi::types::staff_members::StaffMemberAction;
 use axum::{
     http::StatusCode,
@@ -15,7 +15,7 @@ pub async fn update_staff_members(
 ) -> Result<Response, Error> {
     let auth_data = check_auth(&state.pool, &login_token)
         .await
-        .map_err(Error::new)?;
+        .map_err(|e| PanelError::NotAuthorized(e.to_string()))?;
 
     match action {
         StaffMemberAction::ListMembers => {
@@ -53,14 +53,10 @@ pub async fn update_staff_members(
                 .await
                 .map_err(Error::new)?;
 
-            if !perms::has_perm(&sm.resolved_perms, &"staff_members.edit".into()) {
-                return Ok((
-                    StatusCode::FORBIDDEN,
-                    "You do not have permission to edit staff members [staff_members.edit]"
-                        .to_string(),
-                )
-                    .into_response());
-            }
+            require_perm(&sm.resolved_perms, "staff_members.edit")?;
+            state
+                .require_step_up(&login_token, "staff_members.edit")
+                .await?;
 
             // Get the lowest index permission of the member
             let mut sm_lowest_index = i32::MAX;
@@ -82,11 +78,10 @@ pub async fn update_staff_members(
 
             // If the target has a lower index than the member, then error
             if sm_target_lowest_index < sm_lowest_index {
-                return Ok((
-                    StatusCode::FORBIDDEN,
+                return Err(PanelError::PermissionMissing(
                     "Target has a lower index than the member".to_string(),
                 )
-                    .into_response());
+                .into());
             }
 
             let perm_overrides = perm_overrides
@@ -106,14 +101,7 @@ pub async fn update_staff_members(
                 &sm_target.resolved_perms,
                 &new_resolved_perms,
             ) {
-                return Ok((
-                    StatusCode::FORBIDDEN,
-                    format!(
-                        "You do not have permission to edit the following perms: {}",
-                        e
-                    ),
-                )
-                    .into_response());
+                return Err(PanelError::PermissionMissing(e.to_string()).into());
             }
 
             // Then update
@@ -140,6 +128,9 @@ pub async fn update_staff_members(
 
             tx.commit().await.map_err(Error::new)?;
 
+            // Staff permissions may have changed
+            state.perms_cache.invalidate_all();
+
             Ok((StatusCode::NO_CONTENT, "").into_response())
         }
     }
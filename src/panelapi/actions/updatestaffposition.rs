@@ -1,9 +1,10 @@
 use std::str::FromStr;
 
 use crate::panelapi::auth::{check_auth, get_staff_member};
-use crate::panelapi::core::{AppState, Error};
+use crate::panelapi::core::{require_perm, AppState, Error, PanelError};
 use crate::panelapi::types::staff_positions::{
-    CorrespondingServer, StaffPosition, StaffPositionAction,
+    CorrespondingServer, PermissionSource, PermissionSourceKind, ResolvedPermission,
+    ResolvedPermissions, StaffPosition, StaffPositionAction,
 };
 use axum::{
     http::StatusCode,
@@ -14,6 +15,43 @@ use kittycat::perms::{self, Permission};
 use serenity::all::RoleId;
 use strum::VariantNames;
 
+/// Returns all staff positions ordered by index
+async fn fetch_positions(pool: &sqlx::PgPool) -> Result<Vec<StaffPosition>, Error> {
+    let pos = sqlx::query!("SELECT id, name, role_id, perms, corresponding_roles, icon, index, created_at FROM staff_positions ORDER BY index ASC")
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Error while getting staff positions {}", e))
+        .map_err(Error::new)?;
+
+    let mut positions = Vec::new();
+
+    for position_data in pos {
+        positions.push(StaffPosition {
+            id: position_data.id.hyphenated().to_string(),
+            name: position_data.name,
+            role_id: position_data.role_id,
+            perms: position_data.perms,
+            corresponding_roles: serde_json::from_value(position_data.corresponding_roles)
+                .map_err(Error::new)?,
+            icon: position_data.icon,
+            index: position_data.index,
+            created_at: position_data.created_at,
+        });
+    }
+
+    Ok(positions)
+}
+
+/// Locks staff_positions for the rest of the transaction so concurrent reorders cannot interleave and collide
+async fn lock_positions(tx: &mut sqlx::PgConnection) -> Result<(), Error> {
+    sqlx::query!("LOCK TABLE staff_positions IN SHARE ROW EXCLUSIVE MODE")
+        .execute(tx)
+        .await
+        .map_err(Error::new)?;
+
+    Ok(())
+}
+
 pub async fn update_staff_position(
     state: &AppState,
     login_token: String,
@@ -21,31 +59,11 @@ pub async fn update_staff_position(
 ) -> Result<Response, Error> {
     let auth_data = check_auth(&state.pool, &login_token)
         .await
-        .map_err(Error::new)?;
+        .map_err(|e| PanelError::NotAuthorized(e.to_string()))?;
 
     match action {
         StaffPositionAction::ListPositions => {
-            let pos = sqlx::query!("SELECT id, name, role_id, perms, corresponding_roles, icon, index, created_at FROM staff_positions ORDER BY index ASC")
-        .fetch_all(&state.pool)
-        .await
-        .map_err(|e| format!("Error while getting staff positions {}", e))
-        .map_err(Error::new)?;
-
-            let mut positions = Vec::new();
-
-            for position_data in pos {
-                positions.push(StaffPosition {
-                    id: position_data.id.hyphenated().to_string(),
-                    name: position_data.name,
-                    role_id: position_data.role_id,
-                    perms: position_data.perms,
-                    corresponding_roles: serde_json::from_value(position_data.corresponding_roles)
-                        .map_err(Error::new)?,
-                    icon: position_data.icon,
-                    index: position_data.index,
-                    created_at: position_data.created_at,
-                });
-            }
+            let positions = fetch_positions(&state.pool).await?;
 
             Ok((StatusCode::OK, Json(positions)).into_response())
         }
@@ -55,13 +73,10 @@ pub async fn update_staff_position(
                 .await
                 .map_err(Error::new)?;
 
-            if !perms::has_perm(&sm.resolved_perms, &"staff_positions.swap_index".into()) {
-                return Ok((
-                StatusCode::FORBIDDEN,
-                "You do not have permission to swap indexes of staff positions [staff_positions.swap_index]".to_string(),
-            )
-                .into_response());
-            }
+            require_perm(&sm.resolved_perms, "staff_positions.swap_index")?;
+            state
+                .require_step_up(&login_token, "staff_positions.swap_index")
+                .await?;
 
             // Get the lowest index permission of the member
             let mut sm_lowest_index = i32::MAX;
@@ -74,36 +89,35 @@ pub async fn update_staff_position(
 
             let mut tx = state.pool.begin().await.map_err(Error::new)?;
 
+            lock_positions(&mut *tx).await?;
+
             let index_a = sqlx::query!("SELECT index FROM staff_positions WHERE id::text = $1", a)
-                .fetch_one(&mut *tx)
+                .fetch_optional(&mut *tx)
                 .await
-                .map_err(|e| format!("Error while getting lower position {}", e))
                 .map_err(Error::new)?
+                .ok_or_else(|| PanelError::NotFound("Position does not exist".to_string()))?
                 .index;
 
             // Get the higher staff positions index
             let index_b = sqlx::query!("SELECT index FROM staff_positions WHERE id::text = $1", b)
-                .fetch_one(&mut *tx)
+                .fetch_optional(&mut *tx)
                 .await
-                .map_err(|e| format!("Error while getting higher position {}", e))
                 .map_err(Error::new)?
+                .ok_or_else(|| PanelError::NotFound("Position does not exist".to_string()))?
                 .index;
 
             if index_a == index_b {
-                return Ok((
-                    StatusCode::BAD_REQUEST,
-                    "Positions have the same index".to_string(),
-                )
-                    .into_response());
+                return Err(
+                    PanelError::BadRequest("Positions have the same index".to_string()).into(),
+                );
             }
 
             // If either a or b is lower than the lowest index of the member, then error
             if index_a <= sm_lowest_index || index_b <= sm_lowest_index {
-                return Ok((
-                    StatusCode::FORBIDDEN,
+                return Err(PanelError::PermissionMissing(
                     "Either 'a' or 'b' is lower than the lowest index of the member".to_string(),
                 )
-                    .into_response());
+                .into());
             }
 
             // Swap the indexes
@@ -129,7 +143,12 @@ pub async fn update_staff_position(
 
             tx.commit().await.map_err(Error::new)?;
 
-            Ok((StatusCode::NO_CONTENT, "").into_response())
+            // Staff permissions may have changed
+            state.perms_cache.invalidate_all();
+
+            let positions = fetch_positions(&state.pool).await?;
+
+            Ok((StatusCode::OK, Json(positions)).into_response())
         }
         StaffPositionAction::SetIndex { id, index } => {
             let uuid = sqlx::types::uuid::Uuid::parse_str(&id).map_err(Error::new)?;
@@ -139,20 +158,15 @@ pub async fn update_staff_position(
                 .await
                 .map_err(Error::new)?;
 
-            if !perms::has_perm(&sm.resolved_perms, &"staff_positions.set_index".into()) {
-                return Ok((
-                StatusCode::FORBIDDEN,
-                "You do not have permission to set the indexes of staff positions [staff_positions.set_index]".to_string(),
-            )
-                .into_response());
-            }
+            require_perm(&sm.resolved_perms, "staff_positions.set_index")?;
+            state
+                .require_step_up(&login_token, "staff_positions.set_index")
+                .await?;
 
             if index < 0 {
-                return Ok((
-                    StatusCode::BAD_REQUEST,
-                    "Index cannot be lower than 0".to_string(),
-                )
-                    .into_response());
+                return Err(
+                    PanelError::BadRequest("Index cannot be lower than 0".to_string()).into(),
+                );
             }
 
             // Get the lowest index permission of the member
@@ -165,30 +179,27 @@ pub async fn update_staff_position(
             }
 
             if index <= sm_lowest_index {
-                return Ok((
-                    StatusCode::FORBIDDEN,
+                return Err(PanelError::PermissionMissing(
                     "Index to set is lower than or equal to the lowest index of the staff member"
                         .to_string(),
                 )
-                    .into_response());
+                .into());
             }
 
             let mut tx = state.pool.begin().await.map_err(Error::new)?;
 
+            lock_positions(&mut *tx).await?;
+
             let curr_index = sqlx::query!("SELECT index FROM staff_positions WHERE id = $1", uuid)
-                .fetch_one(&mut *tx)
+                .fetch_optional(&mut *tx)
                 .await
-                .map_err(|e| format!("Error while getting position {}", e))
                 .map_err(Error::new)?
+                .ok_or_else(|| PanelError::NotFound("Position does not exist".to_string()))?
                 .index;
 
             // If the current index is lower than the lowest index of the member, then error
             if curr_index <= sm_lowest_index {
-                return Ok((
-                StatusCode::FORBIDDEN,
-                "Current index of position is lower than or equal to the lowest index of the staff member".to_string(),
-            )
-                .into_response());
+                return Err(PanelError::PermissionMissing("Current index of position is lower than or equal to the lowest index of the staff member".to_string()).into());
             }
 
             // Shift indexes one lower
@@ -214,7 +225,12 @@ pub async fn update_staff_position(
 
             tx.commit().await.map_err(Error::new)?;
 
-            Ok((StatusCode::NO_CONTENT, "").into_response())
+            // Staff permissions may have changed
+            state.perms_cache.invalidate_all();
+
+            let positions = fetch_positions(&state.pool).await?;
+
+            Ok((StatusCode::OK, Json(positions)).into_response())
         }
         StaffPositionAction::CreatePosition {
             name,
@@ -229,21 +245,15 @@ pub async fn update_staff_position(
                 .await
                 .map_err(Error::new)?;
 
-            if !perms::has_perm(&sm.resolved_perms, &"staff_positions.create".into()) {
-                return Ok((
-                    StatusCode::FORBIDDEN,
-                    "You do not have permission to create staff positions [staff_positions.create]"
-                        .to_string(),
-                )
-                    .into_response());
-            }
+            require_perm(&sm.resolved_perms, "staff_positions.create")?;
+            state
+                .require_step_up(&login_token, "staff_positions.create")
+                .await?;
 
             if index < 0 {
-                return Ok((
-                    StatusCode::BAD_REQUEST,
-                    "Index cannot be lower than 0".to_string(),
-                )
-                    .into_response());
+                return Err(
+                    PanelError::BadRequest("Index cannot be lower than 0".to_string()).into(),
+                );
             }
 
             // Get the lowest index permission of the member
@@ -256,12 +266,11 @@ pub async fn update_staff_position(
             }
 
             if index <= sm_lowest_index {
-                return Ok((
-                    StatusCode::FORBIDDEN,
+                return Err(PanelError::PermissionMissing(
                     "Index is lower than or equal to the lowest index of the staff member"
                         .to_string(),
                 )
-                    .into_response());
+                .into());
             }
 
             // Shift indexes one lower
@@ -291,25 +300,21 @@ pub async fn update_staff_position(
             };
 
             if !role_exists {
-                return Ok((
-                    StatusCode::BAD_REQUEST,
+                return Err(PanelError::BadRequest(
                     "Role does not exist on the staff server".to_string(),
                 )
-                    .into_response());
+                .into());
             }
 
             // Ensure all corresponding_roles exist on the named server if
             for role in corresponding_roles.iter() {
                 let Ok(corr_server) = CorrespondingServer::from_str(&role.name) else {
-                    return Ok((
-                        StatusCode::BAD_REQUEST,
-                        format!(
-                            "Server {} is not a supported corresponding role. Supported: {:#?}",
-                            role.name,
-                            CorrespondingServer::VARIANTS
-                        ),
-                    )
-                        .into_response());
+                    return Err(PanelError::BadRequest(format!(
+                        "Server {} is not a supported corresponding role. Supported: {:#?}",
+                        role.name,
+                        CorrespondingServer::VARIANTS
+                    ))
+                    .into());
                 };
                 let role_id_snow = role.value.parse::<RoleId>().map_err(Error::new)?;
 
@@ -324,15 +329,12 @@ pub async fn update_staff_position(
                 };
 
                 if !role_exists {
-                    return Ok((
-                        StatusCode::BAD_REQUEST,
-                        format!(
-                            "Role {} does not exist on the server {}",
-                            role_id_snow,
-                            corr_server.get_id()
-                        ),
-                    )
-                        .into_response());
+                    return Err(PanelError::BadRequest(format!(
+                        "Role {} does not exist on the server {}",
+                        role_id_snow,
+                        corr_server.get_id()
+                    ))
+                    .into());
                 }
             }
 
@@ -353,6 +355,9 @@ pub async fn update_staff_position(
 
             tx.commit().await.map_err(Error::new)?;
 
+            // Staff permissions may have changed
+            state.perms_cache.invalidate_all();
+
             Ok((StatusCode::NO_CONTENT, "").into_response())
         }
         StaffPositionAction::EditPosition {
@@ -370,14 +375,10 @@ pub async fn update_staff_position(
                 .await
                 .map_err(Error::new)?;
 
-            if !perms::has_perm(&sm.resolved_perms, &"staff_positions.edit".into()) {
-                return Ok((
-                    StatusCode::FORBIDDEN,
-                    "You do not have permission to edit staff positions [staff_positions.edit]"
-                        .to_string(),
-                )
-                    .into_response());
-            }
+            require_perm(&sm.resolved_perms, "staff_positions.edit")?;
+            state
+                .require_step_up(&login_token, "staff_positions.edit")
+                .await?;
 
             // Get the lowest index permission of the member
             let mut sm_lowest_index = i32::MAX;
@@ -395,18 +396,17 @@ pub async fn update_staff_position(
                 "SELECT perms, index, role_id FROM staff_positions WHERE id = $1 FOR UPDATE",
                 uuid
             )
-            .fetch_one(&mut *tx)
+            .fetch_optional(&mut *tx)
             .await
-            .map_err(|e| format!("Error while getting position {}", e))
-            .map_err(Error::new)?;
+            .map_err(Error::new)?
+            .ok_or_else(|| PanelError::NotFound("Position does not exist".to_string()))?;
 
             // If the index is lower than the lowest index of the member, then error
             if index.index <= sm_lowest_index {
-                return Ok((
-                    StatusCode::FORBIDDEN,
+                return Err(PanelError::PermissionMissing(
                     "Index is lower than the lowest index of the member".to_string(),
                 )
-                    .into_response());
+                .into());
             }
 
             // Check perms
@@ -422,14 +422,7 @@ pub async fn update_staff_position(
                     .map(|x| Permission::from_string(x))
                     .collect::<Vec<Permission>>(),
             ) {
-                return Ok((
-                    StatusCode::FORBIDDEN,
-                    format!(
-                        "You do not have permission to edit the following perms: {}",
-                        e
-                    ),
-                )
-                    .into_response());
+                return Err(PanelError::PermissionMissing(e.to_string()).into());
             }
 
             // Ensure role id exists on the staff server
@@ -448,25 +441,21 @@ pub async fn update_staff_position(
             };
 
             if !role_exists {
-                return Ok((
-                    StatusCode::BAD_REQUEST,
+                return Err(PanelError::BadRequest(
                     "Role does not exist on the staff server".to_string(),
                 )
-                    .into_response());
+                .into());
             }
 
             // Ensure all corresponding_roles exist on the named server if
             for role in corresponding_roles.iter() {
                 let Ok(corr_server) = CorrespondingServer::from_str(&role.name) else {
-                    return Ok((
-                        StatusCode::BAD_REQUEST,
-                        format!(
-                            "Server {} is not a supported corresponding role. Supported: {:#?}",
-                            role.name,
-                            CorrespondingServer::VARIANTS
-                        ),
-                    )
-                        .into_response());
+                    return Err(PanelError::BadRequest(format!(
+                        "Server {} is not a supported corresponding role. Supported: {:#?}",
+                        role.name,
+                        CorrespondingServer::VARIANTS
+                    ))
+                    .into());
                 };
                 let role_id_snow = role.value.parse::<RoleId>().map_err(Error::new)?;
 
@@ -481,15 +470,12 @@ pub async fn update_staff_position(
                 };
 
                 if !role_exists {
-                    return Ok((
-                        StatusCode::BAD_REQUEST,
-                        format!(
-                            "Role {} does not exist on the server {}",
-                            role_id_snow,
-                            corr_server.get_id()
-                        ),
-                    )
-                        .into_response());
+                    return Err(PanelError::BadRequest(format!(
+                        "Role {} does not exist on the server {}",
+                        role_id_snow,
+                        corr_server.get_id()
+                    ))
+                    .into());
                 }
             }
 
@@ -510,8 +496,93 @@ pub async fn update_staff_position(
 
             tx.commit().await.map_err(Error::new)?;
 
+            // Staff permissions may have changed
+            state.perms_cache.invalidate_all();
+
             Ok((StatusCode::NO_CONTENT, "").into_response())
         }
+        StaffPositionAction::ResolvePermissions { user_id } => {
+            let sm = get_staff_member(&state.pool, &state.cache_http, &user_id)
+                .await
+                .map_err(|e| PanelError::NotFound(e.to_string()))?;
+
+            // Sources in priority order, the first one granting a permission is the one that decides it
+            let mut sources = Vec::new();
+
+            if !sm.perm_overrides.is_empty() {
+                sources.push((
+                    PermissionSource {
+                        kind: PermissionSourceKind::Override,
+                        id: String::new(),
+                        name: "Permission overrides".to_string(),
+                        index: None,
+                    },
+                    sm.perm_overrides.clone(),
+                ));
+            }
+
+            for disc in &sm.disciplinaries {
+                sources.push((
+                    PermissionSource {
+                        kind: PermissionSourceKind::Disciplinary,
+                        id: disc.id.clone(),
+                        name: disc.r#type.name.clone(),
+                        index: None,
+                    },
+                    disc.r#type.perm_limits.clone(),
+                ));
+            }
+
+            let mut positions = sm.positions.clone();
+            positions.sort_by_key(|p| p.index);
+
+            for position in positions {
+                sources.push((
+                    PermissionSource {
+                        kind: PermissionSourceKind::Position,
+                        id: position.id,
+                        name: position.name,
+                        index: Some(position.index),
+                    },
+                    position.perms,
+                ));
+            }
+
+            let sources = sources
+                .into_iter()
+                .map(|(source, source_perms)| {
+                    (
+                        source,
+                        source_perms
+                            .iter()
+                            .map(|p| Permission::from_string(p))
+                            .collect::<Vec<_>>(),
+                    )
+                })
+                .collect::<Vec<_>>();
+
+            let resolved = sm
+                .resolved_perms
+                .iter()
+                .map(|perm| ResolvedPermission {
+                    perm: perm.to_string(),
+                    sources: sources
+                        .iter()
+                        .filter(|(_, source_perms)| perms::has_perm(source_perms, perm))
+                        .map(|(source, _)| source.clone())
+                        .collect(),
+                })
+                .collect::<Vec<_>>();
+
+            Ok((
+                StatusCode::OK,
+                Json(ResolvedPermissions {
+                    user_id,
+                    perms: resolved,
+                }),
+            )
+                .into_response())
+        }
         StaffPositionAction::DeletePosition { id } => {
             let uuid = sqlx::types::uuid::Uuid::parse_str(&id).map_err(Error::new)?;
 
@@ -520,14 +591,10 @@ pub async fn update_staff_position(
                 .await
                 .map_err(Error::new)?;
 
-            if !perms::has_perm(&sm.resolved_perms, &"staff_positions.delete".into()) {
-                return Ok((
-                    StatusCode::FORBIDDEN,
-                    "You do not have permission to delete staff positions [staff_positions.delete]"
-                        .to_string(),
-                )
-                    .into_response());
-            }
+            require_perm(&sm.resolved_perms, "staff_positions.delete")?;
+            state
+                .require_step_up(&login_token, "staff_positions.delete")
+                .await?;
 
             // Get the lowest index permission of the member
             let mut sm_lowest_index = i32::MAX;
@@ -545,18 +612,17 @@ pub async fn update_staff_position(
                 "SELECT perms, index, role_id FROM staff_positions WHERE id = $1 FOR UPDATE",
                 uuid
             )
-            .fetch_one(&mut *tx)
+            .fetch_optional(&mut *tx)
             .await
-            .map_err(|e| format!("Error while getting position {}", e))
-            .map_err(Error::new)?;
+            .map_err(Error::new)?
+            .ok_or_else(|| PanelError::NotFound("Position does not exist".to_string()))?;
 
             // If the index is lower than the lowest index of the member, then error
             if index.index <= sm_lowest_index {
-                return Ok((
-                    StatusCode::FORBIDDEN,
+                return Err(PanelError::PermissionMissing(
                     "Index is lower than the lowest index of the member".to_string(),
                 )
-                    .into_response());
+                .into());
             }
 
             // Check perms
@@ -569,11 +635,7 @@ pub async fn update_staff_position(
                     .collect::<Vec<Permission>>(),
                 &Vec::new(),
             ) {
-                return Ok((
-                StatusCode::FORBIDDEN,
-                format!("You do not have permission to edit the following perms [neeed to delete position]: {}", e),
-            )
-                .into_response());
+                return Err(PanelError::PermissionMissing(e.to_string()).into());
             }
 
             // Delete the position
@@ -595,6 +657,9 @@ pub async fn update_staff_position(
 
             tx.commit().await.map_err(Error::new)?;
 
+            // Staff permissions may have changed
+            state.perms_cache.invalidate_all();
+
             Ok((StatusCode::NO_CONTENT, "").into_response())
         }
     }
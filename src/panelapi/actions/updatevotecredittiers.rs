@@ -1,13 +1,115 @@
-use crate::impls::utils::get_user_perms;
+use std::collections::HashMap;
+
 use crate::panelapi::auth::check_auth;
-use crate::panelapi::core::{AppState, Error};
-use crate::panelapi::types::vote_credit_tiers::{VoteCreditTier, VoteCreditTierAction};
+use crate::panelapi::core::{require_perm, AppState, Error, PanelError};
+use crate::panelapi::types::vote_credit_tiers::{
+    VoteCreditBulkSimulation, VoteCreditSimulation, VoteCreditTier, VoteCreditTierAction,
+    VoteCreditTierIssue,
+};
 use axum::{
     http::StatusCode,
     response::{IntoResponse, Response},
     Json,
 };
-use kittycat::perms;
+
+/// Returns any overlaps or gaps in a set of tiers
+///
+/// Tiers are slabs applied in order of position per target type, so two tiers at the same position
+/// overlap and a tier with no votes (other than the last one) is never reached
+pub fn find_tier_issues(tiers: &[VoteCreditTier]) -> Vec<VoteCreditTierIssue> {
+    let mut by_target_type: HashMap<&str, Vec<&VoteCreditTier>> = HashMap::new();
+
+    for tier in tiers {
+        by_target_type
+            .entry(tier.target_type.as_str())
+            .or_default()
+            .push(tier);
+    }
+
+    let mut issues = Vec::new();
+
+    for (target_type, mut tiers) in by_target_type {
+        tiers.sort_by_key(|t| t.position);
+
+        for pair in tiers.windows(2) {
+            if pair[0].position == pair[1].position {
+                issues.push(VoteCreditTierIssue {
+                    target_type: target_type.to_string(),
+                    tier_ids: vec![pair[0].id.clone(), pair[1].id.clone()],
+                    reason: format!("Tiers overlap at position {}", pair[0].position),
+                });
+            }
+        }
+
+        for tier in tiers.iter().take(tiers.len().saturating_sub(1)) {
+            if tier.votes == 0 {
+                issues.push(VoteCreditTierIssue {
+                    target_type: target_type.to_string(),
+                    tier_ids: vec![tier.id.clone()],
+                    reason: format!(
+                        "Tier at position {} has no votes and would never be used",
+                        tier.position
+                    ),
+                });
+            }
+        }
+    }
+
+    issues
+}
+
+/// Returns the cents awarded for `votes` given `(votes, cents)` slabs in tier order
+///
+/// Votes past the last slab are paid at the rate of the last tier, see `VoteCreditTier`
+pub fn calculate_credits(slabs: &[(i32, f64)], votes: i64) -> f64 {
+    let mut remaining = votes.max(0);
+    let mut cents = 0.0;
+
+    for (slab_votes, slab_cents) in slabs {
+        let used = remaining.min(*slab_votes as i64);
+        cents += used as f64 * slab_cents;
+        remaining -= used;
+    }
+
+    if let Some((_, last_cents)) = slabs.last() {
+        cents += remaining as f64 * last_cents;
+    }
+
+    cents
+}
+
+/// Returns the `(votes, cents)` slabs of a target type from a set of tiers
+fn slabs_of(tiers: &[VoteCreditTier], target_type: &str) -> Vec<(i32, f64)> {
+    let mut tiers = tiers
+        .iter()
+        .filter(|t| t.target_type == target_type)
+        .collect::<Vec<_>>();
+
+    tiers.sort_by_key(|t| t.position);
+
+    tiers.iter().map(|t| (t.votes, t.cents)).collect()
+}
+
+/// Fetches all tiers, ordered by position
+async fn fetch_tiers(conn: &mut sqlx::PgConnection) -> Result<Vec<VoteCreditTier>, crate::Error> {
+    let rows = sqlx::query!(
+        "SELECT id, target_type, position, cents, votes, created_at FROM vote_credit_tiers ORDER BY position ASC"
+    )
+    .fetch_all(conn)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| VoteCreditTier {
+            id: row.id,
+            target_type: row.target_type,
+            position: row.position,
+            cents: row.cents,
+            votes: row.votes,
+            created_at: row.created_at,
+        })
+        .collect())
+}
 
 pub async fn update_vote_credit_tiers(
     state: &AppState,
@@ -16,12 +118,11 @@ pub async fn update_vote_credit_tiers(
 ) -> Result<Response, Error> {
     let auth_data = check_auth(&state.pool, &login_token)
         .await
-        .map_err(Error::new)?;
+        .map_err(|e| PanelError::NotAuthorized(e.to_string()))?;
 
-    let user_perms = get_user_perms(&state.pool, &auth_data.user_id)
-        .await
-        .map_err(Error::new)?
-        .resolve();
+    let user_perms = state
+        .get_user_perms(&login_token, &auth_data.user_id)
+        .await?;
 
     match action {
         VoteCreditTierAction::ListTiers => {
@@ -47,6 +148,72 @@ pub async fn update_vote_credit_tiers(
 
             Ok((StatusCode::OK, Json(entries)).into_response())
         }
+        VoteCreditTierAction::Simulate { target_type, votes } => {
+            let mut conn = state.pool.acquire().await.map_err(Error::new)?;
+            let tiers = fetch_tiers(&mut *conn).await.map_err(Error::new)?;
+
+            Ok((
+                StatusCode::OK,
+                Json(VoteCreditSimulation {
+                    votes,
+                    cents: calculate_credits(&slabs_of(&tiers, &target_type), votes as i64),
+                }),
+            )
+                .into_response())
+        }
+        VoteCreditTierAction::SimulateProposed { target_type, tiers } => {
+            let votes = match target_type.as_str() {
+                "bot" => sqlx::query!("SELECT approximate_votes FROM bots")
+                    .fetch_all(&state.pool)
+                    .await
+                    .map_err(Error::new)?
+                    .into_iter()
+                    .map(|r| r.approximate_votes)
+                    .collect::<Vec<_>>(),
+                "server" => sqlx::query!("SELECT approximate_votes FROM servers")
+                    .fetch_all(&state.pool)
+                    .await
+                    .map_err(Error::new)?
+                    .into_iter()
+                    .map(|r| r.approximate_votes)
+                    .collect::<Vec<_>>(),
+                _ => {
+                    return Err(PanelError::BadRequest(
+                        "Target type must be either 'bot' or 'server'".to_string(),
+                    )
+                    .into());
+                }
+            };
+
+            let mut conn = state.pool.acquire().await.map_err(Error::new)?;
+            let current_slabs = slabs_of(
+                &fetch_tiers(&mut *conn).await.map_err(Error::new)?,
+                &target_type,
+            );
+            let proposed_slabs = tiers.iter().map(|t| (t.votes, t.cents)).collect::<Vec<_>>();
+
+            Ok((
+                StatusCode::OK,
+                Json(VoteCreditBulkSimulation {
+                    entities: votes.len() as i64,
+                    current_cents: votes
+                        .iter()
+                        .map(|v| calculate_credits(&current_slabs, *v as i64))
+                        .sum(),
+                    proposed_cents: votes
+                        .iter()
+                        .map(|v| calculate_credits(&proposed_slabs, *v as i64))
+                        .sum(),
+                }),
+            )
+                .into_response())
+        }
+        VoteCreditTierAction::Validate => {
+            let mut conn = state.pool.acquire().await.map_err(Error::new)?;
+            let tiers = fetch_tiers(&mut *conn).await.map_err(Error::new)?;
+
+            Ok((StatusCode::OK, Json(find_tier_issues(&tiers))).into_response())
+        }
         VoteCreditTierAction::CreateTier {
             id,
             position,
@@ -54,36 +221,25 @@ pub async fn update_vote_credit_tiers(
             cents,
             votes,
         } => {
-            if !perms::has_perm(&user_perms, &"vote_credit_tiers.create".into()) {
-                return Ok((
-                StatusCode::FORBIDDEN,
-                "You do not have permission to create vote credit tiers [vote_credit_tiers.create]".to_string(),
-            )
-                .into_response());
-            }
+            require_perm(&user_perms, "vote_credit_tiers.create")?;
 
             if cents < 0.0 {
-                return Ok((
-                    StatusCode::BAD_REQUEST,
-                    "Cents cannot be lower than 0".to_string(),
-                )
-                    .into_response());
+                return Err(
+                    PanelError::BadRequest("Cents cannot be lower than 0".to_string()).into(),
+                );
             }
 
             if votes < 0 {
-                return Ok((
-                    StatusCode::BAD_REQUEST,
-                    "Votes cannot be lower than 0".to_string(),
-                )
-                    .into_response());
+                return Err(
+                    PanelError::BadRequest("Votes cannot be lower than 0".to_string()).into(),
+                );
             }
 
             if target_type != "bot" && target_type != "server" {
-                return Ok((
-                    StatusCode::BAD_REQUEST,
+                return Err(PanelError::BadRequest(
                     "Target type must be either 'bot' or 'server'".to_string(),
                 )
-                    .into_response());
+                .into());
             }
 
             // Insert entry
@@ -135,6 +291,13 @@ pub async fn update_vote_credit_tiers(
                 index_a = index_b;
             }
 
+            // Reject the change (rolling back the transaction) if it would leave the tiers inconsistent
+            let issues = find_tier_issues(&fetch_tiers(&mut *tx).await.map_err(Error::new)?);
+
+            if !issues.is_empty() {
+                return Ok((StatusCode::BAD_REQUEST, Json(issues)).into_response());
+            }
+
             tx.commit().await.map_err(Error::new)?;
 
             Ok((StatusCode::NO_CONTENT, "").into_response())
@@ -146,13 +309,7 @@ pub async fn update_vote_credit_tiers(
             cents,
             votes,
         } => {
-            if !perms::has_perm(&user_perms, &"vote_credit_tiers.update".into()) {
-                return Ok((
-                StatusCode::FORBIDDEN,
-                "You do not have permission to update vote credit tiers [vote_credit_tiers.update]".to_string(),
-            )
-                .into_response());
-            }
+            require_perm(&user_perms, "vote_credit_tiers.update")?;
 
             // Check if entry already exists with same id
             if sqlx::query!("SELECT COUNT(*) FROM vote_credit_tiers WHERE id = $1", id)
@@ -163,35 +320,29 @@ pub async fn update_vote_credit_tiers(
                 .unwrap_or(0)
                 == 0
             {
-                return Ok((
-                    StatusCode::BAD_REQUEST,
+                return Err(PanelError::NotFound(
                     "Entry with same id does not already exist".to_string(),
                 )
-                    .into_response());
+                .into());
             }
 
             if cents < 0.0 {
-                return Ok((
-                    StatusCode::BAD_REQUEST,
-                    "Cents cannot be lower than 0".to_string(),
-                )
-                    .into_response());
+                return Err(
+                    PanelError::BadRequest("Cents cannot be lower than 0".to_string()).into(),
+                );
             }
 
             if votes < 0 {
-                return Ok((
-                    StatusCode::BAD_REQUEST,
-                    "Votes cannot be lower than 0".to_string(),
-                )
-                    .into_response());
+                return Err(
+                    PanelError::BadRequest("Votes cannot be lower than 0".to_string()).into(),
+                );
             }
 
             if target_type != "bot" && target_type != "server" {
-                return Ok((
-                    StatusCode::BAD_REQUEST,
+                return Err(PanelError::BadRequest(
                     "Target type must be either 'bot' or 'server'".to_string(),
                 )
-                    .into_response());
+                .into());
             }
 
             let mut tx = state.pool.begin().await.map_err(Error::new)?;
@@ -244,18 +395,19 @@ pub async fn update_vote_credit_tiers(
                 index_a = index_b;
             }
 
+            // Reject the change (rolling back the transaction) if it would leave the tiers inconsistent
+            let issues = find_tier_issues(&fetch_tiers(&mut *tx).await.map_err(Error::new)?);
+
+            if !issues.is_empty() {
+                return Ok((StatusCode::BAD_REQUEST, Json(issues)).into_response());
+            }
+
             tx.commit().await.map_err(Error::new)?;
 
             Ok((StatusCode::NO_CONTENT, "").into_response())
         }
         VoteCreditTierAction::DeleteTier { id } => {
-            if !perms::has_perm(&user_perms, &"vote_credit_tiers.delete".into()) {
-                return Ok((
-                StatusCode::FORBIDDEN,
-                "You do not have permission to delete vote credit tiers [vote_credit_tiers.delete]".to_string(),
-            )
-                .into_response());
-            }
+            require_perm(&user_perms, "vote_credit_tiers.delete")?;
 
             // Check if entry already exists with same vesion
             if sqlx::query!("SELECT COUNT(*) FROM vote_credit_tiers WHERE id = $1", id)
@@ -266,11 +418,10 @@ pub async fn update_vote_credit_tiers(
                 .unwrap_or(0)
                 == 0
             {
-                return Ok((
-                    StatusCode::BAD_REQUEST,
+                return Err(PanelError::NotFound(
                     "Entry with same id does not already exist".to_string(),
                 )
-                    .into_response());
+                .into());
             }
 
             // Delete entry
@@ -1,30 +1,534 @@
 use axum::{
-    http::StatusCode,
+    http::{header, HeaderMap, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
+    Json,
 };
+use std::collections::HashMap;
 use std::fmt::Display;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::config::CdnScopeData;
+use crate::impls::queue_events::QueueEvent;
+use crate::impls::utils::get_user_perms;
+use crate::panelapi::auth::check_auth;
+use crate::panelapi::types::analytics::BaseAnalytics;
+use crate::panelapi::types::auth::AuthData;
+use crate::panelapi::types::rpc::RPCConfirmation;
+use crate::panelapi::types::webcore::InstanceConfig;
+use crate::rpc::core::{RPCSuccess, RPC_CONFIRMATION_TTL};
+use kittycat::perms::{self, Permission};
+
+/// How long resolved permissions are cached for a login token
+pub const PERMS_CACHE_TTL: Duration = Duration::from_secs(30);
+
+/// How long the instance config is cached before being reread from the database
+pub const INSTANCE_CONFIG_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// How long base analytics are cached for
+pub const BASE_ANALYTICS_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// How long the result of a request with an idempotency key is kept for replays
+pub const IDEMPOTENCY_KEY_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Maximum number of idempotency keys kept at once, the least used ones are evicted first
+pub const MAX_IDEMPOTENCY_KEYS: u64 = 10_000;
+
+/// How long an idempotency key stays reserved by a request that never finished (for example because
+/// the client disconnected) before another request with it may run
+pub const IDEMPOTENCY_RESERVATION_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Window over which login attempts from a single IP are counted
+pub const LOGIN_RATELIMIT_WINDOW: Duration = Duration::from_secs(600);
+
+/// Maximum number of failed logins from a single IP per LOGIN_RATELIMIT_WINDOW
+pub const MAX_FAILED_LOGINS: u32 = 5;
+
+/// Maximum number of successful logins from a single IP per LOGIN_RATELIMIT_WINDOW
+pub const MAX_SUCCESSFUL_LOGINS: u32 = 20;
+
+/// Number of days of reviews used to estimate bot queue wait times
+pub const QUEUE_THROUGHPUT_WINDOW_DAYS: i32 = 14;
 
 pub struct Error {
     pub status: StatusCode,
     pub message: String,
+    /// Stable error code, returned in the `X-Error-Code` header
+    pub code: &'static str,
+    /// Seconds until the request can be retried, returned in the `Retry-After` header
+    pub retry_after: Option<u64>,
 }
 
 impl Error {
+    /// Creates an internal error, use `PanelError` for errors caused by the client
     pub fn new(e: impl Display) -> Self {
         Self {
             status: StatusCode::INTERNAL_SERVER_ERROR,
             message: e.to_string(),
+            code: "internalError",
+            retry_after: None,
         }
     }
 }
 
 impl IntoResponse for Error {
     fn into_response(self) -> Response {
-        (self.status, self.message).into_response()
+        let mut resp = (self.status, [("X-Error-Code", self.code)], self.message).into_response();
+
+        if let Some(retry_after) = self.retry_after {
+            resp.headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+        }
+
+        resp
+    }
+}
+
+/// Typed panel errors, these are converted into an `Error` with the right status code
+pub enum PanelError {
+    /// The login token is invalid or the session is not active
+    NotAuthorized(String),
+    /// The request is invalid
+    BadRequest(String),
+    /// The requested resource does not exist
+    NotFound(String),
+    /// The user is missing the given permission
+    PermissionMissing(String),
+    /// The action needs a fresh MFA check, the client should re-run `ActivateSession`
+    StepUpRequired,
+    /// Too many requests were made, the value is the number of seconds until the client can retry
+    RateLimited(u64),
+    /// The entity was changed by someone else since the client read it, the client should reload and try again
+    StaleWrite,
+    /// Something went wrong on our end
+    Internal(String),
+}
+
+impl PanelError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            PanelError::NotAuthorized(_) => StatusCode::UNAUTHORIZED,
+            PanelError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            PanelError::NotFound(_) => StatusCode::NOT_FOUND,
+            PanelError::PermissionMissing(_) => StatusCode::FORBIDDEN,
+            PanelError::StepUpRequired => StatusCode::FORBIDDEN,
+            PanelError::RateLimited(_) => StatusCode::TOO_MANY_REQUESTS,
+            PanelError::StaleWrite => StatusCode::CONFLICT,
+            PanelError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            PanelError::NotAuthorized(_) => "notAuthorized",
+            PanelError::BadRequest(_) => "badRequest",
+            PanelError::NotFound(_) => "notFound",
+            PanelError::PermissionMissing(_) => "permissionMissing",
+            PanelError::StepUpRequired => "stepUpRequired",
+            PanelError::RateLimited(_) => "rateLimited",
+            PanelError::StaleWrite => "staleWrite",
+            PanelError::Internal(_) => "internalError",
+        }
+    }
+}
+
+impl Display for PanelError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PanelError::NotAuthorized(msg)
+            | PanelError::BadRequest(msg)
+            | PanelError::NotFound(msg)
+            | PanelError::Internal(msg) => write!(f, "{}", msg),
+            PanelError::PermissionMissing(perm) => {
+                write!(f, "You do not have permission to do this [{}]", perm)
+            }
+            PanelError::StepUpRequired => write!(
+                f,
+                "This action requires a recent MFA check, please enter your MFA code again"
+            ),
+            PanelError::StaleWrite => write!(
+                f,
+                "This was changed by someone else since you loaded it, please reload and try again"
+            ),
+            PanelError::RateLimited(retry_after) => {
+                write!(
+                    f,
+                    "You are being rate limited, try again in {} seconds",
+                    retry_after
+                )
+            }
+        }
+    }
+}
+
+impl From<PanelError> for Error {
+    fn from(e: PanelError) -> Self {
+        Self {
+            status: e.status(),
+            message: e.to_string(),
+            code: e.code(),
+            retry_after: match e {
+                PanelError::RateLimited(retry_after) => Some(retry_after),
+                _ => None,
+            },
+        }
+    }
+}
+
+impl IntoResponse for PanelError {
+    fn into_response(self) -> Response {
+        Error::from(self).into_response()
     }
 }
 
 pub struct AppState {
     pub cache_http: botox::cache::CacheHttpImpl,
     pub pool: sqlx::PgPool,
+    pub metrics: metrics_exporter_prometheus::PrometheusHandle,
+    /// Resolved permissions keyed by login token
+    ///
+    /// Must be invalidated whenever a session ends or staff permissions change
+    pub perms_cache: moka::future::Cache<String, Vec<Permission>>,
+    /// Live bot queue events, forwarded to queue websockets
+    pub queue_events: tokio::sync::broadcast::Sender<QueueEvent>,
+    /// Instance config keyed by environment
+    pub instance_config: moka::future::Cache<String, InstanceConfig>,
+    /// Base analytics, these are the same for every user so only one entry is stored
+    pub base_analytics: moka::future::Cache<(), BaseAnalytics>,
+    /// Login attempts keyed by client IP, used to rate limit logins
+    ///
+    /// Entries are shared so concurrent attempts from the same IP update the same counters
+    pub login_attempts: moka::future::Cache<IpAddr, Arc<Mutex<LoginAttempts>>>,
+    /// CDN scopes of the current environment, shared with `crate::config::CDN_SCOPES`
+    pub cdn_scopes: Arc<RwLock<HashMap<String, CdnScopeData>>>,
+    /// Results of requests keyed by (user ID, idempotency key)
+    pub idempotency_keys: moka::future::Cache<(String, String), IdempotentRequest>,
+}
+
+impl AppState {
+    /// Returns the resolved permissions of the user of a login token, caching them for PERMS_CACHE_TTL
+    pub async fn get_user_perms(
+        &self,
+        login_token: &str,
+        user_id: &str,
+    ) -> Result<Vec<Permission>, Error> {
+        if let Some(perms) = self.perms_cache.get(login_token) {
+            return Ok(perms);
+        }
+
+        let perms = get_user_perms(&self.pool, user_id)
+            .await
+            .map_err(Error::new)?
+            .resolve();
+
+        self.perms_cache
+            .insert(login_token.to_string(), perms.clone())
+            .await;
+
+        Ok(perms)
+    }
+
+    /// Checks the login token and that its user has `perm`, returning the auth data of the session
+    pub async fn require_perm(&self, login_token: &str, perm: &str) -> Result<AuthData, Error> {
+        let auth_data = check_auth(&self.pool, login_token)
+            .await
+            .map_err(|e| PanelError::NotAuthorized(e.to_string()))?;
+
+        let user_perms = self.get_user_perms(login_token, &auth_data.user_id).await?;
+
+        require_perm(&user_perms, perm)?;
+
+        Ok(auth_data)
+    }
+
+    /// Returns a `StepUpRequired` error if the session's last MFA check is older than `max_age`
+    pub async fn require_recent_mfa(
+        &self,
+        login_token: &str,
+        max_age: Duration,
+    ) -> Result<(), Error> {
+        let rec = sqlx::query!(
+            "SELECT last_mfa_at FROM staffpanel__authchain WHERE token = $1",
+            login_token
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(Error::new)?;
+
+        let Some(last_mfa_at) = rec.and_then(|r| r.last_mfa_at) else {
+            return Err(PanelError::StepUpRequired.into());
+        };
+
+        let max_age = chrono::Duration::from_std(max_age).map_err(Error::new)?;
+
+        if chrono::Utc::now() - last_mfa_at > max_age {
+            return Err(PanelError::StepUpRequired.into());
+        }
+
+        Ok(())
+    }
+
+    /// Requires a recent MFA check if `perm` has a step-up TTL set in the config
+    pub async fn require_step_up(&self, login_token: &str, perm: &str) -> Result<(), Error> {
+        let Some(ttl) = crate::config::CONFIG.panel.step_up_mfa.get(perm) else {
+            return Ok(());
+        };
+
+        self.require_recent_mfa(login_token, Duration::from_secs(*ttl))
+            .await
+    }
+
+    /// Returns the instance config for the current environment, caching it for INSTANCE_CONFIG_CACHE_TTL
+    ///
+    /// Falls back to the default instance config if none has been set
+    pub async fn get_instance_config(&self) -> Result<InstanceConfig, Error> {
+        let env = crate::config::CURRENT_ENV.to_string();
+
+        if let Some(instance_config) = self.instance_config.get(&env) {
+            return Ok(instance_config);
+        }
+
+        let rec = sqlx::query!(
+            "SELECT description, warnings FROM instance_config WHERE env = $1",
+            env
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(Error::new)?;
+
+        let instance_config = match rec {
+            Some(rec) => InstanceConfig {
+                description: rec.description,
+                warnings: rec.warnings,
+            },
+            None => InstanceConfig::default(),
+        };
+
+        self.instance_config
+            .insert(env, instance_config.clone())
+            .await;
+
+        Ok(instance_config)
+    }
+
+    /// Returns a `RateLimited` error if `ip` has made too many login attempts in the current window
+    pub async fn check_login_ratelimit(&self, ip: IpAddr) -> Result<(), Error> {
+        let Some(attempts) = self.login_attempts.get(&ip) else {
+            return Ok(());
+        };
+
+        let attempts = attempts.lock().unwrap_or_else(|e| e.into_inner());
+
+        let elapsed = attempts.window_start.elapsed();
+
+        if elapsed < LOGIN_RATELIMIT_WINDOW
+            && (attempts.failed >= MAX_FAILED_LOGINS || attempts.succeeded >= MAX_SUCCESSFUL_LOGINS)
+        {
+            let retry_after = (LOGIN_RATELIMIT_WINDOW - elapsed).as_secs().max(1);
+            return Err(PanelError::RateLimited(retry_after).into());
+        }
+
+        Ok(())
+    }
+
+    /// Records a login attempt from `ip`, starting a new window if the previous one is over
+    pub async fn record_login_attempt(&self, ip: IpAddr, success: bool) {
+        let attempts = self
+            .login_attempts
+            .get_with(ip, async {
+                Arc::new(Mutex::new(LoginAttempts {
+                    window_start: Instant::now(),
+                    failed: 0,
+                    succeeded: 0,
+                }))
+            })
+            .await;
+
+        let mut attempts = attempts.lock().unwrap_or_else(|e| e.into_inner());
+
+        if attempts.window_start.elapsed() >= LOGIN_RATELIMIT_WINDOW {
+            *attempts = LoginAttempts {
+                window_start: Instant::now(),
+                failed: 0,
+                succeeded: 0,
+            };
+        }
+
+        if success {
+            attempts.succeeded += 1;
+        } else {
+            attempts.failed += 1;
+        }
+    }
+}
+
+/// Information about the client making a request
+pub struct ClientInfo {
+    pub ip: IpAddr,
+    pub user_agent: Option<String>,
+}
+
+/// Returns the IP of the client, taken from `panel.client_ip_header` if the request came through a trusted proxy
+pub fn client_ip(peer: IpAddr, headers: &HeaderMap) -> IpAddr {
+    let panel = &crate::config::CONFIG.panel;
+
+    if panel.client_ip_header.is_empty() || !panel.trusted_proxies.contains(&peer) {
+        return peer;
+    }
+
+    // Proxies append to the header, so the last entry is the one set by the trusted proxy
+    headers
+        .get(panel.client_ip_header.as_str())
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.rsplit(',').next())
+        .and_then(|ip| ip.trim().parse::<IpAddr>().ok())
+        .unwrap_or(peer)
+}
+
+/// Login attempts made by a single IP in the current rate limit window
+pub struct LoginAttempts {
+    pub window_start: Instant,
+    pub failed: u32,
+    pub succeeded: u32,
+}
+
+/// State of a request made with an idempotency key
+#[derive(Clone)]
+pub enum IdempotentRequest {
+    /// The request is still being processed, since the given time
+    Processing(Instant),
+    /// The request has finished, later requests with the key get the same response
+    Done(StatusCode, String),
+}
+
+/// Returns a `PermissionMissing` error if the user of the session is not an owner
+pub fn require_owner(auth_data: &AuthData) -> Result<(), Error> {
+    if !crate::config::CONFIG
+        .owners
+        .iter()
+        .any(|o| o.to_string() == auth_data.user_id)
+    {
+        return Err(PanelError::PermissionMissing("owner".to_string()).into());
+    }
+
+    Ok(())
+}
+
+/// Returns a `PermissionMissing` error if the given permissions do not include `perm`
+pub fn require_perm(user_perms: &[Permission], perm: &str) -> Result<(), Error> {
+    if !perms::has_perm(user_perms, &perm.into()) {
+        return Err(PanelError::PermissionMissing(perm.to_string()).into());
+    }
+
+    Ok(())
+}
+
+/// Opaque keyset pagination cursor pointing at the last entity of a page
+///
+/// Unlike offsets this stays stable when entities are inserted while paging. Sent to clients as base64 encoded JSON
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct PageCursor {
+    /// Whether the entity was an exact ID match, only used by searches
+    #[serde(default)]
+    pub exact: bool,
+    /// Similarity of the entity to the query, only used by searches
+    #[serde(default)]
+    pub similarity: f32,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub id: String,
+}
+
+impl PageCursor {
+    pub fn encode(&self) -> Result<String, Error> {
+        let json = serde_json::to_vec(self).map_err(Error::new)?;
+        Ok(data_encoding::BASE64URL_NOPAD.encode(&json))
+    }
+
+    /// Decodes a cursor sent by a client, returning a `BadRequest` error if it is invalid
+    pub fn decode(cursor: &str) -> Result<Self, Error> {
+        data_encoding::BASE64URL_NOPAD
+            .decode(cursor.as_bytes())
+            .ok()
+            .and_then(|json| serde_json::from_slice(&json).ok())
+            .ok_or_else(|| PanelError::BadRequest("Invalid cursor".to_string()).into())
+    }
+}
+
+/// Converts the result of an RPC method into a response
+///
+/// Destructive methods return an `RPCConfirmation` with a 202 until they are called again with its token
+pub fn rpc_response(resp: Result<RPCSuccess, crate::Error>) -> Response {
+    match resp {
+        Ok(RPCSuccess::NoContent) => (StatusCode::NO_CONTENT, "").into_response(),
+        Ok(RPCSuccess::Content(c)) | Ok(RPCSuccess::DryRun(c)) => {
+            (StatusCode::OK, c).into_response()
+        }
+        Ok(RPCSuccess::ConfirmationRequired {
+            confirmation_token,
+            summary,
+        }) => (
+            StatusCode::ACCEPTED,
+            Json(RPCConfirmation {
+                confirmation_token,
+                summary,
+                expires_in: RPC_CONFIRMATION_TTL.as_secs(),
+            }),
+        )
+            .into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode_err(cursor: &str) -> Error {
+        match PageCursor::decode(cursor) {
+            Ok(_) => panic!("cursor {:?} should not decode", cursor),
+            Err(e) => e,
+        }
+    }
+
+    #[test]
+    fn page_cursor_round_trips() {
+        let cursor = PageCursor {
+            exact: true,
+            similarity: 0.5,
+            created_at: chrono::DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+            id: "1234567890".to_string(),
+        };
+
+        let Ok(encoded) = cursor.encode() else {
+            panic!("cursor should encode");
+        };
+
+        let Ok(decoded) = PageCursor::decode(&encoded) else {
+            panic!("encoded cursor should decode");
+        };
+
+        assert!(decoded.exact);
+        assert_eq!(decoded.similarity, 0.5);
+        assert_eq!(decoded.created_at, cursor.created_at);
+        assert_eq!(decoded.id, cursor.id);
+    }
+
+    #[test]
+    fn page_cursor_rejects_malformed_base64() {
+        let e = decode_err("not base64!");
+
+        assert_eq!(e.status, StatusCode::BAD_REQUEST);
+        assert_eq!(e.code, "badRequest");
+    }
+
+    #[test]
+    fn page_cursor_rejects_malformed_json() {
+        for json in ["", "{", "[]", r#"{"id": "1"}"#] {
+            let cursor = data_encoding::BASE64URL_NOPAD.encode(json.as_bytes());
+            let e = decode_err(&cursor);
+
+            assert_eq!(e.status, StatusCode::BAD_REQUEST, "{:?}", json);
+        }
+    }
 }
@@ -2,5 +2,6 @@ mod actions;
 mod auth;
 mod core;
 pub mod panel_query;
+mod queue_ws;
 pub mod server;
 mod types;
@@ -5,10 +5,12 @@ use crate::panelapi::types::{
     auth::AuthorizeAction,
     blog::BlogAction,
     bot_whitelist::BotWhitelistAction,
-    partners::PartnerAction,
+    partners::{PartnerAction, PartnerTypeAction},
+    rpclogs::RPCLogFilters,
     shop_items::{ShopCouponAction, ShopHoldAction, ShopItemAction, ShopItemBenefitAction},
     staff_disciplinary::StaffDisciplinaryTypeAction,
     vote_credit_tiers::VoteCreditTierAction,
+    webcore::InstanceConfig,
 };
 use crate::rpc::core::RPCMethod;
 use serde::{Deserialize, Serialize};
@@ -26,6 +28,34 @@ pub enum PanelQuery {
         /// Action to take
         action: AuthorizeAction,
     },
+    /// Returns the panel sessions of the current user
+    ListSessions {
+        /// Login token
+        login_token: String,
+    },
+    /// Sets (or clears) the label of one of the current user's sessions
+    RenameSession {
+        /// Login token
+        login_token: String,
+        /// The ID of the session to rename
+        session_id: String,
+        /// New label of the session, `None` clears it
+        name: Option<String>,
+    },
+    /// Purges expired panel sessions and sessions of former staff right away
+    ///
+    /// This normally happens in the background. This is restricted to owners
+    PurgeExpiredSessions {
+        /// Login token
+        login_token: String,
+    },
+    /// Returns the current user, their resolved permissions and staff position names
+    ///
+    /// This is a lighter alternative to `Hello` for rendering the panel header
+    GetFullIdentity {
+        /// Login token
+        login_token: String,
+    },
     /// Returns configuration data for the panel
     Hello {
         /// Login token
@@ -33,6 +63,22 @@ pub enum PanelQuery {
         /// Hello protocol version, should be `HELLO_VERSION`
         version: u16,
     },
+    /// Updates the instance config (description and warnings) shown on the panel
+    ///
+    /// This is restricted to owners
+    SetInstanceConfig {
+        /// Login token
+        login_token: String,
+        /// The new instance config
+        instance_config: InstanceConfig,
+    },
+    /// Rereads the CDN scopes from the config file, returning the names of the new scopes
+    ///
+    /// The current scopes are kept if any scope path is not an existing directory. This is restricted to owners
+    ReloadCdnScopes {
+        /// Login token
+        login_token: String,
+    },
     /// Returns base analytics
     BaseAnalytics {
         /// Login token
@@ -45,12 +91,36 @@ pub enum PanelQuery {
         /// User ID to fetch details for
         user_id: String,
     },
+    /// Returns information on many users at once as a map of user id to dovewing PlatformUser
+    ///
+    /// Users that could not be found are left out of the map
+    GetUserBulk {
+        /// Login token
+        login_token: String,
+        /// The IDs of the users to fetch (at most 100)
+        user_ids: Vec<String>,
+    },
     /// Returns the bot queue
     ///
     /// This is public to all staff members
     BotQueue {
         /// Login token
         login_token: String,
+        /// Only return bots that have not been claimed
+        #[serde(default)]
+        only_unclaimed: bool,
+        /// Only return bots claimed by this user
+        #[serde(default)]
+        claimed_by: Option<String>,
+        /// Only return bots of this type (`pending` or `claimed`)
+        #[serde(default)]
+        r#type: Option<String>,
+        /// Maximum number of bots to return (at most 100), returns the whole queue if unset
+        #[serde(default)]
+        limit: Option<i64>,
+        /// `next_cursor` of the previous page
+        #[serde(default)]
+        cursor: Option<String>,
     },
     /// Executes an RPC on a target
     ///
@@ -62,6 +132,41 @@ pub enum PanelQuery {
         target_type: TargetType,
         /// RPC Method
         method: RPCMethod,
+        /// Validate the method and describe its effect without running it
+        #[serde(default)]
+        dry_run: bool,
+        /// Confirmation token returned by a previous call, needed for destructive methods
+        #[serde(default)]
+        confirmation_token: Option<String>,
+    },
+    /// Approves a bot, this is a shortcut for the `Approve` RPC method
+    ///
+    /// The user must have permission for the `Approve` RPC method
+    ApproveBot {
+        /// Login token
+        login_token: String,
+        /// The ID of the bot to approve
+        bot_id: String,
+        /// Reason for approving the bot
+        reason: String,
+    },
+    /// Denies a bot, this is a shortcut for the `Deny` RPC method
+    ///
+    /// The user must have permission for the `Deny` RPC method
+    DenyBot {
+        /// Login token
+        login_token: String,
+        /// The ID of the bot to deny
+        bot_id: String,
+        /// Reason for denying the bot
+        reason: String,
+    },
+    /// Returns every permission checked by the panel and RPC, and whether the user has it
+    ///
+    /// This is public to all staff members
+    GetPermissionMatrix {
+        /// Login token
+        login_token: String,
     },
     /// Returns all RPC actions available
     ///
@@ -74,13 +179,84 @@ pub enum PanelQuery {
         /// Filtered
         filtered: bool,
     },
-    /// Gets the list of all RPC log entries made
+    /// Returns a single RPC action by its ID
+    ///
+    /// This is public to all staff members
+    GetRpcMethod {
+        /// Login token
+        login_token: String,
+        /// The ID of the RPC action (e.g. `Claim`)
+        method_id: String,
+    },
+    /// Returns the RPC actions that support a given target type
+    ///
+    /// Setting filtered will filter RPC actions to that what the user has access to
+    ///
+    /// This is public to all staff members
+    GetRpcMethodsForTarget {
+        /// Login token
+        login_token: String,
+        /// Target Type
+        target_type: TargetType,
+        /// Filtered
+        filtered: bool,
+    },
+    /// Gets the list of RPC log entries made, newest first
     GetRpcLogEntries {
         /// Login token
         login_token: String,
+        /// Filters to apply
+        #[serde(default)]
+        filters: RPCLogFilters,
+        /// Maximum number of entries to return (defaults to 50, at most 500)
+        #[serde(default)]
+        limit: Option<i64>,
+        /// Number of entries to skip
+        #[serde(default)]
+        offset: Option<i64>,
+    },
+    /// Returns the disciplinary history of a staff member, newest first
+    GetStaffDisciplinaries {
+        /// Login token
+        login_token: String,
+        /// The user ID of the staff member
+        user_id: String,
+        /// Only return disciplinaries that have not expired yet
+        #[serde(default)]
+        active_only: bool,
+        /// Only return disciplinaries created after this time
+        #[serde(default)]
+        after: Option<chrono::DateTime<chrono::Utc>>,
+        /// Only return disciplinaries created before this time
+        #[serde(default)]
+        before: Option<chrono::DateTime<chrono::Utc>>,
+    },
+    /// Returns the resolved permissions of many users at once, keyed by user ID
+    ///
+    /// Users who are not staff members map to `null`. This is public to all staff members
+    GetUserPermsBulk {
+        /// Login token
+        login_token: String,
+        /// The user IDs to fetch permissions of
+        user_ids: Vec<String>,
+    },
+    /// Retries a failed RPC action from its log entry
+    ///
+    /// Only entries in the `error` state (or stuck in `retrying` for over 10 minutes) can be retried, and only by the user who made them unless the user has `rpc_logs.retry_any`.
+    /// The action is run again as the current user, so the user must have permission for the RPC method
+    RetryRpcLogEntry {
+        /// Login token
+        login_token: String,
+        /// ID of the RPC log entry to retry
+        log_id: String,
+        /// Confirmation token returned by a previous call, needed for destructive methods
+        #[serde(default)]
+        confirmation_token: Option<String>,
     },
     /// Searches for a bot based on a query
     ///
+    /// Returns an `EntitySearchResults` page, before hello version 7 this was a plain array of entities
+    ///
     /// This is public to all staff members
     SearchEntitys {
         /// Login token
@@ -89,6 +265,20 @@ pub enum PanelQuery {
         target_type: TargetType,
         /// Query
         query: String,
+        /// Maximum number of results to return (defaults to 25, at most 100)
+        #[serde(default)]
+        limit: Option<i64>,
+        /// Number of results to skip, ignored if `cursor` is set
+        #[serde(default)]
+        offset: Option<i64>,
+        /// `next_cursor` of the previous page, unlike `offset` this is stable when entities are added while paging
+        #[serde(default)]
+        cursor: Option<String>,
+    },
+    /// Returns all teams with their member counts and owners
+    TeamList {
+        /// Login token
+        login_token: String,
     },
     /// Updates/handles partners
     UpdatePartners {
@@ -97,6 +287,46 @@ pub enum PanelQuery {
         /// Action
         action: PartnerAction,
     },
+    /// Restores a deleted partner, this is only possible until the partner is permanently deleted
+    RestorePartner {
+        /// Login token
+        login_token: String,
+        /// The ID of the partner
+        partner_id: String,
+    },
+    /// Returns click and impression analytics of a partner
+    GetPartnerAnalytics {
+        /// Login token
+        login_token: String,
+        /// The ID of the partner
+        partner_id: String,
+        /// Number of days of analytics to return (defaults to 30, at most 365)
+        #[serde(default)]
+        days: Option<i32>,
+    },
+    /// Updates/handles partner types
+    UpdatePartnerTypes {
+        /// Login token
+        login_token: String,
+        /// Action
+        action: PartnerTypeAction,
+    },
+    /// Returns all blog posts, newest first
+    ///
+    /// Drafts are only returned to users who can manage the blog
+    GetBlogPosts {
+        /// Login token
+        login_token: String,
+    },
+    /// Returns a single blog post by slug, used to preview posts
+    ///
+    /// Drafts are only returned to users who can manage the blog
+    GetBlogPost {
+        /// Login token
+        login_token: String,
+        /// Slug of the blog post
+        slug: String,
+    },
     /// Updates/handles the blog of the list
     UpdateBlog {
         /// Login token
@@ -0,0 +1,115 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::State;
+use axum::response::Response;
+use log::{error, warn};
+use sqlx::postgres::PgListener;
+use sqlx::PgPool;
+use tokio::sync::broadcast::{error::RecvError, Sender};
+
+use super::core::AppState;
+use crate::impls::queue_events::{QueueEvent, QUEUE_EVENTS_CHANNEL};
+
+/// How long a client has to send its login token after connecting
+const AUTH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How often the session of a connected client is rechecked
+const REAUTH_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Forwards bot queue notifications from Postgres to all connected websockets
+///
+/// If notifications are not available, live queue updates are disabled and clients
+/// simply never receive events
+pub async fn listen_queue_events(pool: PgPool, queue_events: Sender<QueueEvent>) {
+    let mut listener = match PgListener::connect_with(&pool).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            error!("Live queue updates are unavailable: {}", e);
+            return;
+        }
+    };
+
+    if let Err(e) = listener.listen(QUEUE_EVENTS_CHANNEL).await {
+        error!("Live queue updates are unavailable: {}", e);
+        return;
+    }
+
+    loop {
+        let notification = match listener.recv().await {
+            Ok(notification) => notification,
+            Err(e) => {
+                error!(
+                    "Queue event listener failed, live queue updates are now unavailable: {}",
+                    e
+                );
+                return;
+            }
+        };
+
+        match serde_json::from_str::<QueueEvent>(notification.payload()) {
+            // Sending only fails if there are no connected clients, which is fine
+            Ok(event) => {
+                let _ = queue_events.send(event);
+            }
+            Err(e) => warn!("Received invalid queue event: {}", e),
+        }
+    }
+}
+
+/// Websocket for live bot queue updates
+///
+/// The first frame sent by the client must be its login token
+pub async fn queue_ws(State(state): State<Arc<AppState>>, ws: WebSocketUpgrade) -> Response {
+    ws.on_upgrade(move |socket| handle_queue_ws(state, socket))
+}
+
+async fn handle_queue_ws(state: Arc<AppState>, mut socket: WebSocket) {
+    let login_token = match tokio::time::timeout(AUTH_TIMEOUT, socket.recv()).await {
+        Ok(Some(Ok(Message::Text(login_token)))) => login_token,
+        _ => {
+            let _ = socket.send(Message::Close(None)).await;
+            return;
+        }
+    };
+
+    if let Err(e) = super::auth::check_auth(&state.pool, &login_token).await {
+        let _ = socket.send(Message::Text(e.to_string())).await;
+        let _ = socket.send(Message::Close(None)).await;
+        return;
+    }
+
+    let mut queue_events = state.queue_events.subscribe();
+    let mut reauth = tokio::time::interval(REAUTH_INTERVAL);
+    reauth.tick().await; // The first tick completes immediately
+
+    loop {
+        tokio::select! {
+            event = queue_events.recv() => match event {
+                Ok(event) => {
+                    let Ok(payload) = serde_json::to_string(&event) else {
+                        continue;
+                    };
+
+                    if socket.send(Message::Text(payload)).await.is_err() {
+                        return;
+                    }
+                }
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return,
+            },
+            msg = socket.recv() => match msg {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                _ => continue,
+            },
+            _ = reauth.tick() => {
+                if let Err(e) = super::auth::check_auth(&state.pool, &login_token).await {
+                    let _ = socket.send(Message::Text(e.to_string())).await;
+                    let _ = socket.send(Message::Close(None)).await;
+                    return;
+                }
+            }
+        }
+    }
+}
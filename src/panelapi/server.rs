@@ -1,41 +1,68 @@
+use std::future::IntoFuture;
+use std::net::SocketAddr;
 use std::str::FromStr;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+use crate::impls::dovewing::PlatformUser;
 use crate::impls::link::Link;
-use crate::impls::{target_types::TargetType, utils::get_user_perms};
+use crate::impls::queue_events::QueueEvent;
+use crate::impls::target_types::TargetType;
 use crate::panelapi::panel_query::PanelQuery;
 use crate::panelapi::types::staff_disciplinary::StaffDisciplinaryType;
 use crate::panelapi::types::{
-    auth::AuthorizeAction,
+    analytics::BaseAnalytics,
+    auth::{AuthorizeAction, MfaLogin, MfaLoginSecret, PanelSession, PurgedSessions},
     blog::{BlogAction, BlogPost},
     bot_whitelist::{BotWhitelist, BotWhitelistAction},
-    entity::{PartialBot, PartialEntity},
-    partners::{CreatePartner, PartnerAction},
-    rpc::RPCWebAction,
-    rpclogs::RPCLogEntry,
+    entity::{
+        BotQueuePage, EntitySearchResults, PartialBot, PartialEntity, PartialServer, PartialTeam,
+    },
+    partners::{
+        CreatePartner, CreatePartnerType, Partner, PartnerAction, PartnerAnalytics,
+        PartnerAnalyticsDay, PartnerEvent, PartnerType, PartnerTypeAction, Partners,
+    },
+    permissions::{PermissionMatrix, PermissionMatrixEntry},
+    rpc::{RPCConfirmation, RPCWebAction},
+    rpclogs::{RPCLogEntries, RPCLogEntry, RPCLogFilters},
     shop_items::{
-        ShopCoupon, ShopCouponAction, ShopItem, ShopItemAction, ShopItemBenefit,
-        ShopItemBenefitAction,
+        ShopCoupon, ShopCouponAction, ShopCouponInvalidReason, ShopCouponValidation, ShopHold,
+        ShopHoldAction, ShopItem, ShopItemAction, ShopItemBenefit, ShopItemBenefitAction,
     },
     staff_disciplinary::StaffDisciplinaryTypeAction,
-    vote_credit_tiers::VoteCreditTierAction,
-    webcore::InstanceConfig,
+    staff_positions::{
+        CorrespondingServer, PermissionSource, PermissionSourceKind, ResolvedPermission,
+        ResolvedPermissions,
+    },
+    vote_credit_tiers::{ProposedVoteCreditTier, VoteCreditTierAction},
+    webcore::{
+        CoreConstants, Health, Hello, InstanceConfig, InstanceDiagnostics, PanelServers, StartAuth,
+    },
 };
-use crate::rpc::core::{RPCHandle, RPCMethod};
+use crate::rpc::core::{FieldType, RPCField, RPCHandle, RPCMethod};
 use axum::extract::DefaultBodyLimit;
-use axum::http::HeaderMap;
+use axum::extract::{ConnectInfo, Path, Request};
+use axum::http::{header, HeaderMap, HeaderName, HeaderValue, Method};
+use axum::middleware::{self, Next};
+use axum::Extension;
 use axum::Json;
 use kittycat::perms::{self, Permission};
 
-use axum::response::IntoResponse;
+use axum::response::{IntoResponse, Response};
 use axum::routing::{get, post};
 use axum::{extract::State, http::StatusCode, Router};
-use log::info;
+use log::{error, info, warn};
+use metrics_exporter_prometheus::PrometheusHandle;
 use sqlx::PgPool;
-use tower_http::cors::{Any, CorsLayer};
+use tokio_util::sync::CancellationToken;
+use tower_http::cors::CorsLayer;
 
 use super::actions;
-use super::core::{AppState, Error};
+use super::core::{
+    client_ip, require_perm, AppState, ClientInfo, Error, PageCursor, PanelError,
+    BASE_ANALYTICS_CACHE_TTL, IDEMPOTENCY_KEY_TTL, INSTANCE_CONFIG_CACHE_TTL,
+    LOGIN_RATELIMIT_WINDOW, MAX_IDEMPOTENCY_KEYS, PERMS_CACHE_TTL, QUEUE_THROUGHPUT_WINDOW_DAYS,
+};
 use super::types::staff_members::StaffMemberAction;
 use super::types::staff_positions::StaffPositionAction;
 use crate::impls::dovewing::DovewingSource;
@@ -43,30 +70,101 @@ use strum::VariantNames;
 
 use num_traits::ToPrimitive;
 
-pub async fn init_panelapi(pool: PgPool, cache_http: botox::cache::CacheHttpImpl) {
+/// How long in-flight requests are given to finish once a shutdown signal is received
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Maximum size of a request body
+///
+/// Panel queries are small JSON payloads (the largest being blog posts), CDN uploads do not go through the panel API
+const MAX_BODY_SIZE: usize = 2 * 1024 * 1024;
+
+/// Maximum number of bots returned per bot queue page
+const MAX_QUEUE_PAGE_SIZE: i64 = 100;
+
+/// Header the request ID is returned in
+const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Runs the panel API until `shutdown` is cancelled, then waits up to `SHUTDOWN_TIMEOUT` for in-flight requests
+pub async fn init_panelapi(
+    pool: PgPool,
+    cache_http: botox::cache::CacheHttpImpl,
+    metrics: PrometheusHandle,
+    shutdown: CancellationToken,
+) {
     use utoipa::OpenApi;
     #[derive(OpenApi)]
     #[openapi(
-        paths(query),
+        paths(query, health),
         components(schemas(
+            // Requests
             PanelQuery,
-            InstanceConfig,
-            RPCMethod,
             TargetType,
-            PartnerAction,
-            CreatePartner,
+            RPCMethod,
             AuthorizeAction,
             BlogAction,
+            BotWhitelistAction,
+            PartnerAction,
+            CreatePartner,
+            PartnerTypeAction,
+            CreatePartnerType,
+            ShopItemAction,
+            ShopItemBenefitAction,
+            ShopCouponAction,
+            ShopHoldAction,
             StaffPositionAction,
             StaffMemberAction,
             StaffDisciplinaryTypeAction,
             VoteCreditTierAction,
+            ProposedVoteCreditTier,
+            RPCLogFilters,
+            CorrespondingServer,
+            PermissionSourceKind,
+            PermissionSource,
+            ResolvedPermission,
+            ResolvedPermissions,
+            Link,
+            // Responses
+            Hello,
+            InstanceConfig,
+            InstanceDiagnostics,
+            CoreConstants,
+            PanelServers,
+            StartAuth,
+            MfaLogin,
+            MfaLoginSecret,
+            PanelSession,
+            PurgedSessions,
+            Health,
+            BaseAnalytics,
+            PlatformUser,
+            PartialEntity,
+            PartialBot,
+            PartialServer,
+            PartialTeam,
+            EntitySearchResults,
+            BotQueuePage,
+            QueueEvent,
+            RPCWebAction,
+            RPCField,
+            FieldType,
+            RPCConfirmation,
+            RPCLogEntry,
+            RPCLogEntries,
+            Partner,
+            Partners,
+            PartnerType,
+            PartnerAnalytics,
+            PartnerAnalyticsDay,
+            PartnerEvent,
+            BlogPost,
             ShopItem,
-            ShopItemAction,
             ShopItemBenefit,
-            ShopItemBenefitAction,
-            BotWhitelistAction,
-            Link,
+            ShopCoupon,
+            ShopCouponValidation,
+            ShopCouponInvalidReason,
+            ShopHold,
+            PermissionMatrix,
+            PermissionMatrixEntry,
         ))
     )]
     struct ApiDoc;
@@ -101,56 +199,628 @@ pub async fn init_panelapi(pool: PgPool, cache_http: botox::cache::CacheHttpImpl
     .await
     .expect("Failed to create staffpanel__authchain table");
 
-    let shared_state = Arc::new(AppState { pool, cache_http });
+    sqlx::query!("ALTER TABLE staffpanel__authchain ADD COLUMN IF NOT EXISTS user_agent TEXT")
+        .execute(&pool)
+        .await
+        .expect("Failed to add user_agent column to staffpanel__authchain");
+
+    sqlx::query!("ALTER TABLE staffpanel__authchain ADD COLUMN IF NOT EXISTS name TEXT")
+        .execute(&pool)
+        .await
+        .expect("Failed to add name column to staffpanel__authchain");
+
+    sqlx::query!(
+        "ALTER TABLE staffpanel__authchain ADD COLUMN IF NOT EXISTS last_mfa_at TIMESTAMPTZ"
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to add last_mfa_at column to staffpanel__authchain");
+
+    sqlx::query!(
+        "ALTER TABLE partners ADD COLUMN IF NOT EXISTS position INTEGER NOT NULL DEFAULT 0"
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to add position column to partners");
+
+    sqlx::query!("ALTER TABLE partners ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ")
+        .execute(&pool)
+        .await
+        .expect("Failed to add deleted_at column to partners");
+
+    // Partners added before positions existed all start at 0, so order them by creation date once
+    sqlx::query!(
+        "UPDATE partners SET position = ranked.position FROM (
+            SELECT id, (ROW_NUMBER() OVER (ORDER BY created_at) - 1)::integer AS position FROM partners WHERE deleted_at IS NULL
+        ) ranked
+        WHERE partners.id = ranked.id AND NOT EXISTS (SELECT 1 FROM partners WHERE position <> 0)"
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to backfill partner positions");
+
+    sqlx::query!(
+        "ALTER TABLE partners ADD COLUMN IF NOT EXISTS version INTEGER NOT NULL DEFAULT 0"
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to add version column to partners");
+
+    sqlx::query!(
+        "ALTER TABLE shop_items ADD COLUMN IF NOT EXISTS version INTEGER NOT NULL DEFAULT 0"
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to add version column to shop_items");
+
+    sqlx::query!("ALTER TABLE rpc_logs ADD COLUMN IF NOT EXISTS target_type TEXT")
+        .execute(&pool)
+        .await
+        .expect("Failed to add target_type column to rpc_logs");
+
+    sqlx::query!("ALTER TABLE rpc_logs ADD COLUMN IF NOT EXISTS error TEXT")
+        .execute(&pool)
+        .await
+        .expect("Failed to add error column to rpc_logs");
+
+    sqlx::query!("ALTER TABLE rpc_logs ADD COLUMN IF NOT EXISTS retrying_at TIMESTAMPTZ")
+        .execute(&pool)
+        .await
+        .expect("Failed to add retrying_at column to rpc_logs");
+
+    // Failed entries used to store their error in state, move it to the error column
+    sqlx::query!(
+        "UPDATE rpc_logs SET error = state, state = 'error' WHERE state NOT IN ('pending', 'success', 'error', 'retrying', 'retried')"
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to move rpc_logs errors to the error column");
+
+    sqlx::query!(
+        "CREATE TABLE IF NOT EXISTS partner_analytics (
+            partner_id TEXT NOT NULL REFERENCES partners(id) ON DELETE CASCADE,
+            day DATE NOT NULL DEFAULT CURRENT_DATE,
+            clicks BIGINT NOT NULL DEFAULT 0,
+            impressions BIGINT NOT NULL DEFAULT 0,
+            PRIMARY KEY (partner_id, day)
+        )"
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create partner_analytics table");
+
+    sqlx::query!("ALTER TABLE staff_members ADD COLUMN IF NOT EXISTS mfa_last_counter BIGINT")
+        .execute(&pool)
+        .await
+        .expect("Failed to add mfa_last_counter column to staff_members");
+
+    sqlx::query!("ALTER TABLE blogs ADD COLUMN IF NOT EXISTS published_at TIMESTAMPTZ")
+        .execute(&pool)
+        .await
+        .expect("Failed to add published_at column to blogs");
+
+    sqlx::query!("ALTER TABLE bot_whitelist ADD COLUMN IF NOT EXISTS expires_at TIMESTAMPTZ")
+        .execute(&pool)
+        .await
+        .expect("Failed to add expires_at column to bot_whitelist");
+
+    // Bots are submitted outside of arcadia, so new pending bots are announced to the queue from the database.
+    // The channel must match queue_events::QUEUE_EVENTS_CHANNEL
+    sqlx::query!(
+        "CREATE OR REPLACE FUNCTION arcadia_notify_bot_submitted() RETURNS TRIGGER AS $$
+        BEGIN
+            PERFORM pg_notify(
+                'arcadia_bot_queue',
+                json_build_object('bot_id', NEW.bot_id, 'type', NEW.type, 'claimed_by', NEW.claimed_by)::text
+            );
+            RETURN NEW;
+        END;
+        $$ LANGUAGE plpgsql"
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create arcadia_notify_bot_submitted function");
+
+    sqlx::query!("DROP TRIGGER IF EXISTS arcadia_bot_submitted ON bots")
+        .execute(&pool)
+        .await
+        .expect("Failed to drop arcadia_bot_submitted trigger");
+
+    sqlx::query!(
+        "CREATE TRIGGER arcadia_bot_submitted AFTER INSERT ON bots
+        FOR EACH ROW WHEN (NEW.type = 'pending') EXECUTE FUNCTION arcadia_notify_bot_submitted()"
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create arcadia_bot_submitted trigger");
+
+    sqlx::query!(
+        "CREATE TABLE IF NOT EXISTS instance_config (
+            env TEXT PRIMARY KEY,
+            description TEXT NOT NULL,
+            warnings TEXT[] NOT NULL DEFAULT '{}',
+            updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        )"
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create instance_config table");
+
+    for index in [
+        "CREATE INDEX IF NOT EXISTS rpc_logs_user_id_idx ON rpc_logs (user_id)",
+        "CREATE INDEX IF NOT EXISTS rpc_logs_method_idx ON rpc_logs (method)",
+        "CREATE INDEX IF NOT EXISTS rpc_logs_target_type_idx ON rpc_logs (target_type)",
+        "CREATE INDEX IF NOT EXISTS rpc_logs_created_at_idx ON rpc_logs (created_at)",
+    ] {
+        sqlx::query(index)
+            .execute(&pool)
+            .await
+            .expect("Failed to create rpc_logs index");
+    }
+
+    // Coupon codes are redeemed by code, so they must be unique. Duplicates have to be removed by hand before this can be created
+    sqlx::query!("CREATE UNIQUE INDEX IF NOT EXISTS shop_coupons_code_key ON shop_coupons (code)")
+        .execute(&pool)
+        .await
+        .expect(
+            "Failed to create unique index on shop_coupons.code, are there duplicate coupon codes?",
+        );
+
+    // pg_trgm needs a superuser to install (see the README), so search falls back to plain ILIKE matching without it
+    let has_trgm = sqlx::query!(
+        "SELECT EXISTS (SELECT 1 FROM pg_extension WHERE extname = 'pg_trgm') AS \"exists!\""
+    )
+    .fetch_one(&pool)
+    .await
+    .expect("Failed to check for pg_trgm extension")
+    .exists;
+
+    // Entity search ranks name matches with arcadia_search_similarity so the same queries work either way
+    if has_trgm {
+        sqlx::query!(
+            "CREATE OR REPLACE FUNCTION arcadia_search_similarity(a TEXT, b TEXT) RETURNS REAL AS $$
+                SELECT similarity(a, b)
+            $$ LANGUAGE sql IMMUTABLE"
+        )
+        .execute(&pool)
+        .await
+        .expect("Failed to create arcadia_search_similarity function");
+
+        // Trigram indexes for entity search
+        for index in [
+            "CREATE INDEX IF NOT EXISTS internal_user_cache__discord_username_trgm_idx ON internal_user_cache__discord USING GIN (username gin_trgm_ops)",
+            "CREATE INDEX IF NOT EXISTS servers_name_trgm_idx ON servers USING GIN (name gin_trgm_ops)",
+            "CREATE INDEX IF NOT EXISTS teams_name_trgm_idx ON teams USING GIN (name gin_trgm_ops)",
+        ] {
+            sqlx::query(index)
+                .execute(&pool)
+                .await
+                .expect("Failed to create search index");
+        }
+    } else {
+        warn!("pg_trgm is not installed, entity search will use ILIKE and only rank exact and prefix matches. Run `CREATE EXTENSION pg_trgm` as a superuser to enable similarity ranking");
+
+        // Exact names first, then prefixes
+        sqlx::query!(
+            "CREATE OR REPLACE FUNCTION arcadia_search_similarity(a TEXT, b TEXT) RETURNS REAL AS $$
+                SELECT CASE
+                    WHEN LOWER(a) = LOWER(b) THEN 1
+                    WHEN LOWER(a) LIKE LOWER(b) || '%' THEN 0.5
+                    ELSE 0
+                END::real
+            $$ LANGUAGE sql IMMUTABLE"
+        )
+        .execute(&pool)
+        .await
+        .expect("Failed to create arcadia_search_similarity function");
+    }
+
+    let (queue_events, _) = tokio::sync::broadcast::channel(100);
+
+    tokio::task::spawn(super::queue_ws::listen_queue_events(
+        pool.clone(),
+        queue_events.clone(),
+    ));
+
+    let shared_state = Arc::new(AppState {
+        pool,
+        cache_http,
+        metrics,
+        perms_cache: moka::future::Cache::builder()
+            .time_to_live(PERMS_CACHE_TTL)
+            .build(),
+        queue_events,
+        instance_config: moka::future::Cache::builder()
+            .time_to_live(INSTANCE_CONFIG_CACHE_TTL)
+            .build(),
+        base_analytics: moka::future::Cache::builder()
+            .time_to_live(BASE_ANALYTICS_CACHE_TTL)
+            .build(),
+        login_attempts: moka::future::Cache::builder()
+            .time_to_live(LOGIN_RATELIMIT_WINDOW)
+            .build(),
+        cdn_scopes: crate::config::CDN_SCOPES.clone(),
+        idempotency_keys: moka::future::Cache::builder()
+            .time_to_live(IDEMPOTENCY_KEY_TTL)
+            .max_capacity(MAX_IDEMPOTENCY_KEYS)
+            .build(),
+    });
+
+    let cors_origins = if crate::config::CONFIG.panel.cors_origins.is_empty() {
+        vec![crate::config::CONFIG.frontend_url.get()]
+    } else {
+        crate::config::CONFIG.panel.cors_origins.clone()
+    };
+
+    let cors_origins = cors_origins
+        .iter()
+        .map(|origin| {
+            origin
+                .trim_end_matches('/')
+                .parse::<HeaderValue>()
+                .unwrap_or_else(|e| panic!("Invalid CORS origin '{}': {}", origin, e))
+        })
+        .collect::<Vec<HeaderValue>>();
 
     let app = Router::new()
         .route("/openapi", get(docs))
+        .route("/health", get(health))
+        .route("/metrics", get(prometheus_metrics))
+        .route("/queue/ws", get(super::queue_ws::queue_ws))
+        .route("/partners/:id/events", post(record_partner_event))
         .route("/", post(query))
         .with_state(shared_state)
-        .layer(DefaultBodyLimit::max(1048576000))
+        .layer(DefaultBodyLimit::max(MAX_BODY_SIZE))
         .layer(
             CorsLayer::new()
-                .allow_origin(Any)
-                .allow_methods(Any)
-                .allow_headers(Any),
-        );
+                .allow_origin(cors_origins)
+                .allow_methods([Method::POST, Method::OPTIONS])
+                .allow_headers([header::CONTENT_TYPE])
+                .expose_headers([REQUEST_ID_HEADER]),
+        )
+        .layer(middleware::from_fn(request_id));
+
+    let addr = format!(
+        "{}:{}",
+        crate::config::CONFIG.server_host,
+        crate::config::CONFIG.server_port.get()
+    );
+
+    let addr = addr.parse::<SocketAddr>().unwrap_or_else(|e| {
+        panic!(
+            "Invalid panel API bind address '{}' (check server_host and server_port in config.yaml): {}",
+            addr, e
+        )
+    });
 
-    let addr = format!("127.0.0.1:{}", crate::config::CONFIG.server_port.get());
     info!("Starting server on {}", addr);
 
     let listener = tokio::net::TcpListener::bind(addr)
         .await
         .expect("Failed to bind to port");
 
-    if let Err(e) = axum::serve(listener, app.into_make_service()).await {
-        panic!("RPC server error: {}", e);
+    let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel::<()>();
+
+    let server = axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(async move {
+        shutdown.cancelled().await;
+        info!("Stopping panel API");
+        let _ = shutdown_tx.send(());
+    })
+    .into_future();
+
+    tokio::pin!(server);
+
+    tokio::select! {
+        res = &mut server => {
+            if let Err(e) = res {
+                panic!("RPC server error: {}", e);
+            }
+
+            info!("Panel API stopped, all in-flight requests finished");
+        }
+        _ = async {
+            if shutdown_rx.await.is_ok() {
+                tokio::time::sleep(SHUTDOWN_TIMEOUT).await;
+            } else {
+                std::future::pending::<()>().await;
+            }
+        } => {
+            warn!(
+                "Panel API did not stop within {} seconds, dropping remaining requests",
+                SHUTDOWN_TIMEOUT.as_secs()
+            );
+        }
     }
 }
 
+/// Health check, returns 503 if the database is unreachable
+#[utoipa::path(
+    get,
+    path = "/health",
+    responses(
+        (status = 200, description = "Healthy", body = Health),
+        (status = SERVICE_UNAVAILABLE, description = "Database is unreachable", body = Health),
+    ),
+)]
+async fn health(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let database = sqlx::query!("SELECT 1 AS ok")
+        .fetch_one(&state.pool)
+        .await
+        .is_ok();
+
+    let discord_ready = state.cache_http.cache.guild_count() > 0;
+
+    let status = if database {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    (
+        status,
+        Json(Health {
+            database,
+            discord_ready,
+        }),
+    )
+}
+
 /// Make Panel Query
 #[utoipa::path(
     post,
     request_body = PanelQuery,
     path = "/",
     responses(
-        (status = 200, description = "Content", body = String),
+        (status = 200, description = "Content, the schema depends on the query (see the `Responses` schemas)", body = String),
+        (status = 202, description = "Confirmation needed before a destructive RPC method is run", body = RPCConfirmation),
         (status = 204, description = "No content"),
         (status = BAD_REQUEST, description = "An error occured", body = String),
+        (status = FORBIDDEN, description = "Missing permissions", body = String),
+        (status = NOT_FOUND, description = "Not found", body = String),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal error", body = String),
     ),
 )]
 #[axum::debug_handler]
 async fn query(
     State(state): State<Arc<AppState>>,
+    Extension(request_id): Extension<RequestId>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
     Json(req): Json<PanelQuery>,
+) -> Response {
+    let variant = req.to_string();
+    let start = Instant::now();
+
+    let client = ClientInfo {
+        ip: client_ip(addr.ip(), &headers),
+        user_agent: headers
+            .get(header::USER_AGENT)
+            .and_then(|h| h.to_str().ok())
+            .map(|h| h.to_string()),
+    };
+
+    let resp = match handle_query(state, req, client).await {
+        Ok(resp) => resp.into_response(),
+        Err(e) => {
+            error!(
+                "[{}] {} failed with {}: {}",
+                request_id.0, variant, e.status, e.message
+            );
+            e.into_response()
+        }
+    };
+
+    info!(
+        "[{}] {} -> {} in {}ms",
+        request_id.0,
+        variant,
+        resp.status().as_u16(),
+        start.elapsed().as_millis()
+    );
+
+    metrics::counter!(
+        "panel_queries_total",
+        "variant" => variant.clone(),
+        "status" => resp.status().as_u16().to_string()
+    )
+    .increment(1);
+    metrics::histogram!("panel_query_duration_seconds", "variant" => variant)
+        .record(start.elapsed().as_secs_f64());
+
+    resp
+}
+
+/// Records a click or impression of a partner, this is public so the frontend can call it directly
+async fn record_partner_event(
+    State(state): State<Arc<AppState>>,
+    Path(partner_id): Path<String>,
+    Json(event): Json<PartnerEvent>,
+) -> Response {
+    let (clicks, impressions): (i64, i64) = match event {
+        PartnerEvent::Click => (1, 0),
+        PartnerEvent::Impression => (0, 1),
+    };
+
+    let res = sqlx::query!(
+        "INSERT INTO partner_analytics (partner_id, clicks, impressions)
+        SELECT id, $2, $3 FROM partners WHERE id = $1 AND deleted_at IS NULL
+        ON CONFLICT (partner_id, day) DO UPDATE SET clicks = partner_analytics.clicks + $2, impressions = partner_analytics.impressions + $3",
+        partner_id,
+        clicks,
+        impressions
+    )
+    .execute(&state.pool)
+    .await;
+
+    match res {
+        Ok(r) if r.rows_affected() == 0 => {
+            PanelError::NotFound("Partner not found".to_string()).into_response()
+        }
+        Ok(_) => (StatusCode::NO_CONTENT, "").into_response(),
+        Err(e) => Error::new(e).into_response(),
+    }
+}
+
+/// ID of a request, returned in the `X-Request-Id` header so failed requests can be found in the logs
+#[derive(Clone)]
+struct RequestId(String);
+
+/// Assigns a request ID to every request
+async fn request_id(mut req: Request, next: Next) -> Response {
+    let id = uuid::Uuid::new_v4().to_string();
+
+    req.extensions_mut().insert(RequestId(id.clone()));
+
+    let mut resp = next.run(req).await;
+
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        resp.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+
+    resp
+}
+
+/// Prometheus metrics, requires the configured metrics token as a bearer token
+async fn prometheus_metrics(State(state): State<Arc<AppState>>, headers: HeaderMap) -> Response {
+    let token = &crate::config::CONFIG.panel.metrics_token;
+
+    if token.is_empty() {
+        return (StatusCode::NOT_FOUND, "Metrics are disabled".to_string()).into_response();
+    }
+
+    let authorized = headers
+        .get("Authorization")
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .map(|h| {
+            ring::constant_time::verify_slices_are_equal(h.as_bytes(), token.as_bytes()).is_ok()
+        })
+        .unwrap_or(false);
+
+    if !authorized {
+        return (
+            StatusCode::UNAUTHORIZED,
+            "Invalid metrics token".to_string(),
+        )
+            .into_response();
+    }
+
+    state.metrics.render().into_response()
+}
+
+/// Checks the limits of a shop coupon and that all of its applicable items exist
+async fn validate_shop_coupon(
+    pool: &sqlx::PgPool,
+    max_uses: Option<i32>,
+    reuse_wait_duration: Option<i32>,
+    expiry: Option<i32>,
+    cents: Option<f64>,
+    applicable_items: &[String],
+) -> Result<(), Error> {
+    if max_uses.is_some_and(|v| v <= 0) {
+        return Err(PanelError::BadRequest("Max uses must be greater than 0".to_string()).into());
+    }
+
+    if reuse_wait_duration.is_some_and(|v| v <= 0) {
+        return Err(PanelError::BadRequest(
+            "Reuse wait duration must be greater than 0".to_string(),
+        )
+        .into());
+    }
+
+    if expiry.is_some_and(|v| v <= 0) {
+        return Err(PanelError::BadRequest("Expiry must be greater than 0".to_string()).into());
+    }
+
+    if cents.is_some_and(|c| c < 0.0) {
+        return Err(PanelError::BadRequest("Cents cannot be lower than 0".to_string()).into());
+    }
+
+    for item in applicable_items {
+        let rows = sqlx::query!("SELECT COUNT(*) FROM shop_items WHERE id = $1", item)
+            .fetch_one(pool)
+            .await
+            .map_err(Error::new)?;
+
+        if rows.count.unwrap_or(0) == 0 {
+            return Err(PanelError::BadRequest(format!("Item {:#?} does not exist", item)).into());
+        }
+    }
+
+    Ok(())
+}
+
+/// Turns a violation of the unique coupon code index into a `BadRequest`
+fn shop_coupon_write_error(e: sqlx::Error) -> Error {
+    match e {
+        sqlx::Error::Database(ref db) if db.constraint() == Some("shop_coupons_code_key") => {
+            PanelError::BadRequest("A coupon with this code already exists".to_string()).into()
+        }
+        e => Error::new(e),
+    }
+}
+
+/// Checks that a bot whitelist entry has a reason and, if it expires, that the expiry is in the future
+fn validate_bot_whitelist_entry(
+    reason: &str,
+    expires_at: Option<chrono::DateTime<chrono::Utc>>,
+) -> Result<(), Error> {
+    if reason.trim().is_empty() {
+        return Err(PanelError::BadRequest("A reason must be provided".to_string()).into());
+    }
+
+    if expires_at.is_some_and(|e| e <= chrono::Utc::now()) {
+        return Err(PanelError::BadRequest("Expiry must be in the future".to_string()).into());
+    }
+
+    Ok(())
+}
+
+async fn handle_query(
+    state: Arc<AppState>,
+    req: PanelQuery,
+    client: ClientInfo,
 ) -> Result<impl IntoResponse, Error> {
     match req {
         PanelQuery::Authorize { version, action } => {
-            super::actions::authorize::authorize(&state, version, action).await
+            super::actions::authorize::authorize(&state, version, action, &client).await
+        }
+        PanelQuery::ListSessions { login_token } => {
+            actions::sessions::list_sessions(&state, login_token).await
+        }
+        PanelQuery::RenameSession {
+            login_token,
+            session_id,
+            name,
+        } => actions::sessions::rename_session(&state, login_token, session_id, name).await,
+        PanelQuery::PurgeExpiredSessions { login_token } => {
+            actions::sessions::purge_expired_sessions(&state, login_token).await
+        }
+        PanelQuery::GetFullIdentity { login_token } => {
+            actions::getfullidentity::get_full_identity(&state, login_token).await
         }
         PanelQuery::Hello {
             login_token,
             version,
         } => super::actions::hello::hello(&state, login_token, version).await,
+        PanelQuery::SetInstanceConfig {
+            login_token,
+            instance_config,
+        } => {
+            super::actions::setinstanceconfig::set_instance_config(
+                &state,
+                login_token,
+                instance_config,
+            )
+            .await
+        }
+        PanelQuery::ReloadCdnScopes { login_token } => {
+            actions::reloadcdnscopes::reload_cdn_scopes(&state, login_token).await
+        }
         PanelQuery::BaseAnalytics { login_token } => {
             super::actions::baseanalytics::base_analytics(&state, login_token).await
         }
@@ -158,20 +828,103 @@ async fn query(
             login_token,
             user_id,
         } => super::actions::getuser::get_user(&state, login_token, user_id).await,
-        PanelQuery::BotQueue { login_token } => {
+        PanelQuery::GetUserBulk {
+            login_token,
+            user_ids,
+        } => super::actions::getuser::get_user_bulk(&state, login_token, user_ids).await,
+        PanelQuery::BotQueue {
+            login_token,
+            only_unclaimed,
+            claimed_by,
+            r#type,
+            limit,
+            cursor,
+        } => {
             super::auth::check_auth(&state.pool, &login_token)
                 .await
-                .map_err(Error::new)?;
+                .map_err(|e| PanelError::NotAuthorized(e.to_string()))?;
 
-            let queue = sqlx::query!(
-                "SELECT bot_id, client_id, last_claimed, claimed_by, type, approval_note, short,
-                invite, approximate_votes, shards, library, invite_clicks, clicks, servers
-                FROM bots WHERE type = 'pending' OR type = 'claimed' ORDER BY created_at"
+            if let Some(ref r#type) = r#type {
+                if r#type != "pending" && r#type != "claimed" {
+                    return Err(PanelError::BadRequest(
+                        "Type must be either pending or claimed".to_string(),
+                    )
+                    .into());
+                }
+            }
+
+            let limit = limit.map(|l| l.clamp(1, MAX_QUEUE_PAGE_SIZE));
+            let cursor = cursor.as_deref().map(PageCursor::decode).transpose()?;
+
+            // Filters are applied outside the window so queue positions stay the same regardless of filtering
+            //
+            // One more bot than requested is fetched to know if there is a next page
+            let mut queue = sqlx::query!(
+                "SELECT bot_id AS \"bot_id!\", client_id AS \"client_id!\", last_claimed, claimed_by,
+                type AS \"type!\", approval_note AS \"approval_note!\", short AS \"short!\",
+                invite AS \"invite!\", approximate_votes AS \"approximate_votes!\", shards AS \"shards!\",
+                library AS \"library!\", invite_clicks AS \"invite_clicks!\", clicks AS \"clicks!\",
+                servers AS \"servers!\", created_at AS \"created_at!\", queue_position
+                FROM (
+                    SELECT bot_id, client_id, last_claimed, claimed_by, type, approval_note, short,
+                    invite, approximate_votes, shards, library, invite_clicks, clicks, servers, created_at,
+                    CASE WHEN type = 'pending' THEN ROW_NUMBER() OVER (PARTITION BY type ORDER BY created_at) END AS queue_position
+                    FROM bots WHERE type = 'pending' OR type = 'claimed'
+                ) queue
+                WHERE ($1::bool = false OR claimed_by IS NULL)
+                AND ($2::text IS NULL OR claimed_by = $2)
+                AND ($3::text IS NULL OR type = $3)
+                AND ($4::timestamptz IS NULL OR (created_at, bot_id) > ($4, $5))
+                ORDER BY created_at, bot_id
+                LIMIT $6",
+                only_unclaimed,
+                claimed_by,
+                r#type,
+                cursor.as_ref().map(|c| c.created_at),
+                cursor.as_ref().map(|c| c.id.clone()),
+                limit.map(|l| l + 1)
             )
             .fetch_all(&state.pool)
             .await
             .map_err(Error::new)?;
 
+            let next_cursor = match limit {
+                Some(limit) if queue.len() as i64 > limit => {
+                    queue.truncate(limit as usize);
+
+                    queue
+                        .last()
+                        .map(|bot| {
+                            PageCursor {
+                                exact: false,
+                                similarity: 0.0,
+                                created_at: bot.created_at,
+                                id: bot.bot_id.clone(),
+                            }
+                            .encode()
+                        })
+                        .transpose()?
+                }
+                _ => None,
+            };
+
+            // Bots leave the queue when approved or denied, so use both to work out throughput
+            let reviews = sqlx::query!(
+                "SELECT COUNT(*) FROM rpc_logs WHERE (method = 'Approve' OR method = 'Deny') AND state = 'success' AND created_at > NOW() - make_interval(days => $1)",
+                QUEUE_THROUGHPUT_WINDOW_DAYS
+            )
+            .fetch_one(&state.pool)
+            .await
+            .map_err(Error::new)?
+            .count
+            .unwrap_or(0);
+
+            let seconds_per_review = if reviews > 0 {
+                Some(i64::from(QUEUE_THROUGHPUT_WINDOW_DAYS) * 24 * 60 * 60 / reviews)
+            } else {
+                None
+            };
+
             let mut bots = Vec::new();
 
             for bot in queue {
@@ -208,40 +961,55 @@ async fn query(
                     servers: bot.servers,
                     mentionable: owners.mentionables(),
                     invite: bot.invite,
+                    queue_position: bot.queue_position,
+                    estimated_wait: bot
+                        .queue_position
+                        .zip(seconds_per_review)
+                        .map(|(position, seconds)| position * seconds),
                 }));
             }
 
-            Ok((StatusCode::OK, Json(bots)).into_response())
+            Ok((StatusCode::OK, Json(BotQueuePage { bots, next_cursor })).into_response())
         }
         PanelQuery::ExecuteRpc {
             login_token,
             target_type,
             method,
+            dry_run,
+            confirmation_token,
         } => {
             let auth_data = super::auth::check_auth(&state.pool, &login_token)
                 .await
-                .map_err(Error::new)?;
-
-            let resp = method
-                .handle(RPCHandle {
-                    pool: state.pool.clone(),
-                    cache_http: state.cache_http.clone(),
-                    user_id: auth_data.user_id,
-                    target_type,
-                })
-                .await;
-
-            match resp {
-                Ok(r) => match r {
-                    crate::rpc::core::RPCSuccess::NoContent => {
-                        Ok((StatusCode::NO_CONTENT, "").into_response())
-                    }
-                    crate::rpc::core::RPCSuccess::Content(c) => {
-                        Ok((StatusCode::OK, c).into_response())
-                    }
-                },
-                Err(e) => Ok((StatusCode::BAD_REQUEST, e.to_string()).into_response()),
-            }
+                .map_err(|e| PanelError::NotAuthorized(e.to_string()))?;
+
+            let handle = RPCHandle {
+                pool: state.pool.clone(),
+                cache_http: state.cache_http.clone(),
+                user_id: auth_data.user_id,
+                target_type,
+                confirmation_token,
+            };
+
+            let resp = if dry_run {
+                method.dry_run(handle).await
+            } else {
+                method.handle(handle).await
+            };
+
+            Ok(super::core::rpc_response(resp))
+        }
+        PanelQuery::ApproveBot {
+            login_token,
+            bot_id,
+            reason,
+        } => actions::reviewbot::approve_bot(&state, login_token, bot_id, reason).await,
+        PanelQuery::DenyBot {
+            login_token,
+            bot_id,
+            reason,
+        } => actions::reviewbot::deny_bot(&state, login_token, bot_id, reason).await,
+        PanelQuery::GetPermissionMatrix { login_token } => {
+            actions::getpermissionmatrix::get_permission_matrix(&state, login_token).await
         }
         PanelQuery::GetRpcMethods {
             login_token,
@@ -249,12 +1017,11 @@ async fn query(
         } => {
             let auth_data = super::auth::check_auth(&state.pool, &login_token)
                 .await
-                .map_err(Error::new)?;
+                .map_err(|e| PanelError::NotAuthorized(e.to_string()))?;
 
-            let user_perms = get_user_perms(&state.pool, &auth_data.user_id)
-                .await
-                .map_err(Error::new)?
-                .resolve();
+            let user_perms = state
+                .get_user_perms(&login_token, &auth_data.user_id)
+                .await?;
 
             let mut rpc_methods = Vec::new();
 
@@ -268,93 +1035,189 @@ async fn query(
                     }
                 }
 
-                let action = RPCWebAction {
-                    id: method.to_string(),
-                    label: variant.label(),
-                    description: variant.description(),
-                    supported_target_types: variant.supported_target_types(),
-                    fields: variant.method_fields(),
-                };
-
-                rpc_methods.push(action);
+                rpc_methods.push(RPCWebAction::new(&variant));
             }
 
             Ok((StatusCode::OK, Json(rpc_methods)).into_response())
         }
-        PanelQuery::GetRpcLogEntries { login_token } => {
-            let auth_data = super::auth::check_auth(&state.pool, &login_token)
+        PanelQuery::GetRpcMethod {
+            login_token,
+            method_id,
+        } => {
+            super::auth::check_auth(&state.pool, &login_token)
                 .await
-                .map_err(Error::new)?;
+                .map_err(|e| PanelError::NotAuthorized(e.to_string()))?;
 
-            let user_perms = get_user_perms(&state.pool, &auth_data.user_id)
+            let Ok(variant) = crate::rpc::core::RPCMethod::from_str(&method_id) else {
+                return Err(PanelError::NotFound(format!(
+                    "RPC method {} does not exist",
+                    method_id
+                ))
+                .into());
+            };
+
+            Ok((StatusCode::OK, Json(RPCWebAction::new(&variant))).into_response())
+        }
+        PanelQuery::GetRpcMethodsForTarget {
+            login_token,
+            target_type,
+            filtered,
+        } => {
+            let auth_data = super::auth::check_auth(&state.pool, &login_token)
                 .await
-                .map_err(Error::new)?
-                .resolve();
+                .map_err(|e| PanelError::NotAuthorized(e.to_string()))?;
 
-            if !perms::has_perm(&user_perms, &"rpc_logs.view".into()) {
-                return Ok((
-                    StatusCode::FORBIDDEN,
-                    "You do not have permission to view rpc logs [rpc_logs.view]".to_string(),
-                )
-                    .into_response());
-            }
+            let user_perms = state
+                .get_user_perms(&login_token, &auth_data.user_id)
+                .await?;
 
-            let entries = sqlx::query!(
-                "SELECT id, user_id, method, data, state, created_at FROM rpc_logs ORDER BY created_at DESC"
-            )
-            .fetch_all(&state.pool)
-            .await
-            .map_err(Error::new)?;
+            let mut rpc_methods = Vec::new();
+
+            for method in crate::rpc::core::RPCMethod::VARIANTS {
+                let variant = crate::rpc::core::RPCMethod::from_str(method).map_err(Error::new)?;
+
+                if !variant.supported_target_types().contains(&target_type) {
+                    continue;
+                }
+
+                if filtered {
+                    let required_perm = format!("rpc.{}", variant).into();
+                    if !perms::has_perm(&user_perms, &required_perm) {
+                        continue;
+                    }
+                }
 
-            let mut rpc_log = vec![];
-
-            for entry in entries {
-                rpc_log.push(RPCLogEntry {
-                    id: entry.id.to_string(),
-                    user_id: entry.user_id,
-                    method: entry.method,
-                    data: entry.data,
-                    state: entry.state,
-                    created_at: entry.created_at,
-                });
+                rpc_methods.push(RPCWebAction::new(&variant));
             }
 
-            Ok((StatusCode::OK, Json(rpc_log)).into_response())
+            Ok((StatusCode::OK, Json(rpc_methods)).into_response())
+        }
+        PanelQuery::GetRpcLogEntries {
+            login_token,
+            filters,
+            limit,
+            offset,
+        } => {
+            actions::getrpclogentries::get_rpc_log_entries(
+                &state,
+                login_token,
+                filters,
+                limit,
+                offset,
+            )
+            .await
+        }
+        PanelQuery::GetStaffDisciplinaries {
+            login_token,
+            user_id,
+            active_only,
+            after,
+            before,
+        } => {
+            actions::getstaffdisciplinaries::get_staff_disciplinaries_of(
+                &state,
+                login_token,
+                user_id,
+                active_only,
+                after,
+                before,
+            )
+            .await
+        }
+        PanelQuery::GetUserPermsBulk {
+            login_token,
+            user_ids,
+        } => actions::getuserpermsbulk::get_user_perms_bulk(&state, login_token, user_ids).await,
+        PanelQuery::RetryRpcLogEntry {
+            login_token,
+            log_id,
+            confirmation_token,
+        } => {
+            actions::retryrpclogentry::retry_rpc_log_entry(
+                &state,
+                login_token,
+                log_id,
+                confirmation_token,
+            )
+            .await
         }
         PanelQuery::SearchEntitys {
             login_token,
             target_type,
             query,
+            limit,
+            offset,
+            cursor,
         } => {
-            super::actions::searchentitys::search_entitys(&state, login_token, target_type, query)
-                .await
+            super::actions::searchentitys::search_entitys(
+                &state,
+                login_token,
+                target_type,
+                query,
+                limit,
+                offset,
+                cursor,
+            )
+            .await
+        }
+        PanelQuery::TeamList { login_token } => {
+            actions::teamlist::team_list(&state, login_token).await
         }
         PanelQuery::UpdatePartners {
             login_token,
             action,
         } => super::actions::updatepartners::update_partners(&state, login_token, action).await,
+        PanelQuery::RestorePartner {
+            login_token,
+            partner_id,
+        } => super::actions::updatepartners::restore_partner(&state, login_token, partner_id).await,
+        PanelQuery::GetPartnerAnalytics {
+            login_token,
+            partner_id,
+            days,
+        } => {
+            actions::getpartneranalytics::get_partner_analytics(
+                &state,
+                login_token,
+                partner_id,
+                days,
+            )
+            .await
+        }
+        PanelQuery::UpdatePartnerTypes {
+            login_token,
+            action,
+        } => {
+            super::actions::updatepartnertypes::update_partner_types(&state, login_token, action)
+                .await
+        }
+        PanelQuery::GetBlogPosts { login_token } => {
+            actions::getblogposts::get_blog_posts(&state, login_token).await
+        }
+        PanelQuery::GetBlogPost { login_token, slug } => {
+            actions::getblogposts::get_blog_post(&state, login_token, slug).await
+        }
         PanelQuery::UpdateBlog {
             login_token,
             action,
         } => {
             let auth_data = super::auth::check_auth(&state.pool, &login_token)
                 .await
-                .map_err(Error::new)?;
+                .map_err(|e| PanelError::NotAuthorized(e.to_string()))?;
 
-            let user_perms = get_user_perms(&state.pool, &auth_data.user_id)
-                .await
-                .map_err(Error::new)?
-                .resolve();
+            let user_perms = state
+                .get_user_perms(&login_token, &auth_data.user_id)
+                .await?;
 
             // TODO: Make this not require a wasteful query
             let ad = super::auth::check_auth(&state.pool, &login_token)
                 .await
-                .map_err(Error::new)?;
+                .map_err(|e| PanelError::NotAuthorized(e.to_string()))?;
 
             match action {
                 BlogAction::ListEntries => {
                     let rows = sqlx::query!(
-                        "SELECT itag, slug, title, description, user_id, content, created_at, draft, tags FROM blogs ORDER BY created_at DESC"
+                        "SELECT itag, slug, title, description, user_id, content, created_at, draft, published_at, tags FROM blogs ORDER BY created_at DESC"
                     )
                     .fetch_all(&state.pool)
                     .await
@@ -373,6 +1236,7 @@ async fn query(
                             content: row.content,
                             created_at: row.created_at,
                             draft: row.draft,
+                            published_at: row.published_at,
                         });
                     }
 
@@ -385,14 +1249,7 @@ async fn query(
                     content,
                     tags,
                 } => {
-                    if !perms::has_perm(&user_perms, &"blog.create_entry".into()) {
-                        return Ok((
-                            StatusCode::FORBIDDEN,
-                            "You do not have permission to create blog entries [blog.create_entry]"
-                                .to_string(),
-                        )
-                            .into_response());
-                    }
+                    require_perm(&user_perms, "blog.create_entry")?;
 
                     // Insert entry
                     sqlx::query!(
@@ -419,14 +1276,7 @@ async fn query(
                     tags,
                     draft,
                 } => {
-                    if !perms::has_perm(&user_perms, &"blog.update_entry".into()) {
-                        return Ok((
-                            StatusCode::FORBIDDEN,
-                            "You do not have permission to update blog entries [blog.update_entry]"
-                                .to_string(),
-                        )
-                            .into_response());
-                    }
+                    require_perm(&user_perms, "blog.update_entry")?;
 
                     let uuid = sqlx::types::uuid::Uuid::parse_str(&itag).map_err(Error::new)?;
 
@@ -439,15 +1289,14 @@ async fn query(
                         .unwrap_or(0)
                         == 0
                     {
-                        return Ok(
-                            (StatusCode::BAD_REQUEST, "Entry does not exist".to_string())
-                                .into_response(),
-                        );
+                        return Err(PanelError::NotFound("Entry does not exist".to_string()).into());
                     }
 
-                    // Update entry
+                    // Update entry, publishing stamps published_at while drafts only keep a schedule that is still in the future
                     sqlx::query!(
-                        "UPDATE blogs SET slug = $2, title = $3, description = $4, content = $5, tags = $6, draft = $7 WHERE itag = $1",
+                        "UPDATE blogs SET slug = $2, title = $3, description = $4, content = $5, tags = $6, draft = $7,
+                        published_at = CASE WHEN $7 THEN (CASE WHEN published_at > NOW() THEN published_at END) ELSE LEAST(COALESCE(published_at, NOW()), NOW()) END
+                        WHERE itag = $1",
                         uuid,
                         slug,
                         title,
@@ -462,16 +1311,34 @@ async fn query(
 
                     Ok((StatusCode::NO_CONTENT, "").into_response())
                 }
-                BlogAction::DeleteEntry { itag } => {
-                    if !perms::has_perm(&user_perms, &"blog.delete_entry".into()) {
-                        return Ok((
-                            StatusCode::FORBIDDEN,
-                            "You do not have permission to delete blog entries [blog.delete_entry]"
-                                .to_string(),
+                BlogAction::Schedule { slug, publish_at } => {
+                    require_perm(&user_perms, "blog.update_entry")?;
+
+                    if publish_at <= chrono::Utc::now() {
+                        return Err(PanelError::BadRequest(
+                            "Publish time must be in the future".to_string(),
                         )
-                            .into_response());
+                        .into());
+                    }
+
+                    let res = sqlx::query!(
+                        "UPDATE blogs SET draft = true, published_at = $2 WHERE slug = $1",
+                        slug,
+                        publish_at
+                    )
+                    .execute(&state.pool)
+                    .await
+                    .map_err(Error::new)?;
+
+                    if res.rows_affected() == 0 {
+                        return Err(PanelError::NotFound("Entry does not exist".to_string()).into());
                     }
 
+                    Ok((StatusCode::NO_CONTENT, "").into_response())
+                }
+                BlogAction::DeleteEntry { itag } => {
+                    require_perm(&user_perms, "blog.delete_entry")?;
+
                     // Check if entry already exists with same vesion
                     let uuid = sqlx::types::uuid::Uuid::parse_str(&itag).map_err(Error::new)?;
                     if sqlx::query!("SELECT COUNT(*) FROM blogs WHERE itag = $1", uuid)
@@ -482,11 +1349,10 @@ async fn query(
                         .unwrap_or(0)
                         == 0
                     {
-                        return Ok((
-                            StatusCode::BAD_REQUEST,
+                        return Err(PanelError::NotFound(
                             "Entry with same id does not already exist".to_string(),
                         )
-                            .into_response());
+                        .into());
                     }
 
                     // Delete entry
@@ -519,12 +1385,11 @@ async fn query(
         } => {
             let auth_data = super::auth::check_auth(&state.pool, &login_token)
                 .await
-                .map_err(Error::new)?;
+                .map_err(|e| PanelError::NotAuthorized(e.to_string()))?;
 
-            let user_perms = get_user_perms(&state.pool, &auth_data.user_id)
-                .await
-                .map_err(Error::new)?
-                .resolve();
+            let user_perms = state
+                .get_user_perms(&login_token, &auth_data.user_id)
+                .await?;
 
             match action {
                 StaffDisciplinaryTypeAction::ListDisciplinaryTypes => {
@@ -566,13 +1431,7 @@ async fn query(
                     needs_approval,
                     max_expiry,
                 } => {
-                    if !perms::has_perm(&user_perms, &"staff_disciplinary_types.create".into()) {
-                        return Ok((
-                            StatusCode::FORBIDDEN,
-                            "You do not have permission to create staff disciplinary types [staff_disciplinary_types.create]".to_string(),
-                        )
-                            .into_response());
-                    }
+                    require_perm(&user_perms, "staff_disciplinary_types.create")?;
 
                     if let Err(e) = perms::check_patch_changes(
                         &user_perms,
@@ -582,14 +1441,7 @@ async fn query(
                             .map(|x| Permission::from_string(x))
                             .collect::<Vec<Permission>>(),
                     ) {
-                        return Ok((
-                            StatusCode::FORBIDDEN,
-                            format!(
-                                "You do not have permission to edit the following perms: {}",
-                                e
-                            ),
-                        )
-                            .into_response());
+                        return Err(PanelError::PermissionMissing(e.to_string()).into());
                     }
 
                     // Insert entry
@@ -620,13 +1472,7 @@ async fn query(
                     needs_approval,
                     max_expiry,
                 } => {
-                    if !perms::has_perm(&user_perms, &"staff_disciplinary_types.update".into()) {
-                        return Ok((
-                            StatusCode::FORBIDDEN,
-                            "You do not have permission to update staff disciplinary types [staff_disciplinary_types.update]".to_string(),
-                        )
-                            .into_response());
-                    }
+                    require_perm(&user_perms, "staff_disciplinary_types.update")?;
 
                     if let Err(e) = perms::check_patch_changes(
                         &user_perms,
@@ -636,14 +1482,7 @@ async fn query(
                             .map(|x| Permission::from_string(x))
                             .collect::<Vec<Permission>>(),
                     ) {
-                        return Ok((
-                            StatusCode::FORBIDDEN,
-                            format!(
-                                "You do not have permission to edit the following perms: {}",
-                                e
-                            ),
-                        )
-                            .into_response());
+                        return Err(PanelError::PermissionMissing(e.to_string()).into());
                     }
 
                     // Check if entry already exists with same vesion
@@ -658,11 +1497,10 @@ async fn query(
                     .unwrap_or(0)
                         == 0
                     {
-                        return Ok((
-                            StatusCode::BAD_REQUEST,
+                        return Err(PanelError::NotFound(
                             "Entry with same id does not already exist".to_string(),
                         )
-                            .into_response());
+                        .into());
                     }
 
                     // Update entry
@@ -684,13 +1522,7 @@ async fn query(
                     Ok((StatusCode::NO_CONTENT, "").into_response())
                 }
                 StaffDisciplinaryTypeAction::DeleteDisciplinaryType { id } => {
-                    if !perms::has_perm(&user_perms, &"staff_disciplinary_types.delete".into()) {
-                        return Ok((
-                            StatusCode::FORBIDDEN,
-                            "You do not have permission to delete staff disciplinary types [staff_disciplinary_types.delete]".to_string(),
-                        )
-                            .into_response());
-                    }
+                    require_perm(&user_perms, "staff_disciplinary_types.delete")?;
 
                     // Check if entry already exists with same vesion
                     if sqlx::query!(
@@ -704,11 +1536,10 @@ async fn query(
                     .unwrap_or(0)
                         == 0
                     {
-                        return Ok((
-                            StatusCode::BAD_REQUEST,
+                        return Err(PanelError::NotFound(
                             "Entry with same id does not already exist".to_string(),
                         )
-                            .into_response());
+                        .into());
                     }
 
                     // Delete entry
@@ -734,17 +1565,16 @@ async fn query(
         } => {
             let auth_data = super::auth::check_auth(&state.pool, &login_token)
                 .await
-                .map_err(Error::new)?;
+                .map_err(|e| PanelError::NotAuthorized(e.to_string()))?;
 
-            let user_perms = get_user_perms(&state.pool, &auth_data.user_id)
-                .await
-                .map_err(Error::new)?
-                .resolve();
+            let user_perms = state
+                .get_user_perms(&login_token, &auth_data.user_id)
+                .await?;
 
             match action {
                 ShopItemAction::List => {
                     let rows = sqlx::query!(
-                        "SELECT id, name, cents, target_types, benefits, created_at, last_updated, created_by, updated_by, duration, description FROM shop_items ORDER BY created_at DESC"
+                        "SELECT id, name, cents, target_types, benefits, created_at, last_updated, created_by, updated_by, duration, description, version FROM shop_items ORDER BY created_at DESC"
                     )
                     .fetch_all(&state.pool)
                     .await
@@ -765,6 +1595,7 @@ async fn query(
                             updated_by: row.updated_by,
                             duration: row.duration,
                             description: row.description,
+                            version: row.version,
                         });
                     }
 
@@ -779,29 +1610,20 @@ async fn query(
                     duration,
                     description,
                 } => {
-                    if !perms::has_perm(&user_perms, &"shop_items.create".into()) {
-                        return Ok((
-                            StatusCode::FORBIDDEN,
-                            "You do not have permission to create shop items [shop_items.create]"
-                                .to_string(),
-                        )
-                            .into_response());
-                    }
+                    require_perm(&user_perms, "shop_items.create")?;
 
                     if cents < 0.0 {
-                        return Ok((
-                            StatusCode::BAD_REQUEST,
+                        return Err(PanelError::BadRequest(
                             "Cents cannot be lower than 0".to_string(),
                         )
-                            .into_response());
+                        .into());
                     }
 
                     if duration < 0 {
-                        return Ok((
-                            StatusCode::BAD_REQUEST,
+                        return Err(PanelError::BadRequest(
                             "Duration cannot be lower than 0".to_string(),
                         )
-                            .into_response());
+                        .into());
                     }
 
                     for benefit in &benefits {
@@ -814,11 +1636,11 @@ async fn query(
                         .map_err(Error::new)?;
 
                         if rows.count.unwrap_or(0) == 0 {
-                            return Ok((
-                                StatusCode::BAD_REQUEST,
-                                format!("Benefit {} does not exist", benefit),
-                            )
-                                .into_response());
+                            return Err(PanelError::BadRequest(format!(
+                                "Benefit {} does not exist",
+                                benefit
+                            ))
+                            .into());
                         }
                     }
 
@@ -848,30 +1670,22 @@ async fn query(
                     benefits,
                     duration,
                     description,
+                    version,
                 } => {
-                    if !perms::has_perm(&user_perms, &"shop_items.update".into()) {
-                        return Ok((
-                            StatusCode::FORBIDDEN,
-                            "You do not have permission to update shop items [shop_items.update]"
-                                .to_string(),
-                        )
-                            .into_response());
-                    }
+                    require_perm(&user_perms, "shop_items.update")?;
 
                     if cents < 0.0 {
-                        return Ok((
-                            StatusCode::BAD_REQUEST,
+                        return Err(PanelError::BadRequest(
                             "Cents cannot be lower than 0".to_string(),
                         )
-                            .into_response());
+                        .into());
                     }
 
                     if duration < 0 {
-                        return Ok((
-                            StatusCode::BAD_REQUEST,
+                        return Err(PanelError::BadRequest(
                             "Duration cannot be lower than 0".to_string(),
                         )
-                            .into_response());
+                        .into());
                     }
 
                     for benefit in &benefits {
@@ -884,11 +1698,11 @@ async fn query(
                         .map_err(Error::new)?;
 
                         if rows.count.unwrap_or(0) == 0 {
-                            return Ok((
-                                StatusCode::BAD_REQUEST,
-                                format!("Benefit {} does not exist", benefit),
-                            )
-                                .into_response());
+                            return Err(PanelError::BadRequest(format!(
+                                "Benefit {} does not exist",
+                                benefit
+                            ))
+                            .into());
                         }
                     }
 
@@ -901,16 +1715,15 @@ async fn query(
                         .unwrap_or(0)
                         == 0
                     {
-                        return Ok((
-                            StatusCode::BAD_REQUEST,
+                        return Err(PanelError::NotFound(
                             "Entry with same id does not already exist".to_string(),
                         )
-                            .into_response());
+                        .into());
                     }
 
-                    // Update entry
-                    sqlx::query!(
-                        "UPDATE shop_items SET name = $1, cents = $2, target_types = $3, benefits = $4, last_updated = NOW(), updated_by = $5, duration = $6, description = $7 WHERE id = $8",
+                    // Update entry, only if nobody else has updated it since the client read it
+                    let res = sqlx::query!(
+                        "UPDATE shop_items SET name = $1, cents = $2, target_types = $3, benefits = $4, last_updated = NOW(), updated_by = $5, duration = $6, description = $7, version = version + 1 WHERE id = $8 AND version = $9",
                         name,
                         cents,
                         &target_types,
@@ -919,22 +1732,20 @@ async fn query(
                         duration,
                         description,
                         id,
+                        version,
                     )
                     .execute(&state.pool)
                     .await
                     .map_err(Error::new)?;
 
+                    if res.rows_affected() == 0 {
+                        return Err(PanelError::StaleWrite.into());
+                    }
+
                     Ok((StatusCode::NO_CONTENT, "").into_response())
                 }
                 ShopItemAction::Delete { id } => {
-                    if !perms::has_perm(&user_perms, &"shop_items.delete".into()) {
-                        return Ok((
-                            StatusCode::FORBIDDEN,
-                            "You do not have permission to delete shop items [shop_items.delete]"
-                                .to_string(),
-                        )
-                            .into_response());
-                    }
+                    require_perm(&user_perms, "shop_items.delete")?;
 
                     // Check if entry already exists with same vesion
                     if sqlx::query!("SELECT COUNT(*) FROM shop_items WHERE id = $1", id)
@@ -945,11 +1756,10 @@ async fn query(
                         .unwrap_or(0)
                         == 0
                     {
-                        return Ok((
-                            StatusCode::BAD_REQUEST,
+                        return Err(PanelError::NotFound(
                             "Entry with same id does not already exist".to_string(),
                         )
-                            .into_response());
+                        .into());
                     }
 
                     // Delete entry
@@ -968,12 +1778,11 @@ async fn query(
         } => {
             let auth_data = super::auth::check_auth(&state.pool, &login_token)
                 .await
-                .map_err(Error::new)?;
+                .map_err(|e| PanelError::NotAuthorized(e.to_string()))?;
 
-            let user_perms = get_user_perms(&state.pool, &auth_data.user_id)
-                .await
-                .map_err(Error::new)?
-                .resolve();
+            let user_perms = state
+                .get_user_perms(&login_token, &auth_data.user_id)
+                .await?;
 
             match action {
                 ShopItemBenefitAction::List => {
@@ -1007,13 +1816,7 @@ async fn query(
                     description,
                     target_types,
                 } => {
-                    if !perms::has_perm(&user_perms, &"shop_item_benefits.create".into()) {
-                        return Ok((
-                            StatusCode::FORBIDDEN,
-                            "You do not have permission to create shop item benefits [shop_item_benefits.create]".to_string(),
-                        )
-                            .into_response());
-                    }
+                    require_perm(&user_perms, "shop_item_benefits.create")?;
 
                     // Insert entry
                     sqlx::query!(
@@ -1037,13 +1840,7 @@ async fn query(
                     description,
                     target_types,
                 } => {
-                    if !perms::has_perm(&user_perms, &"shop_item_benefits.update".into()) {
-                        return Ok((
-                            StatusCode::FORBIDDEN,
-                            "You do not have permission to update shop item benefits [shop_item_benefits.update]".to_string(),
-                        )
-                            .into_response());
-                    }
+                    require_perm(&user_perms, "shop_item_benefits.update")?;
 
                     // Check if entry already exists with same id
                     if sqlx::query!("SELECT COUNT(*) FROM shop_item_benefits WHERE id = $1", id)
@@ -1054,11 +1851,10 @@ async fn query(
                         .unwrap_or(0)
                         == 0
                     {
-                        return Ok((
-                            StatusCode::BAD_REQUEST,
+                        return Err(PanelError::NotFound(
                             "Entry with same id does not already exist".to_string(),
                         )
-                            .into_response());
+                        .into());
                     }
 
                     // Update entry
@@ -1077,13 +1873,7 @@ async fn query(
                     Ok((StatusCode::NO_CONTENT, "").into_response())
                 }
                 ShopItemBenefitAction::Delete { id } => {
-                    if !perms::has_perm(&user_perms, &"shop_item_benefits.delete".into()) {
-                        return Ok((
-                            StatusCode::FORBIDDEN,
-                            "You do not have permission to delete shop item benefits [shop_item_benefits.delete]".to_string(),
-                        )
-                            .into_response());
-                    }
+                    require_perm(&user_perms, "shop_item_benefits.delete")?;
 
                     // Check if entry already exists with same vesion
                     if sqlx::query!("SELECT COUNT(*) FROM shop_item_benefits WHERE id = $1", id)
@@ -1094,11 +1884,10 @@ async fn query(
                         .unwrap_or(0)
                         == 0
                     {
-                        return Ok((
-                            StatusCode::BAD_REQUEST,
+                        return Err(PanelError::NotFound(
                             "Entry with same id does not already exist".to_string(),
                         )
-                            .into_response());
+                        .into());
                     }
 
                     // Check for shop items with this benefit
@@ -1113,11 +1902,10 @@ async fn query(
                     .unwrap_or(0)
                         > 0
                     {
-                        return Ok((
-                            StatusCode::BAD_REQUEST,
+                        return Err(PanelError::BadRequest(
                             "Cannot delete benefit as it is used by shop items".to_string(),
                         )
-                            .into_response());
+                        .into());
                     }
 
                     // Delete entry
@@ -1136,22 +1924,15 @@ async fn query(
         } => {
             let auth_data = super::auth::check_auth(&state.pool, &login_token)
                 .await
-                .map_err(Error::new)?;
+                .map_err(|e| PanelError::NotAuthorized(e.to_string()))?;
 
-            let user_perms = get_user_perms(&state.pool, &auth_data.user_id)
-                .await
-                .map_err(Error::new)?
-                .resolve();
+            let user_perms = state
+                .get_user_perms(&login_token, &auth_data.user_id)
+                .await?;
 
             match action {
                 ShopCouponAction::List => {
-                    if !perms::has_perm(&user_perms, &"shop_coupons.list".into()) {
-                        return Ok((
-                            StatusCode::FORBIDDEN,
-                            "You do not have permission to list shop coupons [shop_coupons.list]",
-                        )
-                            .into_response());
-                    }
+                    require_perm(&user_perms, "shop_coupons.list")?;
 
                     let rows = sqlx::query!(
                         "SELECT id, code, public, max_uses, created_at, created_by, last_updated, updated_by, reuse_wait_duration, expiry, applicable_items, cents, requirements, allowed_users, usable, target_types FROM shop_coupons ORDER BY created_at DESC"
@@ -1199,61 +1980,17 @@ async fn query(
                     usable,
                     target_types,
                 } => {
-                    if !perms::has_perm(&user_perms, &"shop_coupons.create".into()) {
-                        return Ok((
-                            StatusCode::FORBIDDEN,
-                            "You do not have permission to create shop coupons [shop_coupons.create]".to_string(),
-                        )
-                            .into_response());
-                    }
-
-                    if max_uses.unwrap_or_default() <= 0 {
-                        return Ok((
-                            StatusCode::BAD_REQUEST,
-                            "Max uses must be greater than 0".to_string(),
-                        )
-                            .into_response());
-                    }
-
-                    if reuse_wait_duration.unwrap_or_default() <= 0 {
-                        return Ok((
-                            StatusCode::BAD_REQUEST,
-                            "Reuse wait duration must be greater than 0".to_string(),
-                        )
-                            .into_response());
-                    }
-
-                    if expiry.unwrap_or_default() <= 0 {
-                        return Ok((
-                            StatusCode::BAD_REQUEST,
-                            "Expiry must be greater than 0".to_string(),
-                        )
-                            .into_response());
-                    }
-
-                    if cents.unwrap_or_default() < 0.0 {
-                        return Ok((
-                            StatusCode::BAD_REQUEST,
-                            "Cents cannot be lower than 0".to_string(),
-                        )
-                            .into_response());
-                    }
-
-                    for item in &applicable_items {
-                        let rows =
-                            sqlx::query!("SELECT COUNT(*) FROM shop_items WHERE id = $1", item)
-                                .fetch_one(&state.pool)
-                                .await
-                                .map_err(Error::new)?;
+                    require_perm(&user_perms, "shop_coupons.create")?;
 
-                        if rows.count.unwrap_or(0) == 0 {
-                            return Ok((
-                                StatusCode::BAD_REQUEST,
-                                format!("Item {:#?} does not exist", item),
-                            )
-                                .into_response());
-                        }
-                    }
+                    validate_shop_coupon(
+                        &state.pool,
+                        max_uses,
+                        reuse_wait_duration,
+                        expiry,
+                        cents,
+                        &applicable_items,
+                    )
+                    .await?;
 
                     // Insert entry
                     sqlx::query!(
@@ -1274,7 +2011,7 @@ async fn query(
                     )
                     .execute(&state.pool)
                     .await
-                    .map_err(Error::new)?;
+                    .map_err(shop_coupon_write_error)?;
 
                     Ok((StatusCode::NO_CONTENT, "").into_response())
                 }
@@ -1292,64 +2029,20 @@ async fn query(
                     usable,
                     target_types,
                 } => {
-                    if !perms::has_perm(&user_perms, &"shop_coupons.update".into()) {
-                        return Ok((
-                            StatusCode::FORBIDDEN,
-                            "You do not have permission to update shop coupons [shop_coupons.update]".to_string(),
-                        )
-                            .into_response());
-                    }
-
-                    if max_uses.unwrap_or_default() <= 0 {
-                        return Ok((
-                            StatusCode::BAD_REQUEST,
-                            "Max uses must be greater than 0".to_string(),
-                        )
-                            .into_response());
-                    }
-
-                    if reuse_wait_duration.unwrap_or_default() <= 0 {
-                        return Ok((
-                            StatusCode::BAD_REQUEST,
-                            "Reuse wait duration must be greater than 0".to_string(),
-                        )
-                            .into_response());
-                    }
-
-                    if expiry.unwrap_or_default() <= 0 {
-                        return Ok((
-                            StatusCode::BAD_REQUEST,
-                            "Expiry must be greater than 0".to_string(),
-                        )
-                            .into_response());
-                    }
-
-                    if cents.unwrap_or_default() < 0.0 {
-                        return Ok((
-                            StatusCode::BAD_REQUEST,
-                            "Cents cannot be lower than 0".to_string(),
-                        )
-                            .into_response());
-                    }
+                    require_perm(&user_perms, "shop_coupons.update")?;
 
-                    for item in &applicable_items {
-                        let rows =
-                            sqlx::query!("SELECT COUNT(*) FROM shop_items WHERE id = $1", item)
-                                .fetch_one(&state.pool)
-                                .await
-                                .map_err(Error::new)?;
-
-                        if rows.count.unwrap_or(0) == 0 {
-                            return Ok((
-                                StatusCode::BAD_REQUEST,
-                                format!("Item {:#?} does not exist", item),
-                            )
-                                .into_response());
-                        }
-                    }
+                    validate_shop_coupon(
+                        &state.pool,
+                        max_uses,
+                        reuse_wait_duration,
+                        expiry,
+                        cents,
+                        &applicable_items,
+                    )
+                    .await?;
 
-                    // Insert entry
-                    sqlx::query!(
+                    // Update entry
+                    let res = sqlx::query!(
                         "UPDATE shop_coupons SET code = $1, public = $2, max_uses = $3, reuse_wait_duration = $4, expiry = $5, applicable_items = $6, cents = $7, requirements = $8, updated_by = $9, last_updated = NOW(), allowed_users = $10, usable = $11, target_types = $12 WHERE id = $13",
                         code,
                         public,
@@ -1367,18 +2060,53 @@ async fn query(
                     )
                     .execute(&state.pool)
                     .await
-                    .map_err(Error::new)?;
+                    .map_err(shop_coupon_write_error)?;
+
+                    if res.rows_affected() == 0 {
+                        return Err(
+                            PanelError::NotFound("Coupon does not exist".to_string()).into()
+                        );
+                    }
 
                     Ok((StatusCode::NO_CONTENT, "").into_response())
                 }
+                ShopCouponAction::Validate { code } => {
+                    require_perm(&user_perms, "shop_coupons.list")?;
+
+                    let rec = sqlx::query!(
+                        "SELECT usable, created_at, expiry FROM shop_coupons WHERE code = $1",
+                        code
+                    )
+                    .fetch_optional(&state.pool)
+                    .await
+                    .map_err(Error::new)?;
+
+                    let reason = match rec {
+                        None => Some(ShopCouponInvalidReason::NotFound),
+                        Some(rec) if !rec.usable => Some(ShopCouponInvalidReason::NotUsable),
+                        Some(rec)
+                            if rec.expiry.is_some_and(|hours| {
+                                rec.created_at + chrono::Duration::hours(hours as i64)
+                                    < chrono::Utc::now()
+                            }) =>
+                        {
+                            Some(ShopCouponInvalidReason::Expired)
+                        }
+                        Some(_) => None,
+                    };
+
+                    Ok((
+                        StatusCode::OK,
+                        Json(ShopCouponValidation {
+                            code,
+                            redeemable: reason.is_none(),
+                            reason,
+                        }),
+                    )
+                        .into_response())
+                }
                 ShopCouponAction::Delete { id } => {
-                    if !perms::has_perm(&user_perms, &"shop_coupons.delete".into()) {
-                        return Ok((
-                            StatusCode::FORBIDDEN,
-                            "You do not have permission to delete shop coupons [shop_coupons.delete]".to_string(),
-                        )
-                            .into_response());
-                    }
+                    require_perm(&user_perms, "shop_coupons.delete")?;
 
                     // Check if entry already exists with same vesion
                     if sqlx::query!("SELECT COUNT(*) FROM shop_coupons WHERE id = $1", id)
@@ -1389,11 +2117,10 @@ async fn query(
                         .unwrap_or(0)
                         == 0
                     {
-                        return Ok((
-                            StatusCode::BAD_REQUEST,
+                        return Err(PanelError::NotFound(
                             "Entry with same id does not already exist".to_string(),
                         )
-                            .into_response());
+                        .into());
                     }
 
                     // Delete entry
@@ -1412,17 +2139,16 @@ async fn query(
         } => {
             let auth_data = super::auth::check_auth(&state.pool, &login_token)
                 .await
-                .map_err(Error::new)?;
+                .map_err(|e| PanelError::NotAuthorized(e.to_string()))?;
 
-            let user_perms = get_user_perms(&state.pool, &auth_data.user_id)
-                .await
-                .map_err(Error::new)?
-                .resolve();
+            let user_perms = state
+                .get_user_perms(&login_token, &auth_data.user_id)
+                .await?;
 
             match action {
                 BotWhitelistAction::List => {
                     let rows = sqlx::query!(
-                        "SELECT bot_id, user_id, reason, created_at FROM bot_whitelist ORDER BY created_at DESC"
+                        "SELECT bot_id, user_id, reason, created_at, expires_at FROM bot_whitelist ORDER BY created_at DESC"
                     )
                     .fetch_all(&state.pool)
                     .await
@@ -1436,26 +2162,28 @@ async fn query(
                             user_id: row.user_id,
                             reason: row.reason,
                             created_at: row.created_at,
+                            expires_at: row.expires_at,
                         });
                     }
 
                     Ok((StatusCode::OK, Json(entries)).into_response())
                 }
-                BotWhitelistAction::Add { bot_id, reason } => {
-                    if !perms::has_perm(&user_perms, &"bot_whitelist.create".into()) {
-                        return Ok((
-                            StatusCode::FORBIDDEN,
-                            "You do not have permission to add to the bot whitelist (bot_whitelist.create)".to_string(),
-                        )
-                            .into_response());
-                    }
+                BotWhitelistAction::Add {
+                    bot_id,
+                    reason,
+                    expires_at,
+                } => {
+                    require_perm(&user_perms, "bot_whitelist.create")?;
+
+                    validate_bot_whitelist_entry(&reason, expires_at)?;
 
                     // Insert entry
                     sqlx::query!(
-                        "INSERT INTO bot_whitelist (user_id, bot_id, reason) VALUES ($1, $2, $3)",
+                        "INSERT INTO bot_whitelist (user_id, bot_id, reason, expires_at) VALUES ($1, $2, $3, $4)",
                         &auth_data.user_id,
                         bot_id,
                         reason,
+                        expires_at,
                     )
                     .execute(&state.pool)
                     .await
@@ -1463,14 +2191,14 @@ async fn query(
 
                     Ok((StatusCode::NO_CONTENT, "").into_response())
                 }
-                BotWhitelistAction::Edit { bot_id, reason } => {
-                    if !perms::has_perm(&user_perms, &"bot_whitelist.update".into()) {
-                        return Ok((
-                            StatusCode::FORBIDDEN,
-                            "You do not have permission to update bot whitelist (bot_whitelist.update)".to_string(),
-                        )
-                            .into_response());
-                    }
+                BotWhitelistAction::Edit {
+                    bot_id,
+                    reason,
+                    expires_at,
+                } => {
+                    require_perm(&user_perms, "bot_whitelist.update")?;
+
+                    validate_bot_whitelist_entry(&reason, expires_at)?;
 
                     // Check if entry already exists with same vesion
                     if sqlx::query!(
@@ -1484,17 +2212,17 @@ async fn query(
                     .unwrap_or(0)
                         == 0
                     {
-                        return Ok((
-                            StatusCode::BAD_REQUEST,
+                        return Err(PanelError::NotFound(
                             "Entry with same id does not already exist".to_string(),
                         )
-                            .into_response());
+                        .into());
                     }
 
                     // Update entry
                     sqlx::query!(
-                        "UPDATE bot_whitelist SET reason = $1 WHERE bot_id = $2",
+                        "UPDATE bot_whitelist SET reason = $1, expires_at = $2 WHERE bot_id = $3",
                         reason,
+                        expires_at,
                         bot_id,
                     )
                     .execute(&state.pool)
@@ -1504,13 +2232,7 @@ async fn query(
                     Ok((StatusCode::NO_CONTENT, "").into_response())
                 }
                 BotWhitelistAction::Delete { bot_id } => {
-                    if !perms::has_perm(&user_perms, &"bot_whitelist.delete".into()) {
-                        return Ok((
-                            StatusCode::FORBIDDEN,
-                            "You do not have permission to delete bot whitelist entries (bot_whitelist.delete)".to_string(),
-                        )
-                            .into_response());
-                    }
+                    require_perm(&user_perms, "bot_whitelist.delete")?;
 
                     // Check if entry already exists with same vesion
                     if sqlx::query!(
@@ -1524,11 +2246,10 @@ async fn query(
                     .unwrap_or(0)
                         == 0
                     {
-                        return Ok((
-                            StatusCode::BAD_REQUEST,
+                        return Err(PanelError::NotFound(
                             "Entry with same id does not already exist".to_string(),
                         )
-                            .into_response());
+                        .into());
                     }
 
                     // Delete entry
@@ -10,4 +10,12 @@ pub struct BaseAnalytics {
     pub ticket_counts: std::collections::HashMap<String, i64>,
     pub total_users: i64,
     pub changelogs_count: i64,
+    /// Number of bots in the queue (pending or claimed)
+    pub queue_size: i64,
+    /// Number of bots approved in the last 24 hours
+    pub approved_last_day: i64,
+    /// Number of bots denied in the last 24 hours
+    pub denied_last_day: i64,
+    pub total_staff: i64,
+    pub total_partners: i64,
 }
@@ -45,6 +45,8 @@ pub enum AuthorizeAction {
     },
 
     /// ActivateSession activates a session for a given login token
+    ///
+    /// On an already active session, this refreshes the last MFA check for actions that need step-up re-authentication
     ActivateSession {
         /// Login token
         login_token: String,
@@ -82,3 +84,45 @@ pub struct AuthData {
     pub created_at: i64,
     pub state: String,
 }
+
+/// The current user along with everything needed to render the panel header
+#[derive(Serialize, Deserialize, TS, Clone)]
+#[ts(export, export_to = ".generated/FullIdentity.ts")]
+pub struct FullIdentity {
+    pub auth_data: AuthData,
+    /// The user object of the current user
+    pub user: crate::impls::dovewing::PlatformUser,
+    /// The resolved permissions of the user
+    pub perms: Vec<String>,
+    /// Names of the staff positions of the user, highest position first
+    pub positions: Vec<String>,
+}
+
+/// Number of panel sessions removed by `PurgeExpiredSessions`
+#[derive(Serialize, Deserialize, TS, ToSchema, Clone)]
+#[ts(export, export_to = ".generated/PurgedSessions.ts")]
+pub struct PurgedSessions {
+    /// Pending or active sessions that had expired
+    pub expired: u64,
+    /// Sessions of users who are no longer staff members
+    pub former_staff: u64,
+}
+
+/// A panel session of the current user
+#[derive(Serialize, Deserialize, TS, ToSchema, Clone)]
+#[ts(export, export_to = ".generated/PanelSession.ts")]
+pub struct PanelSession {
+    /// The ID of the session
+    pub id: String,
+    /// User-set label of the session
+    pub name: Option<String>,
+    /// User agent the session was created with
+    pub user_agent: Option<String>,
+    /// Coarse description of the client (e.g. `Firefox on Windows`) based on the user agent
+    pub client: String,
+    /// State of the session (`pending` or `active`)
+    pub state: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    /// Whether this is the session making the request
+    pub current: bool,
+}
@@ -55,6 +55,16 @@ pub enum BlogAction {
         draft: bool,
     },
 
+    /// Schedules a blog entry to be published at the given time
+    ///
+    /// The entry is kept as a draft until then
+    Schedule {
+        /// Slug/vanity code of the blog entry to schedule
+        slug: String,
+        /// When the blog entry should be published
+        publish_at: chrono::DateTime<chrono::Utc>,
+    },
+
     /// Delete a blog entry
     DeleteEntry {
         /// ID of the entry to delete
@@ -81,6 +91,8 @@ pub struct BlogPost {
     pub content: String,
     /// Whether or not the blog entry is a draft or not
     pub draft: bool,
+    /// When the blog post was (or is scheduled to be) published
+    pub published_at: Option<chrono::DateTime<chrono::Utc>>,
     /// The tags for the blog entry
     pub tags: Vec<String>,
 }
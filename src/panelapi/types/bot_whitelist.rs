@@ -12,8 +12,10 @@ pub struct BotWhitelist {
     pub user_id: String,
     /// The reason
     pub reason: String,
-    /// The time the tier was created
+    /// The time the bot was whitelisted
     pub created_at: chrono::DateTime<chrono::Utc>,
+    /// When the entry expires, never expires if unset
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
 }
 
 #[derive(
@@ -39,6 +41,8 @@ pub enum BotWhitelistAction {
         bot_id: String,
         /// The reason
         reason: String,
+        /// When the entry should be removed, never expires if unset
+        expires_at: Option<chrono::DateTime<chrono::Utc>>,
     },
     /// Edit a bot whitelist entry
     Edit {
@@ -46,6 +50,8 @@ pub enum BotWhitelistAction {
         bot_id: String,
         /// The reason
         reason: String,
+        /// When the entry should be removed, never expires if unset
+        expires_at: Option<chrono::DateTime<chrono::Utc>>,
     },
     /// Delete a bot whitelist entry
     Delete {
@@ -23,6 +23,10 @@ pub struct PartialBot {
     pub mentionable: Vec<String>,
     pub invite: String,
     pub client_id: String,
+    /// Position of the bot in the queue (1-indexed, by creation date), only set for pending bots in the bot queue
+    pub queue_position: Option<i64>,
+    /// Rough estimate of how long (in seconds) until the bot is reviewed, based on recent approval throughput
+    pub estimated_wait: Option<i64>,
 }
 
 #[derive(Serialize, Deserialize, TS, ToSchema, Clone)]
@@ -46,9 +50,45 @@ pub struct PartialServer {
     pub mentionable: Vec<String>,
 }
 
+#[derive(Serialize, Deserialize, TS, ToSchema, Clone)]
+#[ts(export, export_to = ".generated/PartialTeam.ts")]
+pub struct PartialTeam {
+    pub id: String,
+    pub name: String,
+    pub avatar: String,
+    /// Number of members on the team
+    pub member_count: i64,
+    /// Members of the team with the global owner (global.*) permission
+    pub owners: Vec<PlatformUser>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
 #[derive(Serialize, Deserialize, ToSchema, TS, EnumVariantNames, Display, Clone)]
 #[ts(export, export_to = ".generated/PartialEntity.ts")]
 pub enum PartialEntity {
     Bot(PartialBot),
     Server(PartialServer),
+    Team(PartialTeam),
+}
+
+/// A page of entity search results
+#[derive(Serialize, Deserialize, TS, ToSchema, Clone)]
+#[ts(export, export_to = ".generated/EntitySearchResults.ts")]
+pub struct EntitySearchResults {
+    /// The entities on this page, best matches first
+    pub entities: Vec<PartialEntity>,
+    /// Total number of entities matching the query
+    pub total: i64,
+    /// Cursor to fetch the next page with, `None` if this is the last page
+    pub next_cursor: Option<String>,
+}
+
+/// A page of the bot queue
+#[derive(Serialize, Deserialize, TS, ToSchema, Clone)]
+#[ts(export, export_to = ".generated/BotQueuePage.ts")]
+pub struct BotQueuePage {
+    /// The bots on this page, oldest first
+    pub bots: Vec<PartialEntity>,
+    /// Cursor to fetch the next page with, `None` if this is the last page
+    pub next_cursor: Option<String>,
 }
@@ -4,6 +4,7 @@ pub mod blog;
 pub mod bot_whitelist;
 pub mod entity;
 pub mod partners;
+pub mod permissions;
 pub mod rpc;
 pub mod rpclogs;
 pub mod shop_items;
@@ -31,19 +31,75 @@ pub enum PartnerAction {
     Create {
         /// Create partner data
         partner: CreatePartner,
+        /// Optional client-generated key, retrying a successful create with the same key returns the original result instead of creating the partner again.
+        /// Returns 409 Conflict while another request with the key is still being processed
+        #[serde(default)]
+        idempotency_key: Option<String>,
     },
 
     /// Update a partner
     Update {
         /// Update partner data
         partner: CreatePartner,
+        /// The version of the partner the update is based on, the update is rejected if the partner has changed since
+        version: i32,
     },
 
     /// Delete a partner
+    ///
+    /// The partner can be restored using `RestorePartner` until it is permanently deleted
+    /// once the restore window has passed
     Delete {
         /// ID for the partner to delete
         id: String,
     },
+
+    /// Move a partner to a new position, shifting the partners in between
+    Reposition {
+        /// ID for the partner to move
+        id: String,
+        /// The new position of the partner, positions past the last partner move it to the end
+        new_position: i32,
+    },
+}
+
+#[derive(
+    Serialize,
+    Deserialize,
+    ToSchema,
+    TS,
+    EnumString,
+    EnumVariantNames,
+    Display,
+    Clone,
+    PartialEq,
+    Default,
+)]
+#[ts(export, export_to = ".generated/PartnerTypeAction.ts")]
+pub enum PartnerTypeAction {
+    /// List partner types
+    #[default]
+    List,
+
+    /// Create a new partner type
+    Create {
+        /// Create partner type data
+        partner_type: CreatePartnerType,
+    },
+
+    /// Update a partner type
+    Update {
+        /// Update partner type data
+        partner_type: CreatePartnerType,
+    },
+
+    /// Delete a partner type
+    ///
+    /// This will fail if any partners still use the partner type
+    Delete {
+        /// ID for the partner type to delete
+        id: String,
+    },
 }
 
 #[derive(Serialize, Deserialize, PartialEq, TS, Clone, Default, ToSchema)]
@@ -69,6 +125,18 @@ pub struct Partner {
     pub r#type: String,
     pub created_at: DateTime<Utc>,
     pub user_id: String,
+    pub position: i32,
+    /// Incremented on every update
+    pub version: i32,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, TS, Clone, Default, ToSchema)]
+#[ts(export, export_to = ".generated/CreatePartnerType.ts")]
+pub struct CreatePartnerType {
+    pub id: String,
+    pub name: String,
+    pub short: String,
+    pub icon: String,
 }
 
 #[derive(Serialize, Deserialize, PartialEq, TS, Clone, Default, ToSchema)]
@@ -87,3 +155,33 @@ pub struct Partners {
     pub partners: Vec<Partner>,
     pub partner_types: Vec<PartnerType>,
 }
+
+/// Partner analytics for a single day
+#[derive(Serialize, Deserialize, PartialEq, TS, Clone, ToSchema)]
+#[ts(export, export_to = ".generated/PartnerAnalyticsDay.ts")]
+pub struct PartnerAnalyticsDay {
+    pub day: chrono::NaiveDate,
+    pub clicks: i64,
+    pub impressions: i64,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, TS, Clone, ToSchema)]
+#[ts(export, export_to = ".generated/PartnerAnalytics.ts")]
+pub struct PartnerAnalytics {
+    pub partner_id: String,
+    /// Total clicks within the requested range
+    pub total_clicks: i64,
+    /// Total impressions within the requested range
+    pub total_impressions: i64,
+    /// Daily clicks and impressions, oldest first. Days without any activity are left out
+    pub daily: Vec<PartnerAnalyticsDay>,
+}
+
+/// The kind of partner event to record
+#[derive(Serialize, Deserialize, PartialEq, TS, Clone, ToSchema)]
+#[ts(export, export_to = ".generated/PartnerEvent.ts")]
+#[serde(rename_all = "snake_case")]
+pub enum PartnerEvent {
+    Click,
+    Impression,
+}
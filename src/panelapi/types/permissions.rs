@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use utoipa::ToSchema;
+
+/// Whether the user has a permission, and which of their permissions grants it
+#[derive(Serialize, Deserialize, ToSchema, TS, Clone)]
+#[ts(export, export_to = ".generated/PermissionMatrixEntry.ts")]
+pub struct PermissionMatrixEntry {
+    /// The permission being checked (e.g. `partners.create` or `rpc.Claim`)
+    pub perm: String,
+    /// Whether the user has the permission
+    pub allowed: bool,
+    /// The permission of the user that grants this permission (e.g. `partners.*`), if allowed
+    pub granted_by: Option<String>,
+}
+
+/// Every permission checked by the panel and RPC for a user
+#[derive(Serialize, Deserialize, ToSchema, TS, Clone)]
+#[ts(export, export_to = ".generated/PermissionMatrix.ts")]
+pub struct PermissionMatrix {
+    /// Permissions checked by panel queries
+    pub panel: Vec<PermissionMatrixEntry>,
+    /// Permissions needed to run each RPC method
+    pub rpc: Vec<PermissionMatrixEntry>,
+}
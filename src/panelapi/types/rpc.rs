@@ -2,7 +2,10 @@ use serde::{Deserialize, Serialize};
 use ts_rs::TS;
 use utoipa::ToSchema;
 
-use crate::{impls::target_types::TargetType, rpc::core::RPCField};
+use crate::{
+    impls::target_types::TargetType,
+    rpc::core::{RPCField, RPCMethod},
+};
 
 #[derive(Serialize, Deserialize, ToSchema, TS)]
 #[ts(export, export_to = ".generated/RPCWebAction.ts")]
@@ -18,3 +21,28 @@ pub struct RPCWebAction {
     /// Target types supported by the RPC action
     pub supported_target_types: Vec<TargetType>,
 }
+
+impl RPCWebAction {
+    /// Creates the web action for a given RPC method
+    pub fn new(method: &RPCMethod) -> Self {
+        Self {
+            id: method.to_string(),
+            label: method.label(),
+            description: method.description(),
+            supported_target_types: method.supported_target_types(),
+            fields: method.method_fields(),
+        }
+    }
+}
+
+/// Returned when a destructive RPC method needs to be confirmed before it runs
+#[derive(Serialize, Deserialize, ToSchema, TS)]
+#[ts(export, export_to = ".generated/RPCConfirmation.ts")]
+pub struct RPCConfirmation {
+    /// Token to send back in ExecuteRpc to actually run the method
+    pub confirmation_token: String,
+    /// Summary of what the method will do
+    pub summary: String,
+    /// Number of seconds the confirmation token is valid for
+    pub expires_in: u64,
+}
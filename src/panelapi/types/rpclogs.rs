@@ -2,6 +2,8 @@ use serde::{Deserialize, Serialize};
 use ts_rs::TS;
 use utoipa::ToSchema;
 
+use crate::impls::target_types::TargetType;
+
 #[derive(Serialize, Deserialize, ToSchema, TS)]
 #[ts(export, export_to = ".generated/RPCLogEntry.ts")]
 pub struct RPCLogEntry {
@@ -11,11 +13,40 @@ pub struct RPCLogEntry {
     pub user_id: String,
     /// The method used
     pub method: String,
-    /// The state/status of the rpc action
+    /// The target type the method was used on, missing on older entries
+    pub target_type: Option<String>,
+    /// The state/status of the rpc action (`pending`, `success`, `error`, `retrying` or `retried`)
     pub state: String,
+    /// The error returned by the rpc action if it failed
+    pub error: Option<String>,
     /// The data provided
     #[ts(type = "any")]
     pub data: serde_json::Value,
     /// When the entry was created at
     pub created_at: chrono::DateTime<chrono::Utc>,
 }
+
+/// Filters for RPC log entries, all filters are optional
+#[derive(Serialize, Deserialize, ToSchema, TS, Default, Clone)]
+#[ts(export, export_to = ".generated/RPCLogFilters.ts")]
+pub struct RPCLogFilters {
+    /// Only return entries made by this user
+    pub user_id: Option<String>,
+    /// Only return entries for this method
+    pub method: Option<String>,
+    /// Only return entries for this target type
+    pub target_type: Option<TargetType>,
+    /// Only return entries created after this time
+    pub after: Option<chrono::DateTime<chrono::Utc>>,
+    /// Only return entries created before this time
+    pub before: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, TS)]
+#[ts(export, export_to = ".generated/RPCLogEntries.ts")]
+pub struct RPCLogEntries {
+    /// The entries on this page
+    pub entries: Vec<RPCLogEntry>,
+    /// Total number of entries matching the filters
+    pub total: i64,
+}
@@ -97,6 +97,8 @@ pub struct ShopItem {
     pub created_by: String,
     /// Who last updated the shop item
     pub updated_by: String,
+    /// The version of the shop item, incremented on every edit
+    pub version: i32,
 }
 
 #[derive(
@@ -149,6 +151,8 @@ pub enum ShopItemAction {
         benefits: Vec<String>,
         /// The number of hours the shop item lasts for
         duration: i32,
+        /// The version of the shop item the edit is based on, the edit is rejected if the shop item has changed since
+        version: i32,
     },
     /// Deletes a shop item
     Delete {
@@ -307,6 +311,35 @@ pub enum ShopCouponAction {
         /// The ID of the shop coupon
         id: String,
     },
+    /// Checks whether a coupon code is currently redeemable, and why not if it isn't
+    Validate {
+        /// The code of the shop coupon
+        code: String,
+    },
+}
+
+/// Why a shop coupon cannot be redeemed
+#[derive(Serialize, Deserialize, ToSchema, TS, Clone, PartialEq)]
+#[ts(export, export_to = ".generated/ShopCouponInvalidReason.ts")]
+pub enum ShopCouponInvalidReason {
+    /// No coupon with the code exists
+    NotFound,
+    /// The coupon has been marked as not usable
+    NotUsable,
+    /// The coupon is past its expiry
+    Expired,
+}
+
+/// Result of validating a shop coupon code
+#[derive(Serialize, Deserialize, ToSchema, TS, Clone)]
+#[ts(export, export_to = ".generated/ShopCouponValidation.ts")]
+pub struct ShopCouponValidation {
+    /// The code that was validated
+    pub code: String,
+    /// Whether the coupon can currently be redeemed
+    pub redeemable: bool,
+    /// Why the coupon cannot be redeemed, if it can't
+    pub reason: Option<ShopCouponInvalidReason>,
 }
 
 /// Shop holds store items which are owned by entities
@@ -326,6 +359,8 @@ pub struct ShopHold {
     pub created_at: chrono::DateTime<chrono::Utc>,
     /// Duration, in *seconds*
     pub duration: Option<i64>,
+    /// When the hold expires and is released, None if the hold never expires
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
 }
 
 #[derive(
@@ -379,4 +414,6 @@ pub enum ShopHoldAction {
         /// The ID of the shop hold
         id: sqlx::types::uuid::Uuid,
     },
+    /// Releases all expired shop holds now instead of waiting for the background task
+    ReleaseExpired,
 }
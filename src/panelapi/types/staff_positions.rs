@@ -24,13 +24,17 @@ pub enum StaffPositionAction {
     #[default]
     ListPositions,
     /// Swap the index of two staff positions (A and B) such that the indexes change from (Ia, Ib) -> (Ib, Ia)
+    ///
+    /// Returns the resulting positions ordered by index
     SwapIndex {
         /// Staff Position A
         a: String,
         /// Staff Position B
         b: String,
     },
-    /// Sets the new index of a staff position
+    /// Sets the new index of a staff position, shifting positions at or after the new index down by one
+    ///
+    /// Returns the resulting positions ordered by index
     SetIndex {
         /// The ID of the position
         id: String,
@@ -74,6 +78,58 @@ pub enum StaffPositionAction {
         /// The ID of the position
         id: String,
     },
+    /// Returns the effective permissions of a staff member along with what granted each permission
+    ResolvePermissions {
+        /// The user ID of the staff member
+        user_id: String,
+    },
+}
+
+/// Something that grants a staff member permissions
+#[derive(Serialize, Deserialize, ToSchema, TS, Clone, PartialEq)]
+#[ts(export, export_to = ".generated/PermissionSourceKind.ts")]
+pub enum PermissionSourceKind {
+    /// A staff position of the member
+    Position,
+    /// A permission override set on the member
+    Override,
+    /// An active disciplinary action limiting the member
+    Disciplinary,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, TS, Clone)]
+#[ts(export, export_to = ".generated/PermissionSource.ts")]
+pub struct PermissionSource {
+    /// The kind of source
+    pub kind: PermissionSourceKind,
+    /// The ID of the position/disciplinary, empty for overrides
+    pub id: String,
+    /// The name of the position/disciplinary
+    pub name: String,
+    /// The index of the position, lower indexes take priority over higher ones
+    pub index: Option<i32>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, TS, Clone)]
+#[ts(export, export_to = ".generated/ResolvedPermission.ts")]
+pub struct ResolvedPermission {
+    /// The permission
+    pub perm: String,
+    /// Everything granting this permission, highest priority (the one that decides the permission) first
+    pub sources: Vec<PermissionSource>,
+}
+
+/// The effective permissions of a staff member
+///
+/// Positions are applied from the highest index to the lowest, then disciplinaries and finally overrides,
+/// so a later source can negate (`~perm`) or replace what an earlier one granted
+#[derive(Serialize, Deserialize, ToSchema, TS, Clone)]
+#[ts(export, export_to = ".generated/ResolvedPermissions.ts")]
+pub struct ResolvedPermissions {
+    /// The user ID of the staff member
+    pub user_id: String,
+    /// The resolved permissions of the staff member
+    pub perms: Vec<ResolvedPermission>,
 }
 
 #[derive(Serialize, Deserialize, TS, Clone)]
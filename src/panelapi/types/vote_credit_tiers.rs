@@ -81,4 +81,64 @@ pub enum VoteCreditTierAction {
         /// The ID of the tier
         id: String,
     },
+    /// Checks the full set of tiers for overlaps and gaps, returning any issues found
+    Validate,
+    /// Returns the cents that would be awarded for a vote count under the current tiers
+    Simulate {
+        /// The target type to use the tiers of
+        target_type: String,
+        /// The hypothetical number of votes
+        votes: i32,
+    },
+    /// Returns the total cents owed across all entities of a target type under the current tiers and a proposed set of tiers
+    SimulateProposed {
+        /// The target type to simulate
+        target_type: String,
+        /// The proposed tiers, applied in the order given
+        tiers: Vec<ProposedVoteCreditTier>,
+    },
+}
+
+/// A tier in a proposed set of vote credit tiers
+#[derive(Serialize, Deserialize, ToSchema, TS, Clone, PartialEq)]
+#[ts(export, export_to = ".generated/ProposedVoteCreditTier.ts")]
+pub struct ProposedVoteCreditTier {
+    /// The cents per vote
+    pub cents: f64,
+    /// The number of votes in this tier
+    pub votes: i32,
+}
+
+/// Result of simulating the credits for a single vote count
+#[derive(Serialize, Deserialize, TS, Clone)]
+#[ts(export, export_to = ".generated/VoteCreditSimulation.ts")]
+pub struct VoteCreditSimulation {
+    /// The number of votes simulated
+    pub votes: i32,
+    /// The cents that would be awarded
+    pub cents: f64,
+}
+
+/// Result of simulating a proposed set of tiers across all entities of a target type
+#[derive(Serialize, Deserialize, TS, Clone)]
+#[ts(export, export_to = ".generated/VoteCreditBulkSimulation.ts")]
+pub struct VoteCreditBulkSimulation {
+    /// The number of entities simulated
+    pub entities: i64,
+    /// Total cents owed under the current tiers
+    pub current_cents: f64,
+    /// Total cents owed under the proposed tiers
+    pub proposed_cents: f64,
+}
+
+/// A problem with the current set of vote credit tiers that makes credit calculations ambiguous
+#[derive(Serialize, Deserialize, TS, Clone)]
+#[ts(export, export_to = ".generated/VoteCreditTierIssue.ts")]
+pub struct VoteCreditTierIssue {
+    /// The target type of the tiers with the issue
+    pub target_type: String,
+    /// The IDs of the tiers with the issue
+    pub tier_ids: Vec<String>,
+    /// Human readable description of the issue
+    pub reason: String,
 }
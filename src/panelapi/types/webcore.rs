@@ -16,21 +16,37 @@ pub struct InstanceConfig {
     pub warnings: Vec<String>,
 }
 
+impl Default for InstanceConfig {
+    fn default() -> Self {
+        Self {
+            description: if *crate::config::CURRENT_ENV == "staging" {
+                "Arcadia Staging Panel Instance".to_string()
+            } else {
+                "Arcadia Production Panel Instance".to_string()
+            },
+            warnings: vec![
+                "Oh, hello there. This panel is currently being rewritten, and may have some issues. If you find any issues, please contact a Lead Developer in the `Staff Center` Discord Server!".to_string(),
+                "[Warning]: `panel.infinitybots.gg` will soon be unaccessible as we move our panel into the main site.".to_string()
+            ],
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, TS, ToSchema, Clone)]
 #[ts(export, export_to = ".generated/CoreConstants.ts")]
 pub struct CoreConstants {
     /// URL to the main site (reed is used here currently)
     pub frontend_url: String,
-    /// Infernoplex URL
-    pub infernoplex_url: String,
+    /// Infernoplex URL, needs `core_constants.infernoplex_url`
+    pub infernoplex_url: Option<String>,
     /// CDN URL
     pub cdn_url: String,
-    /// Popplio URL
-    pub popplio_url: String,
+    /// Popplio URL, needs `core_constants.popplio_url`
+    pub popplio_url: Option<String>,
     /// HTMLSanitize URL
     pub htmlsanitize_url: String,
-    /// Servers
-    pub servers: PanelServers,
+    /// Servers, needs `core_constants.servers`
+    pub servers: Option<PanelServers>,
 }
 
 /// Same as CONFIG.servers but using strings instead of NonZeroU64s
@@ -54,6 +70,18 @@ pub struct StartAuth {
     pub response_scope: String,
 }
 
+/// Backend diagnostics sampled when the panel says hello
+#[derive(Serialize, Deserialize, TS, ToSchema, Clone)]
+#[ts(export, export_to = ".generated/InstanceDiagnostics.ts")]
+pub struct InstanceDiagnostics {
+    /// Latency of a `SELECT 1` against the database in milliseconds
+    pub db_latency_ms: u64,
+    /// Number of connections currently in the database pool (active and idle)
+    pub db_pool_size: u32,
+    /// Number of idle connections in the database pool
+    pub db_pool_idle: u32,
+}
+
 /// Hello contains the configuration event needed for the panel to work
 #[derive(Serialize, Deserialize, TS, ToSchema, Clone)]
 #[ts(export, export_to = ".generated/Hello.ts")]
@@ -63,4 +91,15 @@ pub struct Hello {
     pub staff_member: StaffMember,
     pub core_constants: CoreConstants,
     pub target_types: Vec<TargetType>,
+    pub diagnostics: InstanceDiagnostics,
+}
+
+/// Health status of the panel API, used for liveness/readiness probes
+#[derive(Serialize, Deserialize, TS, ToSchema, Clone)]
+#[ts(export, export_to = ".generated/Health.ts")]
+pub struct Health {
+    /// Whether the database is reachable
+    pub database: bool,
+    /// Whether the Discord cache has been populated with at least one guild
+    pub discord_ready: bool,
 }
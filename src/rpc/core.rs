@@ -8,12 +8,46 @@ use strum_macros::{Display, EnumString, EnumVariantNames};
 use ts_rs::TS;
 
 use crate::{
-    impls::{target_types::TargetType, utils::get_user_perms},
+    impls::{
+        queue_events::{self, QueueEvent},
+        target_types::TargetType,
+        utils::get_user_perms,
+    },
     Error,
 };
 use kittycat::perms;
+use once_cell::sync::Lazy;
+use std::time::{Duration, Instant};
 use utoipa::ToSchema;
 
+/// Longest cooldown any method can have, used as the TTL of the cooldown cache
+const MAX_COOLDOWN: Duration = Duration::from_secs(60 * 60);
+
+/// When a method was last successfully run, keyed by (user_id, method)
+static COOLDOWNS: Lazy<moka::future::Cache<(String, String), Instant>> = Lazy::new(|| {
+    moka::future::Cache::builder()
+        .time_to_live(MAX_COOLDOWN)
+        .build()
+});
+
+/// How long a confirmation token for a destructive method stays valid
+pub const RPC_CONFIRMATION_TTL: Duration = Duration::from_secs(120);
+
+/// A destructive method waiting to be confirmed
+#[derive(Clone)]
+struct PendingConfirmation {
+    user_id: String,
+    target_type: TargetType,
+    method: RPCMethod,
+}
+
+/// Pending confirmations of destructive methods, keyed by confirmation token
+static CONFIRMATIONS: Lazy<moka::future::Cache<String, PendingConfirmation>> = Lazy::new(|| {
+    moka::future::Cache::builder()
+        .time_to_live(RPC_CONFIRMATION_TTL)
+        .build()
+});
+
 /// Helper function to check if a member is on a server, returning a boolean
 pub fn member_on_guild(
     cache_http: &botox::cache::CacheHttpImpl,
@@ -119,6 +153,14 @@ impl Default for RPCMethod {
 pub enum RPCSuccess {
     NoContent,
     Content(String),
+    /// Returned by a dry run, describes the intended effect of the method
+    DryRun(String),
+    /// The method is destructive and was not run, call it again with the confirmation token to run it
+    ConfirmationRequired {
+        confirmation_token: String,
+        /// Summary of what the method will do
+        summary: String,
+    },
 }
 
 impl RPCSuccess {
@@ -139,6 +181,14 @@ pub struct RPCField {
     pub field_type: FieldType,
     pub icon: String,
     pub placeholder: String,
+    /// Whether the field must be set (non-empty for text fields)
+    pub required: bool,
+    /// Minimum number of characters of the value, only checked for text fields
+    pub min_length: Option<usize>,
+    /// Maximum number of characters of the value, only checked for text fields
+    pub max_length: Option<usize>,
+    /// Regex the value must match, only checked for text fields
+    pub pattern: Option<String>,
 }
 
 impl RPCField {
@@ -149,6 +199,10 @@ impl RPCField {
             field_type: FieldType::Text,
             icon: "ic:twotone-access-time-filled".to_string(),
             placeholder: "The Target ID to perform the action on".to_string(),
+            required: true,
+            min_length: None,
+            max_length: Some(64),
+            pattern: None,
         }
     }
 
@@ -159,6 +213,10 @@ impl RPCField {
             field_type: FieldType::Textarea,
             icon: "material-symbols:question-mark".to_string(),
             placeholder: "Reason for performing this action".to_string(),
+            required: true,
+            min_length: None,
+            max_length: Some(2000),
+            pattern: None,
         }
     }
 }
@@ -181,6 +239,16 @@ pub struct RPCHandle {
     pub cache_http: botox::cache::CacheHttpImpl,
     pub user_id: String,
     pub target_type: TargetType,
+    /// Confirmation token returned by a previous call, needed to run destructive methods
+    pub confirmation_token: Option<String>,
+}
+
+/// The bot a method acts on, as loaded while checking its preconditions
+struct TargetBot {
+    r#type: String,
+    claimed_by: Option<String>,
+    last_claimed: Option<chrono::DateTime<chrono::Utc>>,
+    team_owner: Option<Uuid>,
 }
 
 impl RPCMethod {
@@ -275,7 +343,10 @@ impl RPCMethod {
         .to_string()
     }
 
-    pub async fn handle(&self, state: RPCHandle) -> Result<RPCSuccess, Error> {
+    /// Ensures that the user can run this method on the target type
+    async fn check_can_run(&self, state: &RPCHandle) -> Result<(), Error> {
+        self.validate_fields()?;
+
         // First ensure that target type on handle is in supported target types
         if !self.supported_target_types().contains(&state.target_type) {
             return Err("This method does not support this target type yet".into());
@@ -306,20 +377,100 @@ impl RPCMethod {
             return Err("You need to have completed onboarding in order to use RPC!".into());
         }
 
+        Ok(())
+    }
+
+    /// How long a user must wait before running this method again, regardless of ratelimits
+    ///
+    /// Must not be longer than MAX_COOLDOWN
+    pub fn cooldown(&self) -> Option<Duration> {
+        match self {
+            RPCMethod::VoteReset { .. } => Some(Duration::from_secs(5 * 60)),
+            RPCMethod::VoteResetAll { .. } => Some(Duration::from_secs(30 * 60)),
+            _ => None,
+        }
+    }
+
+    pub async fn handle(&self, state: RPCHandle) -> Result<RPCSuccess, Error> {
+        self.check_can_run(&state).await?;
+
+        let cooldown_key = (state.user_id.clone(), self.to_string());
+
+        if let Some(cooldown) = self.cooldown() {
+            if let Some(last_run) = COOLDOWNS.get(&cooldown_key) {
+                let elapsed = last_run.elapsed();
+
+                if elapsed < cooldown {
+                    return Err(format!(
+                        "{} is on cooldown. Try again in {} seconds",
+                        self,
+                        (cooldown - elapsed).as_secs().max(1)
+                    )
+                    .into());
+                }
+            }
+        }
+
+        // Destructive methods only run once confirmed, this applies to every caller of handle
+        if self.requires_confirmation() {
+            let Some(confirmation_token) = &state.confirmation_token else {
+                let summary = self.summarize(&state).await?;
+
+                let confirmation_token = botox::crypto::gen_random(32);
+
+                CONFIRMATIONS
+                    .insert(
+                        confirmation_token.clone(),
+                        PendingConfirmation {
+                            user_id: state.user_id.clone(),
+                            target_type: state.target_type.clone(),
+                            method: self.clone(),
+                        },
+                    )
+                    .await;
+
+                return Ok(RPCSuccess::ConfirmationRequired {
+                    confirmation_token,
+                    summary,
+                });
+            };
+
+            let Some(pending) = CONFIRMATIONS.remove(confirmation_token).await else {
+                return Err("Confirmation token is invalid or has expired".into());
+            };
+
+            if pending.user_id != state.user_id
+                || pending.target_type != state.target_type
+                || serde_json::to_value(&pending.method)? != serde_json::to_value(self)?
+            {
+                return Err("Confirmation token does not match this request".into());
+            }
+        }
+
         // Insert into rpc_logs
         let id = sqlx::query!(
-            "INSERT INTO rpc_logs (method, user_id, data) VALUES ($1, $2, $3) RETURNING id",
+            "INSERT INTO rpc_logs (method, user_id, data, target_type) VALUES ($1, $2, $3, $4) RETURNING id",
             self.to_string(),
             &state.user_id,
-            json!(self)
+            json!(self),
+            state.target_type.to_string()
         )
         .fetch_one(&state.pool)
         .await?;
 
-        // Get number of requests in the last 7 minutes
+        // Get number of requests for this method within the ratelimit window
+        let method_name = self.to_string();
+        let ratelimit = crate::config::CONFIG
+            .rpc
+            .method_ratelimits
+            .get(&method_name)
+            .unwrap_or(&crate::config::CONFIG.rpc.ratelimit);
+
         let res = sqlx::query!(
-            "SELECT COUNT(*) FROM rpc_logs WHERE user_id = $1 AND NOW() - created_at < INTERVAL '7 minutes'",
-            &state.user_id
+            "SELECT COUNT(*), EXTRACT(epoch FROM MIN(created_at) + make_interval(mins => $3) - NOW())::bigint AS retry_after FROM rpc_logs WHERE user_id = $1 AND method = $2 AND created_at > NOW() - make_interval(mins => $3)",
+            &state.user_id,
+            &method_name,
+            ratelimit.window_minutes
         )
         .fetch_one(&state.pool)
         .await
@@ -327,7 +478,7 @@ impl RPCMethod {
 
         let count = res.count.unwrap_or_default();
 
-        if count > 5 {
+        if count > ratelimit.max_requests {
             sqlx::query!(
                 "DELETE FROM staffpanel__authchain WHERE user_id = $1",
                 &state.user_id,
@@ -336,13 +487,55 @@ impl RPCMethod {
             .await
             .map_err(|_| "Failed to reset user token")?;
 
-            return Err("Rate limit exceeded. Wait 5-10 minutes and try again?".into());
+            return Err(format!(
+                "Rate limit exceeded for {}. Try again in {} seconds",
+                method_name,
+                res.retry_after.unwrap_or_default().max(1)
+            )
+            .into());
         }
 
         // Now we can handle the method
         let resp = self.handle_method(&state).await;
 
         if resp.is_ok() {
+            if self.cooldown().is_some() {
+                COOLDOWNS.insert(cooldown_key, Instant::now()).await;
+            }
+
+            // Let listeners know if the bot queue changed
+            if let RPCMethod::Claim { target_id, .. }
+            | RPCMethod::Unclaim { target_id, .. }
+            | RPCMethod::Approve { target_id, .. }
+            | RPCMethod::Deny { target_id, .. }
+            | RPCMethod::Unverify { target_id, .. } = self
+            {
+                // The action already went through, so a failure here must not turn it into an error
+                match sqlx::query!(
+                    "SELECT type, claimed_by FROM bots WHERE bot_id = $1",
+                    target_id
+                )
+                .fetch_optional(&state.pool)
+                .await
+                {
+                    Ok(Some(bot)) => {
+                        queue_events::notify(
+                            &state.pool,
+                            &QueueEvent {
+                                bot_id: target_id.clone(),
+                                r#type: bot.r#type,
+                                claimed_by: bot.claimed_by,
+                            },
+                        )
+                        .await;
+                    }
+                    Ok(None) => {}
+                    Err(e) => {
+                        error!("Failed to fetch bot {} for queue event: {}", target_id, e);
+                    }
+                }
+            }
+
             sqlx::query!(
                 "UPDATE rpc_logs SET state = $1 WHERE id = $2",
                 "success",
@@ -352,7 +545,7 @@ impl RPCMethod {
             .await?;
         } else {
             sqlx::query!(
-                "UPDATE rpc_logs SET state = $1 WHERE id = $2",
+                "UPDATE rpc_logs SET state = 'error', error = $1 WHERE id = $2",
                 resp.as_ref()
                     .err()
                     .ok_or("Err variant doesnt have an error!")?
@@ -368,31 +561,11 @@ impl RPCMethod {
 
     /// The low-level method handler
     async fn handle_method(&self, state: &RPCHandle) -> Result<RPCSuccess, Error> {
+        let target = self.check_preconditions(state).await?;
+
         match self {
             RPCMethod::Claim { target_id, force } => {
-                // Check if its claimed by someone
-                let claimed = sqlx::query!(
-                    "SELECT type, claimed_by FROM bots WHERE bot_id = $1",
-                    target_id
-                )
-                .fetch_one(&state.pool)
-                .await?;
-
-                if claimed.r#type != "pending" {
-                    return Err("This bot is not pending review".into());
-                }
-
-                if claimed.r#type == "testbot" {
-                    return Err("This bot is a test bot".into());
-                }
-
-                if !force {
-                    if let Some(claimed_by) = claimed.claimed_by {
-                        return Err(
-                            format!("This bot is already claimed by <@{}>", claimed_by).into()
-                        );
-                    }
-                }
+                let claimed_by_prev = target.and_then(|bot| bot.claimed_by);
 
                 let owners = crate::impls::utils::get_entity_managers(
                     TargetType::Bot,
@@ -416,7 +589,7 @@ impl RPCMethod {
                     "claimed",
                     json!({
                         "target_id": target_id,
-                        "claimed_by_prev": claimed.claimed_by,
+                        "claimed_by_prev": claimed_by_prev,
                     })
                 )
                 .execute(&state.pool)
@@ -448,25 +621,7 @@ impl RPCMethod {
                 Ok(RPCSuccess::NoContent)
             }
             RPCMethod::Unclaim { target_id, reason } => {
-                if reason.len() > 2000 {
-                    return Err("Reason must be lower than/equal to 2000 characters".into());
-                }
-
-                // Check if its claimed by someone
-                let claimed = sqlx::query!(
-                    "SELECT type, claimed_by, owner FROM bots WHERE bot_id = $1",
-                    target_id
-                )
-                .fetch_one(&state.pool)
-                .await?;
-
-                if claimed.r#type == "testbot" {
-                    return Err("This bot is a test bot".into());
-                }
-
-                if claimed.r#type != "pending" {
-                    return Err("This bot is not pending review".into());
-                }
+                let claimed_by_prev = target.and_then(|bot| bot.claimed_by);
 
                 let owners = crate::impls::utils::get_entity_managers(
                     TargetType::Bot,
@@ -475,10 +630,6 @@ impl RPCMethod {
                 )
                 .await?;
 
-                if claimed.claimed_by.is_none() {
-                    return Err(format!("<@{}> is not claimed", target_id).into());
-                }
-
                 sqlx::query!(
                     "UPDATE bots SET claimed_by = NULL, type = 'pending' WHERE bot_id = $1",
                     target_id
@@ -492,7 +643,7 @@ impl RPCMethod {
                     "unclaimed",
                     json!({
                         "target_id": target_id,
-                        "claimed_by_prev": claimed.claimed_by,
+                        "claimed_by_prev": claimed_by_prev,
                     })
                 )
                 .execute(&state.pool)
@@ -520,32 +671,6 @@ impl RPCMethod {
                 Ok(RPCSuccess::NoContent)
             }
             RPCMethod::Approve { target_id, reason } => {
-                if reason.len() > 2000 {
-                    return Err("Reason must be lower than/equal to 2000 characters".into());
-                }
-
-                let claimed = sqlx::query!(
-                    "SELECT type, claimed_by, last_claimed FROM bots WHERE bot_id = $1",
-                    target_id
-                )
-                .fetch_one(&state.pool)
-                .await?;
-
-                if claimed.r#type != "pending" {
-                    return Err("Entity is not pending review?".into());
-                }
-
-                if claimed.claimed_by.is_none()
-                    || claimed.claimed_by.as_ref().unwrap().is_empty()
-                    || claimed.last_claimed.is_none()
-                {
-                    return Err(format!(
-                        "<@{}> is not claimed? Do ``/claim`` to claim this bot first!",
-                        target_id
-                    )
-                    .into());
-                }
-
                 let owners = crate::impls::utils::get_entity_managers(
                     TargetType::Bot,
                     target_id,
@@ -686,32 +811,6 @@ impl RPCMethod {
                 )
             }
             RPCMethod::Deny { target_id, reason } => {
-                if reason.len() > 2000 {
-                    return Err("Reason must be lower than/equal to 2000 characters".into());
-                }
-
-                let claimed = sqlx::query!(
-                    "SELECT type, claimed_by, owner, last_claimed FROM bots WHERE bot_id = $1",
-                    target_id
-                )
-                .fetch_one(&state.pool)
-                .await?;
-
-                if claimed.r#type != "pending" {
-                    return Err(" is not pending review?".into());
-                }
-
-                if claimed.claimed_by.is_none()
-                    || claimed.claimed_by.as_ref().unwrap().is_empty()
-                    || claimed.last_claimed.is_none()
-                {
-                    return Err(format!(
-                        "<@{}> is not claimed? Do ``/claim`` to claim this bot first!",
-                        target_id
-                    )
-                    .into());
-                }
-
                 let owners = crate::impls::utils::get_entity_managers(
                     TargetType::Bot,
                     target_id,
@@ -752,28 +851,6 @@ impl RPCMethod {
                 Ok(RPCSuccess::NoContent)
             }
             RPCMethod::Unverify { target_id, reason } => {
-                if reason.len() > 2000 {
-                    return Err("Reason must be lower than/equal to 2000 characters".into());
-                }
-
-                // Ensure the bot actually exists
-                let bot = sqlx::query!("SELECT COUNT(*) FROM bots WHERE bot_id = $1", target_id)
-                    .fetch_one(&state.pool)
-                    .await?;
-
-                if bot.count.unwrap_or_default() == 0 {
-                    return Err(" does not exist".into());
-                }
-
-                let bot_type_rec =
-                    sqlx::query!("SELECT type FROM bots WHERE bot_id = $1", target_id)
-                        .fetch_one(&state.pool)
-                        .await?;
-
-                if bot_type_rec.r#type == "certified" {
-                    return Err("Certified bots cannot be unverified".into());
-                }
-
                 sqlx::query!(
                     "UPDATE bots SET type = 'pending', claimed_by = NULL WHERE bot_id = $1",
                     target_id
@@ -803,19 +880,6 @@ impl RPCMethod {
                 reason,
                 time_period_hours,
             } => {
-                if reason.len() > 2000 {
-                    return Err("Reason must be lower than/equal to 2000 characters".into());
-                }
-
-                // Ensure the bot actually exists
-                let bot = sqlx::query!("SELECT COUNT(*) FROM bots WHERE bot_id = $1", target_id)
-                    .fetch_one(&state.pool)
-                    .await?;
-
-                if bot.count.unwrap_or_default() == 0 {
-                    return Err(" does not exist".into());
-                }
-
                 // Set premium_period_length which is a postgres interval
                 sqlx::query!(
                     "UPDATE bots SET start_premium_period = NOW(), premium_period_length = make_interval(hours => $1), premium = true WHERE bot_id = $2",
@@ -848,19 +912,6 @@ impl RPCMethod {
                 Ok(RPCSuccess::NoContent)
             }
             RPCMethod::PremiumRemove { target_id, reason } => {
-                if reason.len() > 2000 {
-                    return Err("Reason must be lower than/equal to 2000 characters".into());
-                }
-
-                // Ensure the bot actually exists
-                let bot = sqlx::query!("SELECT COUNT(*) FROM bots WHERE bot_id = $1", target_id)
-                    .fetch_one(&state.pool)
-                    .await?;
-
-                if bot.count.unwrap_or_default() == 0 {
-                    return Err(" does not exist".into());
-                }
-
                 // Set premium_period_length which is a postgres interval
                 sqlx::query!(
                     "UPDATE bots SET premium = false WHERE bot_id = $1",
@@ -892,19 +943,6 @@ impl RPCMethod {
                 Ok(RPCSuccess::NoContent)
             }
             RPCMethod::VoteBanAdd { target_id, reason } => {
-                if reason.len() > 2000 {
-                    return Err("Reason must be lower than/equal to 2000 characters".into());
-                }
-
-                // Ensure the bot actually exists
-                let bot = sqlx::query!("SELECT COUNT(*) FROM bots WHERE bot_id = $1", target_id)
-                    .fetch_one(&state.pool)
-                    .await?;
-
-                if bot.count.unwrap_or_default() == 0 {
-                    return Err(" does not exist".into());
-                }
-
                 sqlx::query!(
                     "UPDATE bots SET vote_banned = true WHERE bot_id = $1",
                     target_id
@@ -935,19 +973,6 @@ impl RPCMethod {
                 Ok(RPCSuccess::NoContent)
             }
             RPCMethod::VoteBanRemove { target_id, reason } => {
-                if reason.len() > 2000 {
-                    return Err("Reason must be lower than/equal to 2000 characters".into());
-                }
-
-                // Ensure the bot actually exists
-                let bot = sqlx::query!("SELECT COUNT(*) FROM bots WHERE bot_id = $1", target_id)
-                    .fetch_one(&state.pool)
-                    .await?;
-
-                if bot.count.unwrap_or_default() == 0 {
-                    return Err(" does not exist".into());
-                }
-
                 sqlx::query!(
                     "UPDATE bots SET vote_banned = false WHERE bot_id = $1",
                     target_id
@@ -978,10 +1003,6 @@ impl RPCMethod {
                 Ok(RPCSuccess::NoContent)
             }
             RPCMethod::VoteReset { target_id, reason } => {
-                if reason.len() > 2000 {
-                    return Err("Reason must be lower than/equal to 2000 characters".into());
-                }
-
                 sqlx::query!("UPDATE entity_votes SET void = TRUE, void_reason = 'Votes (single entity) reset', voided_at = NOW() WHERE target_type = $1 AND target_id = $2 AND void = FALSE", state.target_type.to_string(), target_id)
                     .execute(&state.pool)
                     .await?;
@@ -1006,10 +1027,6 @@ impl RPCMethod {
                 Ok(RPCSuccess::NoContent)
             }
             RPCMethod::VoteResetAll { reason } => {
-                if reason.len() > 2000 {
-                    return Err("Reason must be lower than/equal to 2000 characters".into());
-                }
-
                 let mut tx = state.pool.begin().await?;
 
                 sqlx::query!("UPDATE entity_votes SET void = TRUE, void_reason = 'Votes (all entities) reset', voided_at = NOW() WHERE target_type = $1 AND immutable = false", state.target_type.to_string())
@@ -1041,29 +1058,8 @@ impl RPCMethod {
                 reason,
                 kick,
             } => {
-                if reason.len() > 2000 {
-                    return Err("Reason must be lower than/equal to 2000 characters".into());
-                }
-
-                // Ensure the bot actually exists
-                let bot = sqlx::query!("SELECT COUNT(*) FROM bots WHERE bot_id = $1", target_id)
-                    .fetch_one(&state.pool)
-                    .await?;
-
-                if bot.count.unwrap_or_default() == 0 {
-                    return Err(" does not exist".into());
-                }
-
                 let target_id_snow = target_id.parse::<UserId>()?;
 
-                if crate::config::CONFIG
-                    .protected_bots
-                    .contains(&target_id_snow)
-                    && *kick
-                {
-                    return Err("You can't force delete this bot with 'kick' enabled!".into());
-                }
-
                 sqlx::query!("DELETE FROM bots WHERE bot_id = $1", target_id)
                     .execute(&state.pool)
                     .await?;
@@ -1112,19 +1108,6 @@ impl RPCMethod {
                 Ok(RPCSuccess::NoContent)
             }
             RPCMethod::CertifyAdd { target_id, reason } => {
-                if reason.len() > 2000 {
-                    return Err("Reason must be lower than/equal to 2000 characters".into());
-                }
-
-                // Ensure the bot actually exists
-                let bot = sqlx::query!("SELECT COUNT(*) FROM bots WHERE bot_id = $1", target_id)
-                    .fetch_one(&state.pool)
-                    .await?;
-
-                if bot.count.unwrap_or_default() == 0 {
-                    return Err(" does not exist".into());
-                }
-
                 sqlx::query!(
                     "UPDATE bots SET type = 'certified' WHERE bot_id = $1",
                     target_id
@@ -1153,19 +1136,6 @@ impl RPCMethod {
                 Ok(RPCSuccess::NoContent)
             }
             RPCMethod::CertifyRemove { target_id, reason } => {
-                if reason.len() > 2000 {
-                    return Err("Reason must be lower than/equal to 2000 characters".into());
-                }
-
-                // Ensure the bot actually exists
-                let bot = sqlx::query!("SELECT COUNT(*) FROM bots WHERE bot_id = $1", target_id)
-                    .fetch_one(&state.pool)
-                    .await?;
-
-                if bot.count.unwrap_or_default() == 0 {
-                    return Err(" does not exist".into());
-                }
-
                 sqlx::query!(
                     "UPDATE bots SET type = 'approved' WHERE bot_id = $1",
                     target_id
@@ -1200,29 +1170,6 @@ impl RPCMethod {
                 new_owner,
                 reason,
             } => {
-                if reason.len() > 2000 {
-                    return Err("Reason must be lower than/equal to 2000 characters".into());
-                }
-
-                // Ensure the bot actually exists
-                let bot = sqlx::query!("SELECT COUNT(*) FROM bots WHERE bot_id = $1", target_id)
-                    .fetch_one(&state.pool)
-                    .await?;
-
-                if bot.count.unwrap_or_default() == 0 {
-                    return Err(" does not exist".into());
-                }
-
-                // Check that the bot is not in a team
-                let team_owner =
-                    sqlx::query!("SELECT team_owner FROM bots WHERE bot_id = $1", target_id)
-                        .fetch_one(&state.pool)
-                        .await?;
-
-                if team_owner.team_owner.is_some() {
-                    return Err(" is in a team. Please use BotTransferOwnershipTeam".into());
-                }
-
                 sqlx::query!(
                     "UPDATE bots SET owner = $2 WHERE bot_id = $1",
                     target_id,
@@ -1258,34 +1205,7 @@ impl RPCMethod {
                 new_team,
                 reason,
             } => {
-                if reason.len() > 2000 {
-                    return Err("Reason must be lower than/equal to 2000 characters".into());
-                }
-
-                // Ensure the bot actually exists
-                let bot = sqlx::query!("SELECT COUNT(*) FROM bots WHERE bot_id = $1", target_id)
-                    .fetch_one(&state.pool)
-                    .await?;
-
-                if bot.count.unwrap_or_default() == 0 {
-                    return Err(" does not exist".into());
-                }
-
-                // Parse the team ID
-                let team_id = match new_team.parse::<Uuid>() {
-                    Ok(id) => id,
-                    Err(_) => return Err("Invalid team ID".into()),
-                };
-
-                // Check that the bot is not in a team
-                let team_owner =
-                    sqlx::query!("SELECT team_owner FROM bots WHERE bot_id = $1", target_id)
-                        .fetch_one(&state.pool)
-                        .await?;
-
-                if team_owner.team_owner.is_none() {
-                    return Err(" is not in a team. Please use TransferOwnership".into());
-                }
+                let team_id = new_team.parse::<Uuid>()?;
 
                 sqlx::query!(
                     "UPDATE bots SET team_owner = $2 WHERE bot_id = $1",
@@ -1318,19 +1238,6 @@ impl RPCMethod {
                 Ok(RPCSuccess::NoContent)
             }
             RPCMethod::AppBanUser { target_id, reason } => {
-                if reason.len() > 2000 {
-                    return Err("Reason must be lower than/equal to 2000 characters".into());
-                }
-
-                // Ensure the user actually exists
-                let user = sqlx::query!("SELECT COUNT(*) FROM users WHERE user_id = $1", target_id)
-                    .fetch_one(&state.pool)
-                    .await?;
-
-                if user.count.unwrap_or_default() == 0 {
-                    return Err(" does not exist".into());
-                }
-
                 // Set app_banned to true
                 sqlx::query!(
                     "UPDATE users SET app_banned = true WHERE user_id = $1",
@@ -1362,19 +1269,6 @@ impl RPCMethod {
                 Ok(RPCSuccess::NoContent)
             }
             RPCMethod::AppUnbanUser { target_id, reason } => {
-                if reason.len() > 2000 {
-                    return Err("Reason must be lower than/equal to 2000 characters".into());
-                }
-
-                // Ensure the user actually exists
-                let user = sqlx::query!("SELECT COUNT(*) FROM users WHERE user_id = $1", target_id)
-                    .fetch_one(&state.pool)
-                    .await?;
-
-                if user.count.unwrap_or_default() == 0 {
-                    return Err(" does not exist".into());
-                }
-
                 // Set app_banned to false
                 sqlx::query!(
                     "UPDATE users SET app_banned = false WHERE user_id = $1",
@@ -1406,6 +1300,411 @@ impl RPCMethod {
         }
     }
 
+    /// Validates the method and describes what it would do without mutating anything
+    ///
+    /// Dry runs are not logged to rpc_logs and do not count towards the ratelimit
+    pub async fn dry_run(&self, state: RPCHandle) -> Result<RPCSuccess, Error> {
+        Ok(RPCSuccess::DryRun(self.summarize(&state).await?))
+    }
+
+    /// Runs all checks of the method that do not mutate anything and returns a
+    /// human-readable summary of its intended effect
+    pub async fn summarize(&self, state: &RPCHandle) -> Result<String, Error> {
+        self.check_can_run(state).await?;
+        self.describe_effect(state).await
+    }
+
+    /// Whether or not the method must be confirmed with a confirmation token before it runs
+    ///
+    /// This is used for destructive methods which are hard to undo
+    pub fn requires_confirmation(&self) -> bool {
+        matches!(
+            self,
+            RPCMethod::VoteReset { .. }
+                | RPCMethod::VoteResetAll { .. }
+                | RPCMethod::ForceRemove { .. }
+        )
+    }
+
+    /// Returns the reason of the method, if it has one
+    fn reason(&self) -> Option<&str> {
+        match self {
+            RPCMethod::Claim { .. } => None,
+            RPCMethod::Unclaim { reason, .. } => Some(reason),
+            RPCMethod::Approve { reason, .. } => Some(reason),
+            RPCMethod::Deny { reason, .. } => Some(reason),
+            RPCMethod::Unverify { reason, .. } => Some(reason),
+            RPCMethod::PremiumAdd { reason, .. } => Some(reason),
+            RPCMethod::PremiumRemove { reason, .. } => Some(reason),
+            RPCMethod::VoteBanAdd { reason, .. } => Some(reason),
+            RPCMethod::VoteBanRemove { reason, .. } => Some(reason),
+            RPCMethod::VoteReset { reason, .. } => Some(reason),
+            RPCMethod::VoteResetAll { reason } => Some(reason),
+            RPCMethod::ForceRemove { reason, .. } => Some(reason),
+            RPCMethod::CertifyAdd { reason, .. } => Some(reason),
+            RPCMethod::CertifyRemove { reason, .. } => Some(reason),
+            RPCMethod::BotTransferOwnershipUser { reason, .. } => Some(reason),
+            RPCMethod::BotTransferOwnershipTeam { reason, .. } => Some(reason),
+            RPCMethod::AppBanUser { reason, .. } => Some(reason),
+            RPCMethod::AppUnbanUser { reason, .. } => Some(reason),
+        }
+    }
+
+    /// Checks that the target of the method is in a state the method can act on
+    ///
+    /// Shared by `handle_method` and `describe_effect` so that dry runs and summaries reject
+    /// exactly what a real run would. Returns the target bot for methods that act on one
+    async fn check_preconditions(&self, state: &RPCHandle) -> Result<Option<TargetBot>, Error> {
+        async fn get_bot(pool: &PgPool, target_id: &str) -> Result<TargetBot, Error> {
+            let Some(bot) = sqlx::query!(
+                "SELECT type, claimed_by, last_claimed, team_owner FROM bots WHERE bot_id = $1",
+                target_id
+            )
+            .fetch_optional(pool)
+            .await?
+            else {
+                return Err(format!("<@{}> does not exist", target_id).into());
+            };
+
+            Ok(TargetBot {
+                r#type: bot.r#type,
+                claimed_by: bot.claimed_by.filter(|c| !c.is_empty()),
+                last_claimed: bot.last_claimed,
+                team_owner: bot.team_owner,
+            })
+        }
+
+        if let Some(reason) = self.reason() {
+            if reason.len() > 2000 {
+                return Err("Reason must be lower than/equal to 2000 characters".into());
+            }
+        }
+
+        match self {
+            RPCMethod::Claim { target_id, force } => {
+                let bot = get_bot(&state.pool, target_id).await?;
+
+                if bot.r#type == "testbot" {
+                    return Err("This bot is a test bot".into());
+                }
+
+                if bot.r#type != "pending" {
+                    return Err("This bot is not pending review".into());
+                }
+
+                if !force {
+                    if let Some(claimed_by) = &bot.claimed_by {
+                        return Err(
+                            format!("This bot is already claimed by <@{}>", claimed_by).into()
+                        );
+                    }
+                }
+
+                Ok(Some(bot))
+            }
+            RPCMethod::Unclaim { target_id, .. } => {
+                let bot = get_bot(&state.pool, target_id).await?;
+
+                if bot.r#type == "testbot" {
+                    return Err("This bot is a test bot".into());
+                }
+
+                if bot.r#type != "pending" {
+                    return Err("This bot is not pending review".into());
+                }
+
+                if bot.claimed_by.is_none() {
+                    return Err(format!("<@{}> is not claimed", target_id).into());
+                }
+
+                Ok(Some(bot))
+            }
+            RPCMethod::Approve { target_id, .. } | RPCMethod::Deny { target_id, .. } => {
+                let bot = get_bot(&state.pool, target_id).await?;
+
+                if bot.r#type != "pending" {
+                    return Err(format!("<@{}> is not pending review", target_id).into());
+                }
+
+                if bot.claimed_by.is_none() || bot.last_claimed.is_none() {
+                    return Err(format!(
+                        "<@{}> is not claimed? Do ``/claim`` to claim this bot first!",
+                        target_id
+                    )
+                    .into());
+                }
+
+                Ok(Some(bot))
+            }
+            RPCMethod::Unverify { target_id, .. } => {
+                let bot = get_bot(&state.pool, target_id).await?;
+
+                if bot.r#type == "certified" {
+                    return Err("Certified bots cannot be unverified".into());
+                }
+
+                Ok(Some(bot))
+            }
+            RPCMethod::PremiumAdd { target_id, .. }
+            | RPCMethod::PremiumRemove { target_id, .. }
+            | RPCMethod::VoteBanAdd { target_id, .. }
+            | RPCMethod::VoteBanRemove { target_id, .. }
+            | RPCMethod::CertifyAdd { target_id, .. }
+            | RPCMethod::CertifyRemove { target_id, .. } => {
+                Ok(Some(get_bot(&state.pool, target_id).await?))
+            }
+            RPCMethod::ForceRemove {
+                target_id, kick, ..
+            } => {
+                let bot = get_bot(&state.pool, target_id).await?;
+
+                if *kick
+                    && crate::config::CONFIG
+                        .protected_bots
+                        .contains(&target_id.parse::<UserId>()?)
+                {
+                    return Err("You can't force delete this bot with 'kick' enabled!".into());
+                }
+
+                Ok(Some(bot))
+            }
+            RPCMethod::BotTransferOwnershipUser { target_id, .. } => {
+                let bot = get_bot(&state.pool, target_id).await?;
+
+                if bot.team_owner.is_some() {
+                    return Err(format!(
+                        "<@{}> is in a team. Please use BotTransferOwnershipTeam",
+                        target_id
+                    )
+                    .into());
+                }
+
+                Ok(Some(bot))
+            }
+            RPCMethod::BotTransferOwnershipTeam {
+                target_id,
+                new_team,
+                ..
+            } => {
+                if new_team.parse::<Uuid>().is_err() {
+                    return Err("Invalid team ID".into());
+                }
+
+                let bot = get_bot(&state.pool, target_id).await?;
+
+                if bot.team_owner.is_none() {
+                    return Err(format!(
+                        "<@{}> is not in a team. Please use BotTransferOwnershipUser",
+                        target_id
+                    )
+                    .into());
+                }
+
+                Ok(Some(bot))
+            }
+            RPCMethod::VoteReset { .. } | RPCMethod::VoteResetAll { .. } => Ok(None),
+            RPCMethod::AppBanUser { target_id, .. } | RPCMethod::AppUnbanUser { target_id, .. } => {
+                let user = sqlx::query!("SELECT COUNT(*) FROM users WHERE user_id = $1", target_id)
+                    .fetch_one(&state.pool)
+                    .await?;
+
+                if user.count.unwrap_or_default() == 0 {
+                    return Err(format!("<@{}> does not exist", target_id).into());
+                }
+
+                Ok(None)
+            }
+        }
+    }
+
+    /// Performs the read-only validation of a method and returns a human-readable
+    /// description of its intended effect
+    async fn describe_effect(&self, state: &RPCHandle) -> Result<String, Error> {
+        // Only methods acting on a bot use these, and they always have a target bot
+        let (bot_type, claimed_by) = self
+            .check_preconditions(state)
+            .await?
+            .map(|bot| (bot.r#type, bot.claimed_by))
+            .unwrap_or_default();
+
+        match self {
+            RPCMethod::Claim { target_id, .. } => match claimed_by {
+                Some(claimed_by) => Ok(format!(
+                    "Would force claim <@{}>, taking it from <@{}>",
+                    target_id, claimed_by
+                )),
+                None => Ok(format!("Would claim <@{}>", target_id)),
+            },
+            RPCMethod::Unclaim { target_id, .. } => Ok(format!(
+                "Would unclaim <@{}> (currently claimed by <@{}>)",
+                target_id,
+                claimed_by.unwrap_or_default()
+            )),
+            RPCMethod::Approve { target_id, .. } => Ok(format!(
+                "Would approve <@{}>, add it to a cache server and kick it from the testing server",
+                target_id
+            )),
+            RPCMethod::Deny { target_id, .. } => Ok(format!("Would deny <@{}>", target_id)),
+            RPCMethod::Unverify { target_id, .. } => Ok(format!(
+                "Would move <@{}> from {} back to pending review",
+                target_id, bot_type
+            )),
+            RPCMethod::PremiumAdd {
+                target_id,
+                time_period_hours,
+                ..
+            } => Ok(format!(
+                "Would add premium to <@{}> for {} hours",
+                target_id, time_period_hours
+            )),
+            RPCMethod::PremiumRemove { target_id, .. } => {
+                Ok(format!("Would remove premium from <@{}>", target_id))
+            }
+            RPCMethod::VoteBanAdd { target_id, .. } => {
+                Ok(format!("Would vote-ban <@{}>", target_id))
+            }
+            RPCMethod::VoteBanRemove { target_id, .. } => {
+                Ok(format!("Would remove the vote-ban on <@{}>", target_id))
+            }
+            RPCMethod::VoteReset { target_id, .. } => {
+                let votes = sqlx::query!(
+                    "SELECT COUNT(*) FROM entity_votes WHERE target_type = $1 AND target_id = $2 AND void = FALSE",
+                    state.target_type.to_string(),
+                    target_id
+                )
+                .fetch_one(&state.pool)
+                .await?
+                .count
+                .unwrap_or_default();
+
+                Ok(format!(
+                    "Would void {} vote(s) of {} {}",
+                    votes, state.target_type, target_id
+                ))
+            }
+            RPCMethod::VoteResetAll { .. } => {
+                let votes = sqlx::query!(
+                    "SELECT COUNT(*) FROM entity_votes WHERE target_type = $1 AND immutable = false AND void = FALSE",
+                    state.target_type.to_string()
+                )
+                .fetch_one(&state.pool)
+                .await?
+                .count
+                .unwrap_or_default();
+
+                Ok(format!(
+                    "Would void {} vote(s) across all entities of type {}",
+                    votes, state.target_type
+                ))
+            }
+            RPCMethod::ForceRemove {
+                target_id, kick, ..
+            } => {
+                if *kick
+                    && member_on_guild(
+                        &state.cache_http,
+                        crate::config::CONFIG.servers.main,
+                        target_id.parse::<UserId>()?,
+                    )
+                {
+                    Ok(format!(
+                        "Would delete <@{}> from the list and kick it from the main server",
+                        target_id
+                    ))
+                } else {
+                    Ok(format!("Would delete <@{}> from the list", target_id))
+                }
+            }
+            RPCMethod::CertifyAdd { target_id, .. } => Ok(format!(
+                "Would move <@{}> from {} to certified",
+                target_id, bot_type
+            )),
+            RPCMethod::CertifyRemove { target_id, .. } => Ok(format!(
+                "Would move <@{}> from {} to approved",
+                target_id, bot_type
+            )),
+            RPCMethod::BotTransferOwnershipUser {
+                target_id,
+                new_owner,
+                ..
+            } => Ok(format!(
+                "Would transfer ownership of <@{}> to <@{}>",
+                target_id, new_owner
+            )),
+            RPCMethod::BotTransferOwnershipTeam {
+                target_id,
+                new_team,
+                ..
+            } => Ok(format!(
+                "Would transfer ownership of <@{}> to team {}",
+                target_id, new_team
+            )),
+            RPCMethod::AppBanUser { target_id, .. } => {
+                Ok(format!("Would ban <@{}> from using apps", target_id))
+            }
+            RPCMethod::AppUnbanUser { target_id, .. } => {
+                Ok(format!("Would unban <@{}> from using apps", target_id))
+            }
+        }
+    }
+
+    /// Checks the values of the method against the constraints of its fields
+    pub fn validate_fields(&self) -> Result<(), Error> {
+        let value = serde_json::to_value(self)?;
+
+        let Some(data) = value.as_object().and_then(|o| o.values().next()) else {
+            return Err("Invalid RPC method data".into());
+        };
+
+        for field in self.method_fields() {
+            let Some(value) = data.get(&field.id).filter(|v| !v.is_null()) else {
+                if field.required {
+                    return Err(format!("{} is required", field.label).into());
+                }
+
+                continue;
+            };
+
+            let Some(value) = value.as_str() else {
+                continue;
+            };
+
+            if field.required && value.trim().is_empty() {
+                return Err(format!("{} is required", field.label).into());
+            }
+
+            let len = value.chars().count();
+
+            if let Some(min_length) = field.min_length {
+                if len < min_length {
+                    return Err(format!(
+                        "{} must be at least {} characters",
+                        field.label, min_length
+                    )
+                    .into());
+                }
+            }
+
+            if let Some(max_length) = field.max_length {
+                if len > max_length {
+                    return Err(format!(
+                        "{} must be lower than/equal to {} characters",
+                        field.label, max_length
+                    )
+                    .into());
+                }
+            }
+
+            if let Some(pattern) = &field.pattern {
+                if !regex::Regex::new(pattern)?.is_match(value) {
+                    return Err(format!("{} is not in the expected format", field.label).into());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     // Returns a set of RPCField's for a given enum variant
     pub fn method_fields(&self) -> Vec<RPCField> {
         match self {
@@ -1417,6 +1716,10 @@ impl RPCMethod {
                     field_type: FieldType::Boolean,
                     icon: "fa-solid:sign-out-alt".to_string(),
                     placeholder: "Yes/No".to_string(),
+                    required: false,
+                    min_length: None,
+                    max_length: None,
+                    pattern: None,
                 },
             ],
             RPCMethod::Unclaim { .. } => vec![RPCField::target_id(), RPCField::reason()],
@@ -1431,6 +1734,10 @@ impl RPCMethod {
                     field_type: FieldType::Hour,
                     icon: "material-symbols:timer".to_string(),
                     placeholder: "Time period. Format: X years/days/hours".to_string(),
+                    required: true,
+                    min_length: None,
+                    max_length: None,
+                    pattern: None,
                 },
                 RPCField::reason(),
             ],
@@ -1447,6 +1754,10 @@ impl RPCMethod {
                     field_type: FieldType::Boolean,
                     icon: "fa-solid:sign-out-alt".to_string(),
                     placeholder: "Kick the bot from the server".to_string(),
+                    required: false,
+                    min_length: None,
+                    max_length: None,
+                    pattern: None,
                 },
                 RPCField::reason(),
             ],
@@ -1460,6 +1771,10 @@ impl RPCMethod {
                     field_type: FieldType::Text,
                     icon: "material-symbols:timer".to_string(),
                     placeholder: "New Owner".to_string(),
+                    required: true,
+                    min_length: None,
+                    max_length: None,
+                    pattern: Some(r"^[0-9]{17,20}$".to_string()),
                 },
                 RPCField::reason(),
             ],
@@ -1471,6 +1786,13 @@ impl RPCMethod {
                     field_type: FieldType::Text,
                     icon: "material-symbols:timer".to_string(),
                     placeholder: "New Team".to_string(),
+                    required: true,
+                    min_length: None,
+                    max_length: None,
+                    pattern: Some(
+                        r"^[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}$"
+                            .to_string(),
+                    ),
                 },
                 RPCField::reason(),
             ],
@@ -4,14 +4,15 @@ use std::time::Duration;
 
 use poise::serenity_prelude::{
     ButtonStyle, CreateActionRow, CreateButton, CreateInputText, CreateInteractionResponse,
-    CreateInteractionResponseMessage, CreateQuickModal, InputTextStyle, ModalInteraction,
+    CreateInteractionResponseMessage, CreateQuickModal, EditInteractionResponse, InputTextStyle,
+    ModalInteraction,
 };
 use poise::CreateReply;
 use serenity::builder::CreateEmbed;
 use strum::VariantNames;
 
 use crate::impls::target_types::TargetType;
-use crate::rpc::core::{FieldType, RPCMethod};
+use crate::rpc::core::{FieldType, RPCMethod, RPCSuccess, RPC_CONFIRMATION_TTL};
 use crate::{Context, Error};
 
 async fn autocomplete<'a>(
@@ -256,64 +257,128 @@ pub async fn rpc(
     };
 
     let data = ctx.data();
+    let target_type: TargetType = target_type.into();
+
+    let rpc_handle = |confirmation_token| crate::rpc::core::RPCHandle {
+        cache_http: botox::cache::CacheHttpImpl::from_ctx(ctx.serenity_context()),
+        pool: data.pool.clone(),
+        user_id: ctx.author().id.to_string(),
+        target_type: target_type.clone(),
+        confirmation_token,
+    };
 
-    match rpc_method
-        .method
-        .handle(crate::rpc::core::RPCHandle {
-            cache_http: botox::cache::CacheHttpImpl::from_ctx(ctx.serenity_context()),
-            pool: data.pool.clone(),
-            user_id: ctx.author().id.to_string(),
-            target_type: target_type.into(),
-        })
-        .await
-    {
-        Ok(resp) => match resp {
-            crate::rpc::core::RPCSuccess::NoContent => {
-                rpc_method
-                    .interaction
-                    .create_response(
-                        &ctx.serenity_context().http,
-                        CreateInteractionResponse::Message(
-                            CreateInteractionResponseMessage::default().content(format!(
-                                "Successfully performed the operation required: `{}`",
-                                rpc_method.method
-                            )),
-                        ),
-                    )
-                    .await?;
-
-                Ok(())
-            }
-            crate::rpc::core::RPCSuccess::Content(msg) => {
-                rpc_method
-                    .interaction
-                    .create_response(
-                        &ctx.serenity_context().http,
-                        CreateInteractionResponse::Message(
-                            CreateInteractionResponseMessage::default().content(format!(
-                                "Successfully performed the operation required: `{}`\n**{}**",
-                                rpc_method.method, msg
-                            )),
-                        ),
-                    )
-                    .await?;
-                Ok(())
-            }
-        },
-        Err(e) => {
+    let resp = rpc_method.method.handle(rpc_handle(None)).await;
+
+    let (confirmation_token, summary) = match resp {
+        Ok(RPCSuccess::ConfirmationRequired {
+            confirmation_token,
+            summary,
+        }) => (confirmation_token, summary),
+        resp => {
             rpc_method
                 .interaction
                 .create_response(
                     &ctx.serenity_context().http,
                     CreateInteractionResponse::Message(
-                        CreateInteractionResponseMessage::default().content(format!(
-                            "Error performing `{}`: **{}**",
-                            rpc_method.method, e
-                        )),
+                        CreateInteractionResponseMessage::default()
+                            .content(describe_result(&rpc_method.method, resp)),
                     ),
                 )
                 .await?;
-            Ok(())
+
+            return Ok(());
         }
+    };
+
+    // Destructive methods need to be confirmed before they run
+    rpc_method
+        .interaction
+        .create_response(
+            &ctx.serenity_context().http,
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::default()
+                    .content(format!(
+                        "`{}` needs to be confirmed before it runs: **{}**",
+                        rpc_method.method, summary
+                    ))
+                    .components(vec![CreateActionRow::Buttons(vec![
+                        CreateButton::new("rpc:confirm")
+                            .label("Confirm")
+                            .style(ButtonStyle::Danger),
+                        CreateButton::new("rpc:cancel")
+                            .label("Cancel")
+                            .style(ButtonStyle::Secondary),
+                    ])]),
+            ),
+        )
+        .await?;
+
+    let msg = rpc_method
+        .interaction
+        .get_response(&ctx.serenity_context().http)
+        .await?;
+
+    let Some(interaction) = msg
+        .await_component_interaction(ctx.serenity_context().shard.clone())
+        .author_id(ctx.author().id)
+        .timeout(RPC_CONFIRMATION_TTL)
+        .await
+    else {
+        rpc_method
+            .interaction
+            .edit_response(
+                &ctx.serenity_context().http,
+                EditInteractionResponse::new()
+                    .content(format!("Confirmation of `{}` timed out", rpc_method.method))
+                    .components(vec![]),
+            )
+            .await?;
+
+        return Ok(());
+    };
+
+    let content = if interaction.data.custom_id == "rpc:confirm" {
+        let resp = rpc_method
+            .method
+            .handle(rpc_handle(Some(confirmation_token)))
+            .await;
+
+        describe_result(&rpc_method.method, resp)
+    } else {
+        format!("Cancelled `{}`", rpc_method.method)
+    };
+
+    interaction
+        .create_response(
+            &ctx.serenity_context().http,
+            CreateInteractionResponse::UpdateMessage(
+                CreateInteractionResponseMessage::default()
+                    .content(content)
+                    .components(vec![]),
+            ),
+        )
+        .await?;
+
+    Ok(())
+}
+
+/// Returns the message shown to the user for the result of an RPC method
+fn describe_result(method: &RPCMethod, resp: Result<RPCSuccess, Error>) -> String {
+    match resp {
+        Ok(RPCSuccess::NoContent) => format!(
+            "Successfully performed the operation required: `{}`",
+            method
+        ),
+        Ok(RPCSuccess::Content(msg)) => format!(
+            "Successfully performed the operation required: `{}`\n**{}**",
+            method, msg
+        ),
+        Ok(RPCSuccess::DryRun(msg)) => format!("Dry run of `{}`: **{}**", method, msg),
+        // Only returned by the first call of a destructive method
+        Ok(RPCSuccess::ConfirmationRequired { summary, .. }) => format!(
+            "`{}` needs to be confirmed before it runs: **{}**",
+            method, summary
+        ),
+        Err(e) => format!("Error performing `{}`: **{}**", method, e),
     }
 }
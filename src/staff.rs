@@ -1,8 +1,12 @@
 use crate::{checks, impls::utils::get_user_perms};
 use kittycat::perms;
+use poise::serenity_prelude::CreateAttachment;
 use poise::serenity_prelude::CreateEmbed;
+use poise::serenity_prelude::CreateMessage;
 use poise::serenity_prelude::GuildId;
+use poise::serenity_prelude::Mentionable;
 use poise::serenity_prelude::User;
+use poise::serenity_prelude::UserId;
 use poise::CreateReply;
 
 type Error = crate::Error;
@@ -14,14 +18,25 @@ type Context<'a> = crate::Context<'a>;
     prefix_command,
     slash_command,
     guild_cooldown = 10,
-    subcommands("staff_list", "staff_guildlist", "staff_guildleave", "staff_stats")
+    subcommands(
+        "staff_list",
+        "staff_guildlist",
+        "staff_guildleave",
+        "staff_stats",
+        "staff_inactive",
+        "staff_export"
+    )
 )]
 pub async fn staff(ctx: Context<'_>) -> Result<(), Error> {
-    ctx.say("Some available options are ``staff list``, ``staff guildlist``, ``staff_guildleave``, ``staff_stats``")
+    ctx.say("Some available options are ``staff list``, ``staff guildlist``, ``staff_guildleave``, ``staff_stats``, ``staff inactive``, ``staff export``")
         .await?;
     Ok(())
 }
 
+/// Maximum number of staff members shown on a single page of the staff list
+const STAFF_LIST_PAGE_SIZE: usize = 20;
+
+/// List all staff members
 #[poise::command(
     rename = "list",
     track_edits,
@@ -29,138 +44,166 @@ pub async fn staff(ctx: Context<'_>) -> Result<(), Error> {
     slash_command,
     check = "checks::staff_server"
 )]
-pub async fn staff_list(_: Context<'_>) -> Result<(), Error> {
-    Err("This command is currently disabled".into())
-
-    /* TODO: FINISH REWRITING
-    // Get list of users with staff flag set to true
+pub async fn staff_list(ctx: Context<'_>) -> Result<(), Error> {
     let data = ctx.data();
 
-    let server_id = match ctx.guild_id() {
-        Some(server_id) => server_id,
-        None => return Err("This command can only be used in a server".into()),
-    };
+    let positions = sqlx::query!("SELECT id, name FROM staff_positions ORDER BY index ASC")
+        .fetch_all(&data.pool)
+        .await?;
 
-    let positions = sqlx::query!(
-        "SELECT id, name FROM staff_positions ORDER BY index ASC"
-    )
-    .fetch_all(&data.pool)
-    .await?;
+    let members =
+        sqlx::query!("SELECT user_id, positions FROM staff_members ORDER BY created_at ASC")
+            .fetch_all(&data.pool)
+            .await?;
 
-    let mut select_menus = Vec::<CreateSelectMenuOption>::new();
+    if members.is_empty() {
+        ctx.say("There are no staff members").await?;
+        return Ok(());
+    }
 
-    for position in positions {
-        select_menus.push(
-            CreateSelectMenuOption::new(format!("{} ({})", position.name, position.id), position.id)
-                .description("View staff member's with this position"),
+    let mut in_server = Vec::new();
+    let mut not_cached = Vec::new();
+
+    for member in members {
+        let position_names = positions
+            .iter()
+            .filter(|p| member.positions.contains(&p.id))
+            .map(|p| p.name.as_str())
+            .collect::<Vec<&str>>();
+
+        let line = format!(
+            "<@{}> ({}) - {}",
+            member.user_id,
+            member.user_id,
+            if position_names.is_empty() {
+                "No positions".to_string()
+            } else {
+                position_names.join(", ")
+            }
         );
+
+        // Only the cache is checked here, fetching every member over HTTP is too slow, so members
+        // missing from the cache may still be in the staff server
+        let on_staff_server = match member.user_id.parse::<UserId>() {
+            Ok(user_id) => ctx
+                .serenity_context()
+                .cache
+                .member(crate::config::CONFIG.servers.staff, user_id)
+                .is_some(),
+            Err(_) => false,
+        };
+
+        if on_staff_server {
+            in_server.push(line);
+        } else {
+            not_cached.push(line);
+        }
     }
 
-    // Create select menu
-    let msg = ctx
-        .send(
-            CreateReply::new()
-                .content("**Please select a position to view a list of staff members**")
-                .components(vec![
-                    CreateActionRow::SelectMenu(CreateSelectMenu::new(
-                        "Choose a position",
-                        CreateSelectMenuKind::String {
-                            options: select_menus.clone(),
-                        },
-                    )),
-                    CreateActionRow::Buttons(vec![CreateButton::new("sl:cancel").label("Cancel")]),
-                ]),
-        )
-        .await?
-        .into_message()
-        .await?;
+    let mut pages = Vec::new();
 
-    // Wait for user to select a staff member
-    let interaction = msg
-        .await_component_interactions(ctx.serenity_context())
-        .author_id(ctx.author().id)
-        .timeout(Duration::from_secs(120));
+    for (i, chunk) in in_server.chunks(STAFF_LIST_PAGE_SIZE).enumerate() {
+        pages.push(format!(
+            "**Staff Members ({})**\n{}",
+            i + 1,
+            chunk.join("\n")
+        ));
+    }
 
-    let mut collect_stream = interaction.stream();
+    for (i, chunk) in not_cached.chunks(STAFF_LIST_PAGE_SIZE).enumerate() {
+        pages.push(format!(
+            "**Not cached / unknown ({})**\n{}",
+            i + 1,
+            chunk.join("\n")
+        ));
+    }
 
-    while let Some(item) = collect_stream.next().await {
-        item.defer(&ctx.serenity_context()).await?;
+    let pages = pages.iter().map(|p| p.as_str()).collect::<Vec<&str>>();
 
-        let id = &item.data.custom_id;
+    poise::builtins::paginate(ctx, &pages).await?;
 
-        if id == "sl:cancel" {
-            log::info!("Received cancel interaction");
-            item.delete_response(ctx.serenity_context()).await?;
-            return Ok(());
-        }
+    Ok(())
+}
 
-        // Get select menu value
-        let values = match &item.data.kind {
-            ComponentInteractionDataKind::StringSelect { values } => values,
-            _ => {
-                log::info!("Received interaction of wrong type: {:?}", item.data.kind);
-                continue;
-            }
-        };
+/// Escapes a field for use in a CSV file
+///
+/// Fields that spreadsheets would run as a formula are prefixed with a `'` so they are shown as text
+fn csv_field(field: &str) -> String {
+    let field = if field.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{}", field)
+    } else {
+        field.to_string()
+    };
 
-        let id = match values.get(0) {
-            Some(id) => id,
-            None => {
-                log::info!("Failed to get select menu value");
-                continue;
-            }
-        };
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field
+    }
+}
+
+/// Export all staff members as a CSV file
+#[poise::command(
+    rename = "export",
+    prefix_command,
+    slash_command,
+    check = "checks::staff_server"
+)]
+pub async fn staff_export(ctx: Context<'_>) -> Result<(), Error> {
+    let data = ctx.data();
 
-        log::info!("Received interaction: {}", id);
+    let positions = sqlx::query!("SELECT id, name FROM staff_positions ORDER BY index ASC")
+        .fetch_all(&data.pool)
+        .await?;
 
-        let user_id = match id.parse::<UserId>() {
-            Ok(id) => id,
-            Err(_) => {
-                log::info!("Failed to parse user_id: {}", id);
-                continue;
-            }
+    let members =
+        sqlx::query!("SELECT user_id, positions FROM staff_members ORDER BY created_at ASC")
+            .fetch_all(&data.pool)
+            .await?;
+
+    let mut csv = String::from("user_id,username,positions,in_staff_server\n");
+
+    for member in members {
+        let position_names = positions
+            .iter()
+            .filter(|p| member.positions.contains(&p.id))
+            .map(|p| p.name.as_str())
+            .collect::<Vec<&str>>();
+
+        // Like staff list, only the cache is used here. A cache miss does not mean the member left the staff server,
+        // so membership is only reported as `true` or `unknown`
+        let username = match member.user_id.parse::<UserId>() {
+            Ok(user_id) => ctx
+                .serenity_context()
+                .cache
+                .member(crate::config::CONFIG.servers.staff, user_id)
+                .map(|m| m.user.name.to_string()),
+            Err(_) => None,
         };
 
-        let member = {
-            let cache_user = ctx.serenity_context().cache.member(server_id, user_id);
-
-            match cache_user {
-                Some(user) => user.clone(),
-                None => {
-                    log::error!("Failed to get user from cache: {}", user_id);
-                    continue;
-                }
-            }
+        let in_staff_server = if username.is_some() {
+            "true"
+        } else {
+            "unknown"
         };
 
-        let msg = EditInteractionResponse::new()
-            .content("")
-            .embed(
-                CreateEmbed::default()
-                    .title(format!(
-                        "{}'s [{}] information",
-                        member.user.name,
-                        member.display_name()
-                    ))
-                    .description("This is the information we have on this staff member")
-                    .field("User ID", staff.user_id, true)
-                    .field("Permissions", perms, true),
-            )
-            .components(vec![
-                CreateActionRow::SelectMenu(CreateSelectMenu::new(
-                    "Choose a staff member",
-                    CreateSelectMenuKind::String {
-                        options: select_menus.clone(),
-                    },
-                )),
-                CreateActionRow::Buttons(vec![CreateButton::new("sl:cancel").label("Cancel")]),
-            ]);
-
-        item.edit_response(ctx.serenity_context(), msg).await?;
+        csv.push_str(&format!(
+            "{},{},{},{}\n",
+            csv_field(&member.user_id),
+            csv_field(username.as_deref().unwrap_or_default()),
+            csv_field(&position_names.join(";")),
+            in_staff_server
+        ));
     }
 
+    ctx.send(
+        CreateReply::default()
+            .content("Staff members exported")
+            .attachment(CreateAttachment::bytes(csv.into_bytes(), "staff.csv")),
+    )
+    .await?;
+
     Ok(())
-    */
 }
 
 /// Get guild list, this is intentionally public
@@ -259,3 +302,389 @@ pub async fn staff_stats(
     ctx.send(msg).await?;
     Ok(())
 }
+
+/// Maximum number of days that can be looked back on by the inactivity report
+const MAX_INACTIVE_DAYS: i32 = 365;
+
+/// List staff members who have not performed any RPC actions recently
+#[poise::command(
+    rename = "inactive",
+    prefix_command,
+    slash_command,
+    check = "checks::staff_server"
+)]
+pub async fn staff_inactive(
+    ctx: Context<'_>,
+    #[description = "Number of days without any actions"] days: i32,
+) -> Result<(), Error> {
+    let data = ctx.data();
+
+    let user_perms = get_user_perms(&data.pool, &ctx.author().id.to_string())
+        .await?
+        .resolve();
+
+    if !perms::has_perm(&user_perms, &"arcadia.view_inactive_staff".into()) {
+        return Err(
+            "You do not have permission to view inactive staff [arcadia.view_inactive_staff]"
+                .into(),
+        );
+    }
+
+    if !(1..=MAX_INACTIVE_DAYS).contains(&days) {
+        return Err(format!("Days must be between 1 and {}", MAX_INACTIVE_DAYS).into());
+    }
+
+    let inactive = sqlx::query!(
+        "SELECT staff_members.user_id, MAX(rpc_logs.created_at) AS last_action FROM staff_members
+        LEFT JOIN rpc_logs ON rpc_logs.user_id = staff_members.user_id
+        GROUP BY staff_members.user_id
+        HAVING MAX(rpc_logs.created_at) IS NULL OR MAX(rpc_logs.created_at) < NOW() - make_interval(days => $1)
+        ORDER BY last_action ASC NULLS FIRST",
+        days
+    )
+    .fetch_all(&data.pool)
+    .await?;
+
+    if inactive.is_empty() {
+        ctx.say(format!(
+            "All staff members have performed an action in the last {} days",
+            days
+        ))
+        .await?;
+        return Ok(());
+    }
+
+    let lines = inactive
+        .iter()
+        .map(|member| {
+            format!(
+                "<@{}> ({}) - {}",
+                member.user_id,
+                member.user_id,
+                match member.last_action {
+                    Some(last_action) => format!("last action <t:{}:R>", last_action.timestamp()),
+                    None => "no actions".to_string(),
+                }
+            )
+        })
+        .collect::<Vec<String>>();
+
+    let total_pages = lines.len().div_ceil(STAFF_LIST_PAGE_SIZE);
+
+    let pages = lines
+        .chunks(STAFF_LIST_PAGE_SIZE)
+        .enumerate()
+        .map(|(i, chunk)| {
+            format!(
+                "**Staff inactive for {} days ({}/{})**\n{}",
+                days,
+                i + 1,
+                total_pages,
+                chunk.join("\n")
+            )
+        })
+        .collect::<Vec<String>>();
+
+    let pages = pages.iter().map(|p| p.as_str()).collect::<Vec<&str>>();
+
+    poise::builtins::paginate(ctx, &pages).await?;
+
+    Ok(())
+}
+
+/// Check your own onboarding status
+#[poise::command(
+    category = "Staff",
+    prefix_command,
+    slash_command,
+    check = "checks::is_staff"
+)]
+pub async fn onboardstatus(ctx: Context<'_>) -> Result<(), Error> {
+    let data = ctx.data();
+
+    let onboarding = sqlx::query!(
+        "SELECT state, created_at FROM staff_onboardings WHERE user_id = $1 AND void = false ORDER BY created_at DESC LIMIT 1",
+        ctx.author().id.to_string()
+    )
+    .fetch_optional(&data.pool)
+    .await?;
+
+    let embed = CreateEmbed::default().title("Onboarding Status");
+
+    let embed = match onboarding {
+        None => embed
+            .description("You have not started onboarding yet")
+            .field(
+                "What's next?",
+                "Ask a manager to start your onboarding. You need to complete onboarding to use RPC and the testing commands",
+                false,
+            )
+            .color(0xFF0000),
+        Some(onboarding) => {
+            // Completed onboardings are valid for a month (see checks::needs_onboarding)
+            let expires_at = onboarding
+                .created_at
+                .checked_add_months(chrono::Months::new(1))
+                .ok_or("Failed to compute onboarding expiry")?;
+
+            if onboarding.state != "completed" {
+                embed
+                    .description(format!(
+                        "Your onboarding is in progress (state: `{}`)",
+                        onboarding.state
+                    ))
+                    .field(
+                        "Started",
+                        format!("<t:{}:R>", onboarding.created_at.timestamp()),
+                        true,
+                    )
+                    .field(
+                        "What's next?",
+                        "Finish the remaining onboarding steps and wait for a manager to review your onboarding",
+                        false,
+                    )
+                    .color(0xFFA500)
+            } else if expires_at > chrono::Utc::now() {
+                embed
+                    .description("Your onboarding is complete")
+                    .field(
+                        "Completed",
+                        format!("<t:{}:R>", onboarding.created_at.timestamp()),
+                        true,
+                    )
+                    .field(
+                        "Expires",
+                        format!("<t:{}:R>", expires_at.timestamp()),
+                        true,
+                    )
+                    .field(
+                        "What's next?",
+                        "Nothing! Remember that you will need to redo onboarding once it expires",
+                        false,
+                    )
+                    .color(0x00FF00)
+            } else {
+                embed
+                    .description("Your onboarding has expired")
+                    .field(
+                        "Expired",
+                        format!("<t:{}:R>", expires_at.timestamp()),
+                        true,
+                    )
+                    .field(
+                        "What's next?",
+                        "Ask a manager to start a new onboarding for you",
+                        false,
+                    )
+                    .color(0xFF0000)
+            }
+        }
+    };
+
+    ctx.send(CreateReply::default().embed(embed)).await?;
+
+    Ok(())
+}
+
+/// Maximum number of action log entries shown on a single page
+const ACTION_LOGS_PAGE_SIZE: usize = 5;
+
+/// View the RPC actions performed by or on a user
+#[poise::command(
+    category = "Staff",
+    prefix_command,
+    slash_command,
+    check = "checks::staff_server"
+)]
+pub async fn actionlogs(
+    ctx: Context<'_>,
+    #[description = "The user to view action logs of"] user: User,
+    #[description = "Only show this action (RPC method), e.g. Claim"] action: Option<String>,
+) -> Result<(), Error> {
+    let data = ctx.data();
+
+    let user_perms = get_user_perms(&data.pool, &ctx.author().id.to_string())
+        .await?
+        .resolve();
+
+    if !perms::has_perm(&user_perms, &"rpc_logs.view".into()) {
+        return Err("You do not have permission to view action logs [rpc_logs.view]".into());
+    }
+
+    let entries = sqlx::query!(
+        "SELECT user_id, method, data, state, error, created_at FROM rpc_logs
+        WHERE (user_id = $1 OR data -> method ->> 'target_id' = $1)
+        AND ($2::text IS NULL OR method = $2)
+        ORDER BY created_at DESC LIMIT 100",
+        user.id.to_string(),
+        action
+    )
+    .fetch_all(&data.pool)
+    .await?;
+
+    if entries.is_empty() {
+        ctx.say("No action logs found for this user").await?;
+        return Ok(());
+    }
+
+    let mut lines = Vec::new();
+
+    for entry in entries {
+        // Data is the serialized RPC method, keyed by method name
+        let fields = entry.data.get(&entry.method);
+
+        let target_id = fields
+            .and_then(|f| f.get("target_id"))
+            .and_then(|t| t.as_str())
+            .map(|t| format!("<@{}>", t))
+            .unwrap_or_else(|| "None".to_string());
+
+        let reason = fields
+            .and_then(|f| f.get("reason"))
+            .and_then(|r| r.as_str())
+            .unwrap_or("No reason given");
+
+        lines.push(format!(
+            "**{}** <t:{}:R>\n**Actor:** <@{}>\n**Target:** {}\n**Reason:** {}\n**State:** {}",
+            entry.method,
+            entry.created_at.timestamp(),
+            entry.user_id,
+            target_id,
+            reason.chars().take(200).collect::<String>(),
+            match entry.error {
+                Some(error) => format!(
+                    "{} ({})",
+                    entry.state,
+                    error.chars().take(200).collect::<String>()
+                ),
+                None => entry.state,
+            }
+        ));
+    }
+
+    let pages = lines
+        .chunks(ACTION_LOGS_PAGE_SIZE)
+        .map(|chunk| format!("**Action logs of {}**\n\n{}", user.name, chunk.join("\n\n")))
+        .collect::<Vec<String>>();
+
+    let pages = pages.iter().map(|p| p.as_str()).collect::<Vec<&str>>();
+
+    poise::builtins::paginate(ctx, &pages).await?;
+
+    Ok(())
+}
+
+/// Regenerate the API token of a user, use this if their token was leaked
+#[poise::command(
+    category = "Staff",
+    prefix_command,
+    slash_command,
+    check = "checks::staff_server"
+)]
+pub async fn resetapitoken(
+    ctx: Context<'_>,
+    #[description = "The user whose API token should be reset"] user: User,
+) -> Result<(), Error> {
+    let data = ctx.data();
+
+    let user_perms = get_user_perms(&data.pool, &ctx.author().id.to_string())
+        .await?
+        .resolve();
+
+    if !perms::has_perm(&user_perms, &"arcadia.reset_api_token".into()) {
+        return Err(
+            "You do not have permission to reset API tokens [arcadia.reset_api_token]".into(),
+        );
+    }
+
+    let token = botox::crypto::gen_random(512);
+
+    let res = sqlx::query!(
+        "UPDATE users SET api_token = $1 WHERE user_id = $2",
+        token,
+        user.id.to_string()
+    )
+    .execute(&data.pool)
+    .await?;
+
+    if res.rows_affected() == 0 {
+        return Err("This user does not exist on the list".into());
+    }
+
+    // The old token is already invalid, so a failed DM should not fail the command
+    let dm_sent = user
+        .id
+        .direct_message(
+            ctx.http(),
+            CreateMessage::new().embed(
+                CreateEmbed::new()
+                    .title("API Token Reset")
+                    .description(format!(
+                        "Your API token has been reset by staff. Your new API token is:\n\n||{}||\n\nNever share this token with anyone",
+                        token
+                    ))
+                    .color(0xFFA500),
+            ),
+        )
+        .await
+        .is_ok();
+
+    crate::config::CONFIG
+        .channels
+        .staff_logs
+        .send_message(
+            ctx.http(),
+            CreateMessage::new().embed(
+                CreateEmbed::new()
+                    .title("API Token Reset")
+                    .description(format!(
+                        "{} reset the API token of {}",
+                        ctx.author().id.mention(),
+                        user.id.mention()
+                    ))
+                    .field("DM Sent", if dm_sent { "Yes" } else { "No" }, true),
+            ),
+        )
+        .await?;
+
+    if dm_sent {
+        ctx.say("API token reset, the new token has been sent to the user")
+            .await?;
+    } else {
+        ctx.say("API token reset, but the new token could not be sent to the user as their DMs are closed. They can view it on the site instead")
+            .await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::csv_field;
+
+    #[test]
+    fn csv_field_keeps_plain_fields() {
+        assert_eq!(csv_field("1234567890"), "1234567890");
+        assert_eq!(csv_field("Head Staff Manager"), "Head Staff Manager");
+        assert_eq!(csv_field(""), "");
+    }
+
+    #[test]
+    fn csv_field_quotes_special_characters() {
+        assert_eq!(csv_field("a, b"), "\"a, b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("a\nb"), "\"a\nb\"");
+    }
+
+    #[test]
+    fn csv_field_neutralizes_formulas() {
+        assert_eq!(csv_field("=1+1"), "'=1+1");
+        assert_eq!(csv_field("+1"), "'+1");
+        assert_eq!(csv_field("-1"), "'-1");
+        assert_eq!(csv_field("@SUM(A1)"), "'@SUM(A1)");
+        assert_eq!(
+            csv_field("=HYPERLINK(\"x\", \"y\")"),
+            "\"'=HYPERLINK(\"\"x\"\", \"\"y\"\")\""
+        );
+    }
+}
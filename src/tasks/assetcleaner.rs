@@ -15,8 +15,7 @@ pub async fn asset_cleaner(ctx: &serenity::all::Context) -> Result<(), crate::Er
 
     let assets = ["avatars", "banners", "blobs"];
 
-    let cdn_scopes = crate::config::CONFIG.panel.cdn_scopes.get();
-    let Some(cdn_path) = cdn_scopes.get(&crate::config::CONFIG.panel.main_scope) else {
+    let Some(cdn_path) = crate::config::cdn_scope(&crate::config::CONFIG.panel.main_scope) else {
         return Err("No CDN scope for main scope".into());
     };
 
@@ -25,7 +24,7 @@ pub async fn asset_cleaner(ctx: &serenity::all::Context) -> Result<(), crate::Er
         for (entity_type, id_column) in &type_id_map {
             let entity_type_dir = format!("{}/{}/{}", cdn_path.path, asset, entity_type);
 
-            if let Err(e) = std::fs::metadata(&entity_type_dir) {
+            if let Err(e) = tokio::fs::metadata(&entity_type_dir).await {
                 if e.kind() != std::io::ErrorKind::NotFound {
                     error!("Could not validate '{}': {}", entity_type_dir, e);
                 }
@@ -34,11 +33,10 @@ pub async fn asset_cleaner(ctx: &serenity::all::Context) -> Result<(), crate::Er
 
             info!("Validating '{}' for entity type '{}'", asset, entity_type);
 
-            let dir = std::fs::read_dir(&entity_type_dir)?;
+            let mut dir = tokio::fs::read_dir(&entity_type_dir).await?;
 
-            for entry in dir {
-                let entry = entry?;
-                let is_dir = entry.file_type()?.is_dir();
+            while let Some(entry) = dir.next_entry().await? {
+                let is_dir = entry.file_type().await?.is_dir();
                 let file_name = entry
                     .file_name()
                     .into_string()
@@ -49,9 +47,9 @@ pub async fn asset_cleaner(ctx: &serenity::all::Context) -> Result<(), crate::Er
                     warn!("Invalid file name: {}", file_name);
 
                     if is_dir {
-                        std::fs::remove_dir_all(&file_path)?;
+                        tokio::fs::remove_dir_all(&file_path).await?;
                     } else {
-                        std::fs::remove_file(&file_path)?;
+                        tokio::fs::remove_file(&file_path).await?;
                     }
 
                     continue;
@@ -70,9 +68,9 @@ pub async fn asset_cleaner(ctx: &serenity::all::Context) -> Result<(), crate::Er
                     warn!("Found orphaned file: {}", file_path.display());
 
                     if is_dir {
-                        std::fs::remove_dir_all(&file_path)?;
+                        tokio::fs::remove_dir_all(&file_path).await?;
                     } else {
-                        std::fs::remove_file(&file_path)?;
+                        tokio::fs::remove_file(&file_path).await?;
                     }
                 }
             }
@@ -1,6 +1,12 @@
 use poise::serenity_prelude::{CreateEmbed, CreateEmbedFooter, CreateMessage};
 
-use crate::{config, impls::target_types::TargetType};
+use crate::{
+    config,
+    impls::{
+        queue_events::{self, QueueEvent},
+        target_types::TargetType,
+    },
+};
 
 // Internal struct used to send notifications on unclaimed bots
 struct AutoUnclaimNotification {
@@ -19,6 +25,7 @@ pub async fn auto_unclaim(ctx: &serenity::all::Context) -> Result<(), crate::Err
         .map_err(|e| format!("Error creating transaction: {:?}", e))?;
 
     let mut notifications = Vec::new();
+    let mut unclaimed = Vec::new();
 
     let bots = sqlx::query!(
         "SELECT bot_id, claimed_by, last_claimed FROM bots WHERE claimed_by IS NOT NULL AND NOW() - last_claimed > INTERVAL '1 hour' FOR UPDATE",
@@ -41,6 +48,7 @@ pub async fn auto_unclaim(ctx: &serenity::all::Context) -> Result<(), crate::Err
             .await
             .map_err(|e| format!("Error while unclaiming bot {}: {}", bot.bot_id, e))?;
 
+            unclaimed.push(bot.bot_id);
             continue;
         }
 
@@ -57,6 +65,7 @@ pub async fn auto_unclaim(ctx: &serenity::all::Context) -> Result<(), crate::Err
             .await
             .map_err(|e| format!("Error while unclaiming bot {}: {}", bot.bot_id, e))?;
 
+            unclaimed.push(bot.bot_id);
             continue;
         }
 
@@ -76,6 +85,8 @@ pub async fn auto_unclaim(ctx: &serenity::all::Context) -> Result<(), crate::Err
                 .await
                 .map_err(|e| format!("Error while unclaiming bot {}: {}", bot.bot_id, e))?;
 
+                unclaimed.push(bot.bot_id.clone());
+
                 notifications.push(AutoUnclaimNotification {
                     bot_id: bot.bot_id,
                     claimed_by,
@@ -89,6 +100,18 @@ pub async fn auto_unclaim(ctx: &serenity::all::Context) -> Result<(), crate::Err
         .await
         .map_err(|e| format!("Error while committing transaction: {}", e))?;
 
+    for bot_id in unclaimed {
+        queue_events::notify(
+            pool,
+            &QueueEvent {
+                bot_id,
+                r#type: "pending".to_string(),
+                claimed_by: None,
+            },
+        )
+        .await;
+    }
+
     for notification in notifications {
         // Now send message in #lounge
         let msg = CreateMessage::default()
@@ -0,0 +1,19 @@
+use log::info;
+
+pub async fn blog_publisher(ctx: &serenity::all::Context) -> Result<(), crate::Error> {
+    let data = ctx.data::<crate::Data>();
+    let pool = &data.pool;
+
+    let published = sqlx::query!(
+        "UPDATE blogs SET draft = false WHERE draft = true AND published_at IS NOT NULL AND published_at <= NOW() RETURNING slug"
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Error while publishing scheduled blog posts: {}", e))?;
+
+    for post in published {
+        info!("Published scheduled blog post {}", post.slug);
+    }
+
+    Ok(())
+}
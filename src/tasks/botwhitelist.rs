@@ -0,0 +1,59 @@
+use poise::serenity_prelude::{CreateMessage, UserId};
+use serenity::all::Mentionable;
+
+pub async fn bot_whitelist_expiry(ctx: &serenity::all::Context) -> Result<(), crate::Error> {
+    let data = ctx.data::<crate::Data>();
+    let pool = &data.pool;
+
+    let expired = sqlx::query!(
+        "DELETE FROM bot_whitelist WHERE expires_at IS NOT NULL AND expires_at <= NOW() RETURNING bot_id, user_id, reason"
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Error while removing expired bot whitelist entries: {}", e))?;
+
+    // The entries are already removed, so one bad entry or failed log message should not stop the others from being logged
+    for entry in expired {
+        log::info!("Removed expired bot whitelist entry for {}", entry.bot_id);
+
+        let bot_id = match entry.bot_id.parse::<UserId>() {
+            Ok(bot_id) => bot_id,
+            Err(e) => {
+                log::warn!("Error while parsing bot id {}: {}", entry.bot_id, e);
+                continue;
+            }
+        };
+
+        let user_id = match entry.user_id.parse::<UserId>() {
+            Ok(user_id) => user_id,
+            Err(e) => {
+                log::warn!("Error while parsing user id {}: {}", entry.user_id, e);
+                continue;
+            }
+        };
+
+        if let Err(e) = crate::config::CONFIG
+            .channels
+            .mod_logs
+            .send_message(
+                &ctx.http,
+                CreateMessage::new().content(format!(
+                    "{} ({}) has been removed from the bot whitelist as its entry has expired. It was added by {} with reason: {}",
+                    bot_id.mention(),
+                    bot_id,
+                    user_id.mention(),
+                    entry.reason,
+                )),
+            )
+            .await
+        {
+            log::warn!(
+                "Error while logging expired bot whitelist entry for {}: {}",
+                bot_id,
+                e
+            );
+        }
+    }
+
+    Ok(())
+}
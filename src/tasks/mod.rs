@@ -1,10 +1,15 @@
 pub mod assetcleaner;
 pub mod autounclaim;
 pub mod bans;
+pub mod blogpublisher;
+pub mod botwhitelist;
 pub mod deletedbots;
 pub mod genericcleaner;
 pub mod japiupdate;
+pub mod partnerpurge;
 pub mod premium;
+pub mod sessionpurge;
+pub mod shopholds;
 pub mod specrolesync;
 pub mod staffresync;
 pub mod teamcleaner;
@@ -37,6 +42,20 @@ pub fn tasks() -> Vec<Task> {
             duration: std::time::Duration::from_secs(300),
             run: Box::new(move |ctx| crate::tasks::bans::bans_sync(ctx).boxed()),
         },
+        Task {
+            name: "blog_publisher",
+            description: "Publishing scheduled blog posts",
+            enabled: true,
+            duration: std::time::Duration::from_secs(60),
+            run: Box::new(move |ctx| crate::tasks::blogpublisher::blog_publisher(ctx).boxed()),
+        },
+        Task {
+            name: "bot_whitelist_expiry",
+            description: "Removing expired bot whitelist entries",
+            enabled: true,
+            duration: std::time::Duration::from_secs(300),
+            run: Box::new(move |ctx| crate::tasks::botwhitelist::bot_whitelist_expiry(ctx).boxed()),
+        },
         Task {
             name: "deleted_bots",
             description: "Cleaning up deleted bots",
@@ -51,6 +70,13 @@ pub fn tasks() -> Vec<Task> {
             duration: std::time::Duration::from_secs(400),
             run: Box::new(move |ctx| crate::tasks::genericcleaner::generic_cleaner(ctx).boxed()),
         },
+        Task {
+            name: "partner_purge",
+            description: "Permanently deleting partners past their restore window",
+            enabled: true,
+            duration: std::time::Duration::from_secs(600),
+            run: Box::new(move |ctx| crate::tasks::partnerpurge::partner_purge(ctx).boxed()),
+        },
         Task {
             name: "premium_remove",
             description: "Removing expired subscriptions",
@@ -58,6 +84,20 @@ pub fn tasks() -> Vec<Task> {
             duration: std::time::Duration::from_secs(75),
             run: Box::new(move |ctx| crate::tasks::premium::premium_remove(ctx).boxed()),
         },
+        Task {
+            name: "session_purge",
+            description: "Purging expired panel sessions",
+            enabled: true,
+            duration: std::time::Duration::from_secs(600),
+            run: Box::new(move |ctx| crate::tasks::sessionpurge::session_purge(ctx).boxed()),
+        },
+        Task {
+            name: "shop_hold_release",
+            description: "Releasing expired shop holds",
+            enabled: true,
+            duration: std::time::Duration::from_secs(300),
+            run: Box::new(move |ctx| crate::tasks::shopholds::shop_hold_release(ctx).boxed()),
+        },
         Task {
             name: "spec_role_sync",
             description: "Syncing special roles",
@@ -0,0 +1,53 @@
+use crate::impls::utils::partner_image_path;
+use log::{info, warn};
+use sqlx::PgPool;
+
+/// Permanently deletes partners whose restore window has passed, returning the IDs of the deleted partners
+pub async fn purge_deleted_partners(pool: &PgPool) -> Result<Vec<String>, crate::Error> {
+    let window = crate::config::CONFIG.panel.partner_restore_window as i32;
+
+    let partners = sqlx::query!(
+        "DELETE FROM partners WHERE deleted_at IS NOT NULL AND deleted_at + make_interval(hours => $1) < NOW() RETURNING id",
+        window
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut ids = Vec::new();
+
+    for partner in partners {
+        // The partner is already gone at this point, so a leftover image should not stop the others from being purged
+        let path = match partner_image_path(&partner.id, true) {
+            Ok(path) => path,
+            Err(e) => {
+                warn!("Failed to find trashed image of {}: {}", partner.id, e);
+                ids.push(partner.id);
+                continue;
+            }
+        };
+
+        if let Err(e) = tokio::fs::remove_file(&path).await {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                warn!("Failed to delete trashed image {}: {}", path, e);
+            }
+        }
+
+        ids.push(partner.id);
+    }
+
+    Ok(ids)
+}
+
+pub async fn partner_purge(ctx: &serenity::all::Context) -> Result<(), crate::Error> {
+    let data = ctx.data::<crate::Data>();
+
+    let purged = purge_deleted_partners(&data.pool)
+        .await
+        .map_err(|e| format!("Error while purging deleted partners: {}", e))?;
+
+    for id in purged {
+        info!("Permanently deleted partner {}", id);
+    }
+
+    Ok(())
+}
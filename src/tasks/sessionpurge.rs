@@ -0,0 +1,43 @@
+use log::info;
+use sqlx::PgPool;
+
+/// Deletes expired panel sessions and sessions of users who are no longer staff
+///
+/// Returns the number of expired sessions and the number of sessions of former staff removed
+pub async fn purge_expired_sessions(pool: &PgPool) -> Result<(u64, u64), sqlx::Error> {
+    // Same expiry rules as check_auth, which only applies them when someone logs in
+    let expired = sqlx::query!(
+        "DELETE FROM staffpanel__authchain WHERE created_at < NOW() - INTERVAL '1 hour' OR (state = 'pending' AND created_at < NOW() - INTERVAL '5 minutes')"
+    )
+    .execute(pool)
+    .await?
+    .rows_affected();
+
+    let former_staff = sqlx::query!(
+        "DELETE FROM staffpanel__authchain WHERE NOT EXISTS (
+            SELECT 1 FROM staff_members WHERE staff_members.user_id = staffpanel__authchain.user_id AND cardinality(staff_members.positions) > 0
+        )"
+    )
+    .execute(pool)
+    .await?
+    .rows_affected();
+
+    Ok((expired, former_staff))
+}
+
+pub async fn session_purge(ctx: &serenity::all::Context) -> Result<(), crate::Error> {
+    let data = ctx.data::<crate::Data>();
+
+    let (expired, former_staff) = purge_expired_sessions(&data.pool)
+        .await
+        .map_err(|e| format!("Error while purging panel sessions: {}", e))?;
+
+    if expired > 0 || former_staff > 0 {
+        info!(
+            "Purged {} expired panel sessions and {} sessions of former staff",
+            expired, former_staff
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,29 @@
+use log::info;
+use sqlx::PgPool;
+
+/// Releases (deletes) all shop holds whose duration has passed, returning the number released
+///
+/// Holds without a duration never expire. This is idempotent, already released holds are simply gone
+pub async fn release_expired_shop_holds(pool: &PgPool) -> Result<u64, sqlx::Error> {
+    let res = sqlx::query!(
+        "DELETE FROM shop_holds WHERE duration IS NOT NULL AND created_at + duration < NOW()"
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(res.rows_affected())
+}
+
+pub async fn shop_hold_release(ctx: &serenity::all::Context) -> Result<(), crate::Error> {
+    let data = ctx.data::<crate::Data>();
+
+    let released = release_expired_shop_holds(&data.pool)
+        .await
+        .map_err(|e| format!("Error while releasing expired shop holds: {}", e))?;
+
+    if released > 0 {
+        info!("Released {} expired shop holds", released);
+    }
+
+    Ok(())
+}
@@ -387,6 +387,7 @@ pub async fn claim(
         cache_http: botox::cache::CacheHttpImpl::from_ctx(ctx.serenity_context()),
         user_id: ctx.author().id.to_string(),
         target_type: TargetType::Bot,
+        confirmation_token: None,
     })
     .await?;
 
@@ -427,6 +428,7 @@ pub async fn unclaim(
         cache_http: botox::cache::CacheHttpImpl::from_ctx(ctx.serenity_context()),
         user_id: ctx.author().id.to_string(),
         target_type: TargetType::Bot,
+        confirmation_token: None,
     })
     .await?;
 
@@ -467,6 +469,7 @@ pub async fn approve(
         cache_http: botox::cache::CacheHttpImpl::from_ctx(ctx.serenity_context()),
         user_id: ctx.author().id.to_string(),
         target_type: TargetType::Bot,
+        confirmation_token: None,
     })
     .await?;
 
@@ -512,6 +515,7 @@ pub async fn deny(
         cache_http: botox::cache::CacheHttpImpl::from_ctx(ctx.serenity_context()),
         user_id: ctx.author().id.to_string(),
         target_type: TargetType::Bot,
+        confirmation_token: None,
     })
     .await?;
 